@@ -187,6 +187,8 @@ struct Config {
     tls_built_in_certs_native: bool,
     #[cfg(feature = "__rustls")]
     crls: Vec<CertificateRevocationList>,
+    #[cfg(feature = "__rustls")]
+    server_cert_check: Option<crate::tls::ServerCertCheck>,
     #[cfg(feature = "__tls")]
     min_tls_version: Option<tls::Version>,
     #[cfg(feature = "__tls")]
@@ -312,6 +314,8 @@ impl ClientBuilder {
                 identity: None,
                 #[cfg(feature = "__rustls")]
                 crls: vec![],
+                #[cfg(feature = "__rustls")]
+                server_cert_check: None,
                 #[cfg(feature = "__tls")]
                 min_tls_version: None,
                 #[cfg(feature = "__tls")]
@@ -772,6 +776,24 @@ impl ClientBuilder {
                                 root_cert_store,
                                 signature_algorithms,
                             )))
+                    } else if let Some(check) = config.server_cert_check {
+                        let crls = config
+                            .crls
+                            .iter()
+                            .map(|e| e.as_rustls_crl())
+                            .collect::<Vec<_>>();
+                        let verifier = rustls::client::WebPkiServerVerifier::builder_with_provider(
+                            Arc::new(root_cert_store),
+                            provider,
+                        )
+                        .with_crls(crls)
+                        .build()
+                        .map_err(|_| crate::error::builder("invalid TLS verification settings"))?;
+                        config_builder
+                            .dangerous()
+                            .with_custom_certificate_verifier(Arc::new(
+                                crate::tls::CheckedVerifier::new(verifier, check),
+                            ))
                     } else {
                         if config.crls.is_empty() {
                             config_builder.with_root_certificates(root_cert_store)
@@ -1766,6 +1788,25 @@ impl ClientBuilder {
         self
     }
 
+    /// Add an extra check on the leaf certificate of the server.
+    ///
+    /// The check runs after the standard certificate verification succeeded,
+    /// and the handshake fails if it returns `false`. Useful for certificate
+    /// pinning.
+    ///
+    /// # Optional
+    ///
+    /// This requires the `rustls-tls(-...)` Cargo feature enabled.
+    #[cfg(feature = "__rustls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+    pub fn tls_server_cert_check<F>(mut self, check: F) -> ClientBuilder
+    where
+        F: Fn(&str, &[u8]) -> bool + Send + Sync + 'static,
+    {
+        self.config.server_cert_check = Some(Arc::new(check));
+        self
+    }
+
     /// Controls the use of built-in/preloaded certificates during certificate validation.
     ///
     /// Defaults to `true` -- built-in system certs will be used.
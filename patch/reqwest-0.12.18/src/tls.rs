@@ -730,6 +730,91 @@ impl ServerCertVerifier for IgnoreHostname {
     }
 }
 
+/// Additional check applied to the leaf certificate after the standard
+/// WebPKI verification succeeded.
+///
+/// Receives the server name and the DER encoded leaf certificate, and returns
+/// whether the certificate is accepted.
+#[cfg(feature = "__rustls")]
+pub type ServerCertCheck = std::sync::Arc<dyn Fn(&str, &[u8]) -> bool + Send + Sync>;
+
+#[cfg(feature = "__rustls")]
+pub(crate) struct CheckedVerifier {
+    inner: std::sync::Arc<rustls::client::WebPkiServerVerifier>,
+    check: ServerCertCheck,
+}
+
+#[cfg(feature = "__rustls")]
+impl CheckedVerifier {
+    pub(crate) fn new(
+        inner: std::sync::Arc<rustls::client::WebPkiServerVerifier>,
+        check: ServerCertCheck,
+    ) -> Self {
+        Self { inner, check }
+    }
+}
+
+#[cfg(feature = "__rustls")]
+impl fmt::Debug for CheckedVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CheckedVerifier").finish()
+    }
+}
+
+#[cfg(feature = "__rustls")]
+impl ServerCertVerifier for CheckedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls_pki_types::CertificateDer<'_>,
+        intermediates: &[rustls_pki_types::CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, TLSError> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        let name = server_name.to_str();
+        if (self.check)(&name, end_entity.as_ref()) {
+            Ok(verified)
+        } else {
+            Err(TLSError::General(
+                "server certificate rejected by check".into(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls_pki_types::CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TLSError> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls_pki_types::CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TLSError> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+
+    fn request_ocsp_response(&self) -> bool {
+        self.inner.request_ocsp_response()
+    }
+}
+
 /// Hyper extension carrying extra TLS layer information.
 /// Made available to clients on responses when `tls_info` is set.
 #[derive(Clone)]
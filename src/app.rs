@@ -1,5 +1,7 @@
 pub mod config;
 pub mod constant;
+pub mod jobs;
 pub mod lazy;
 pub mod model;
+pub mod settings;
 // pub mod rule;
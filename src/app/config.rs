@@ -1,9 +1,16 @@
 use std::borrow::Cow;
 
-use super::{constant::AUTHORIZATION_BEARER_PREFIX, lazy::AUTH_TOKEN, model::AppConfig};
-use crate::common::model::{
-    ApiStatus, GenericError,
-    config::{ConfigData, ConfigResponse, ConfigUpdateRequest},
+use super::{
+    constant::AUTHORIZATION_BEARER_PREFIX,
+    model::{AppConfig, admin::Permission, redaction},
+    settings::{self, ReloadReport},
+};
+use crate::{
+    common::model::{
+        ApiStatus, GenericError,
+        config::{ConfigData, ConfigResponse, ConfigUpdateRequest},
+    },
+    core::middleware::check_admin,
 };
 use axum::{
     Json,
@@ -34,7 +41,7 @@ macro_rules! handle_resets {
 
 pub async fn handle_config_update(
     headers: HeaderMap,
-    Json(request): Json<ConfigUpdateRequest>,
+    Json(mut request): Json<ConfigUpdateRequest>,
 ) -> Result<Json<ConfigResponse>, (StatusCode, Json<GenericError>)> {
     let auth_header = headers
         .get(AUTHORIZATION)
@@ -50,18 +57,25 @@ pub async fn handle_config_update(
             }),
         ))?;
 
-    if auth_header != *AUTH_TOKEN {
+    if let Err(status) = check_admin(auth_header, Permission::ManageConfig) {
         return Err((
-            StatusCode::UNAUTHORIZED,
+            status,
             Json(GenericError {
                 status: ApiStatus::Error,
-                code: Some(401),
-                error: Some(Cow::Borrowed("无效的认证令牌")),
+                code: Some(status.as_u16()),
+                error: Some(Cow::Borrowed(if status == StatusCode::FORBIDDEN {
+                    "权限不足"
+                } else {
+                    "无效的认证令牌"
+                })),
                 message: None,
             }),
         ));
     }
 
+    // 保留策略随配置保存，重启后仍然有效
+    let retention_changed = request.log_retention.is_some();
+
     match request.action.as_str() {
         "get" => Ok(Json(ConfigResponse {
             status: ApiStatus::Success,
@@ -75,6 +89,8 @@ pub async fn handle_config_update(
                 share_token: AppConfig::get_share_token(),
                 include_web_references: AppConfig::get_web_refs(),
                 fetch_raw_models: AppConfig::get_fetch_models(),
+                log_retention: AppConfig::get_log_retention(),
+                redaction: redaction::get(),
             }),
             message: None,
         })),
@@ -96,6 +112,21 @@ pub async fn handle_config_update(
                 ));
             }
 
+            // 脱敏规则需先校验
+            if let Some(settings) = request.redaction.take()
+                && let Err(e) = redaction::update(settings)
+            {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(GenericError {
+                        status: ApiStatus::Error,
+                        code: Some(400),
+                        error: Some(Cow::Borrowed("更新脱敏规则失败: 无效的正则表达式")),
+                        message: Some(Cow::Owned(e.to_string())),
+                    }),
+                ));
+            }
+
             handle_updates!(request,
                 vision_ability => AppConfig::update_vision_ability,
                 enable_slow_pool => AppConfig::update_slow_pool,
@@ -105,7 +136,11 @@ pub async fn handle_config_update(
                 share_token => AppConfig::update_share_token,
                 include_web_references => AppConfig::update_web_refs,
                 fetch_raw_models => AppConfig::update_fetch_models,
+                log_retention => AppConfig::update_log_retention,
             );
+            if retention_changed {
+                save_config()?;
+            }
 
             Ok(Json(ConfigResponse {
                 status: ApiStatus::Success,
@@ -137,7 +172,12 @@ pub async fn handle_config_update(
                 share_token => AppConfig::reset_share_token,
                 include_web_references => AppConfig::reset_web_refs,
                 fetch_raw_models => AppConfig::reset_fetch_models,
+                log_retention => AppConfig::reset_log_retention,
+                redaction => redaction::reset,
             );
+            if retention_changed {
+                save_config()?;
+            }
 
             Ok(Json(ConfigResponse {
                 status: ApiStatus::Success,
@@ -157,3 +197,98 @@ pub async fn handle_config_update(
         )),
     }
 }
+
+/// 保存随配置持久化的设置
+fn save_config() -> Result<(), (StatusCode, Json<GenericError>)> {
+    AppConfig::save().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: Some(500),
+                error: Some(Cow::Borrowed("保存配置失败")),
+                message: Some(Cow::Owned(e.to_string())),
+            }),
+        )
+    })
+}
+
+#[derive(::serde::Serialize)]
+pub struct ConfigReloadResponse {
+    status: ApiStatus,
+    #[serde(flatten)]
+    report: ReloadReport,
+}
+
+/// 重新读取 .env 文件与配置文件，支持热重载的设置项立即生效
+pub async fn handle_config_reload()
+-> Result<Json<ConfigReloadResponse>, (StatusCode, Json<GenericError>)> {
+    match settings::reload() {
+        Ok(report) => Ok(Json(ConfigReloadResponse {
+            status: ApiStatus::Success,
+            report,
+        })),
+        Err(errors) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: Some(400),
+                error: Some(Cow::Borrowed("重新加载配置失败")),
+                message: Some(Cow::Owned(errors.join("; "))),
+            }),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::model::LogRetention;
+
+    async fn request(action: &str, body: serde_json::Value) -> ConfigResponse {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            unsafe { std::env::set_var("AUTH_TOKEN", "config-test") };
+            AppConfig::__init_default();
+        });
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, "Bearer config-test".parse().unwrap());
+        let mut body = body;
+        body["action"] = action.into();
+        let request = serde_json::from_value(body).unwrap();
+        match handle_config_update(headers, Json(request)).await {
+            Ok(Json(response)) => response,
+            Err((status, _)) => panic!("{action}: {status}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_log_retention_update() {
+        let retention = LogRetention {
+            max_age: 86400,
+            success_max_age: 0,
+            failure_max_age: 604800,
+            content_max_age: 3600,
+            max_bytes: 1 << 20,
+        };
+        request(
+            "update",
+            serde_json::json!({ "log_retention": serde_json::to_value(retention).unwrap() }),
+        )
+        .await;
+        let data = request("get", serde_json::json!({})).await.data.unwrap();
+        assert!(data.log_retention == retention);
+
+        // 随配置保存，重新加载后仍然有效
+        let persisted = AppConfig::from_bytes(&AppConfig::to_bytes().unwrap()).unwrap();
+        AppConfig::reset_log_retention();
+        AppConfig::set_persisted(persisted);
+        assert!(AppConfig::get_log_retention() == retention);
+
+        // 重置后恢复为环境变量中的值
+        request("reset", serde_json::json!({ "log_retention": {} })).await;
+        let data = request("get", serde_json::json!({})).await.data.unwrap();
+        assert!(data.log_retention == LogRetention::default());
+    }
+}
@@ -100,6 +100,10 @@ def_pub_const!(
     ROUTE_LOGS_PATH => "/logs",
     ROUTE_LOGS_GET_PATH => "/logs/get",
     ROUTE_LOGS_TOKENS_GET_PATH => "/logs/tokens/get",
+    ROUTE_LOGS_ANALYTICS_PATH => "/logs/analytics",
+    ROUTE_LOGS_EXPORT_PATH => "/logs/export",
+    ROUTE_LOGS_TAIL_PATH => "/logs/tail",
+    ROUTE_LOGS_REPLAY_PATH => "/logs/replay",
     ROUTE_CONFIG_PATH => "/config",
     ROUTE_TOKENS_PATH => "/tokens",
     ROUTE_TOKENS_GET_PATH => "/tokens/get",
@@ -112,13 +116,32 @@ def_pub_const!(
     ROUTE_TOKENS_REFRESH_PATH => "/tokens/refresh",
     ROUTE_TOKENS_STATUS_SET_PATH => "/tokens/status/set",
     ROUTE_TOKENS_PROXY_SET_PATH => "/tokens/proxy/set",
+    ROUTE_TOKENS_PROXY_ASSIGN_PATH => "/tokens/proxy/assign",
     ROUTE_TOKENS_TIMEZONE_SET_PATH => "/tokens/timezone/set",
+    ROUTE_TOKENS_BULK_PATH => "/tokens/bulk",
+    ROUTE_TOKENS_EXPORT_PATH => "/tokens/export",
+    ROUTE_TOKENS_IMPORT_PATH => "/tokens/import",
     ROUTE_PROXIES_PATH => "/proxies",
     ROUTE_PROXIES_GET_PATH => "/proxies/get",
     ROUTE_PROXIES_SET_PATH => "/proxies/set",
     ROUTE_PROXIES_ADD_PATH => "/proxies/add",
     ROUTE_PROXIES_DELETE_PATH => "/proxies/del",
     ROUTE_PROXIES_SET_GENERAL_PATH => "/proxies/set-general",
+    ROUTE_PROXY_GROUPS_GET_PATH => "/proxies/groups/get",
+    ROUTE_PROXY_GROUPS_SET_PATH => "/proxies/groups/set",
+    ROUTE_PROXY_GROUPS_DELETE_PATH => "/proxies/groups/del",
+    ROUTE_BACKUP_PATH => "/backup",
+    ROUTE_BACKUP_CREATE_PATH => "/backup/create",
+    ROUTE_BACKUP_LIST_PATH => "/backup/list",
+    ROUTE_BACKUP_RESTORE_PATH => "/backup/restore",
+    ROUTE_CONFIG_RELOAD_PATH => "/config/reload",
+    ROUTE_ADMINS_GET_PATH => "/admins/get",
+    ROUTE_ADMINS_ADD_PATH => "/admins/add",
+    ROUTE_ADMINS_SET_PATH => "/admins/set",
+    ROUTE_ADMINS_DELETE_PATH => "/admins/del",
+    ROUTE_AUDIT_GET_PATH => "/audit/get",
+    ROUTE_JOBS_GET_PATH => "/jobs/get",
+    ROUTE_JOBS_RUN_PATH => "/jobs/run",
     ROUTE_ENV_EXAMPLE_PATH => "/env-example",
     ROUTE_STATIC_PATH => "/static/{path}",
     ROUTE_SHARED_STYLES_PATH => "/static/shared-styles.css",
@@ -228,7 +251,7 @@ pub fn init_thinking_tags() {
             return;
         }
 
-        let tag = crate::common::utils::parse_from_env("THINKING_TAG", DEFAULT_THINKING_TAG);
+        let tag = crate::app::settings::get::<&str>("THINKING_TAG");
 
         if tag == DEFAULT_THINKING_TAG {
             return;
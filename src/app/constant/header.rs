@@ -164,7 +164,7 @@ def_content_type!(
     text_plain_utf8 => "text/plain;charset=utf-8",
     text_css_utf8 => "text/css;charset=utf-8",
     text_js_utf8 => "text/javascript;charset=utf-8",
-    text_csv_utf8 = "text/csv;charset=utf-8",
+    text_csv_utf8 => "text/csv;charset=utf-8",
     text_xml_utf8 = "text/xml;charset=utf-8",
     text_markdown_utf8 = "text/markdown;charset=utf-8",
 
@@ -221,7 +221,10 @@ def_content_type!(
     font_woff2 = "font/woff2",
 
     // 通用二进制流
-    application_octet_stream = "application/octet-stream"
+    application_octet_stream = "application/octet-stream",
+
+    // 数据流
+    application_x_ndjson => "application/x-ndjson"
 );
 
 // 文件扩展名到 HeaderValue 的映射
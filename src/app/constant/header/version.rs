@@ -19,8 +19,6 @@ use ::core::mem::MaybeUninit;
 // 定义所有常量
 crate::define_typed_constants! {
     &'static str => {
-        /// 默认的客户端版本号
-        DEFAULT_CLIENT_VERSION = "1.0.0",
         /// 环境变量名：Cursor 客户端版本
         ENV_CURSOR_CLIENT_VERSION = "CURSOR_CLIENT_VERSION",
         /// Chrome 版本信息
@@ -102,10 +100,7 @@ pub fn header_value_ua_cursor_latest() -> http::header::HeaderValue {
 pub fn initialize_cursor_version() {
     use ::core::ops::Deref as _;
 
-    let version = crate::common::utils::parse_from_env(
-        ENV_CURSOR_CLIENT_VERSION,
-        DEFAULT_CLIENT_VERSION,
-    );
+    let version = crate::app::settings::get::<&str>(ENV_CURSOR_CLIENT_VERSION);
 
     // 验证版本格式
     validate_version_string(&version);
@@ -116,7 +111,9 @@ pub fn initialize_cursor_version() {
             __cold_path!();
             __eprintln!("Error: Invalid version string for HTTP header");
             // 使用默认版本
-            http::header::HeaderValue::from_static(DEFAULT_CLIENT_VERSION)
+            http::header::HeaderValue::from_static(crate::app::settings::default_of(
+                ENV_CURSOR_CLIENT_VERSION,
+            ))
         }
     };
 
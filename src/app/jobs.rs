@@ -0,0 +1,238 @@
+//! 后台任务调度
+//!
+//! 每个任务可以按固定间隔或 cron 表达式定时执行，也可以通过 `/jobs/run` 手动触发。
+//! 执行计划、随机延迟与并发上限分别来自 `JOB_<NAME>_SCHEDULE`、`JOB_<NAME>_JITTER`
+//! 与 `JOB_<NAME>_CONCURRENCY`，修改后需要重启。
+
+mod builtin;
+mod schedule;
+
+pub use schedule::Schedule;
+
+use super::model::{AppState, DateTime};
+use crate::common::utils::parse_from_env;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+
+/// 任务的定义
+struct JobSpec {
+    name: &'static str,
+    description: &'static str,
+    /// 未设置 `JOB_<NAME>_SCHEDULE` 时使用的执行计划
+    default_schedule: fn() -> String,
+    /// 启动后是否立即执行一次
+    run_at_start: bool,
+    run: fn(Arc<AppState>) -> JobFuture,
+}
+
+/// 一次执行的结果
+#[derive(Clone, Serialize)]
+pub struct JobRun {
+    pub started_at: DateTime,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub message: String,
+    /// 是否为手动触发
+    pub manual: bool,
+}
+
+/// 任务的配置与执行记录
+#[derive(Serialize)]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// 为空表示仅手动触发
+    pub schedule: Option<String>,
+    /// 随机延迟的上限（秒）
+    pub jitter: u64,
+    pub concurrency: usize,
+    /// 正在执行的次数
+    pub running: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run: Option<DateTime>,
+    pub runs: u64,
+    pub failures: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<JobRun>,
+}
+
+#[derive(Default)]
+struct Record {
+    next_run: Option<DateTime>,
+    runs: u64,
+    failures: u64,
+    last_run: Option<JobRun>,
+}
+
+struct Job {
+    spec: &'static JobSpec,
+    schedule: Option<Schedule>,
+    jitter: Duration,
+    concurrency: usize,
+    permits: Arc<Semaphore>,
+    record: Mutex<Record>,
+}
+
+struct Scheduler {
+    state: Arc<AppState>,
+    jobs: Box<[Job]>,
+}
+
+static SCHEDULER: OnceLock<Scheduler> = OnceLock::new();
+
+/// 手动触发失败的原因
+pub enum TriggerError {
+    NotFound,
+    /// 已达到并发上限
+    Busy,
+}
+
+impl Job {
+    fn from_env(spec: &'static JobSpec) -> Self {
+        let key = |suffix: &str| format!("JOB_{}_{suffix}", spec.name.to_ascii_uppercase());
+
+        let schedule_key = key("SCHEDULE");
+        let schedule = parse_from_env(&schedule_key, crate::app::constant::EMPTY_STRING);
+        let schedule = if schedule.trim().is_empty() {
+            Schedule::parse(&(spec.default_schedule)())
+        } else {
+            Schedule::parse(&schedule)
+        }
+        .unwrap_or_else(|e| {
+            eprintln!("{schedule_key} 无效，任务 {} 仅可手动触发: {e}", spec.name);
+            None
+        });
+        let concurrency = parse_from_env(&key("CONCURRENCY"), 1usize).max(1);
+
+        Self {
+            spec,
+            schedule,
+            jitter: Duration::from_secs(parse_from_env(&key("JITTER"), 0usize) as u64),
+            concurrency,
+            permits: Arc::new(Semaphore::new(concurrency)),
+            record: Mutex::new(Record::default()),
+        }
+    }
+
+    /// 按执行计划循环执行，上一次未结束或已达到并发上限时跳过本次
+    async fn run_loop(&'static self, state: Arc<AppState>) {
+        let Some(schedule) = &self.schedule else {
+            return;
+        };
+        let mut first = self.spec.run_at_start;
+        loop {
+            if !first {
+                let Some(mut delay) = schedule.delay_from(&*DateTime::now()) else {
+                    eprintln!("任务 {} 没有下一次执行时间，已停止定时执行", self.spec.name);
+                    self.record.lock().next_run = None;
+                    return;
+                };
+                if !self.jitter.is_zero() {
+                    delay += Duration::from_millis(rand::random_range(
+                        0..=self.jitter.as_millis() as u64,
+                    ));
+                }
+                self.record.lock().next_run = chrono::TimeDelta::from_std(delay)
+                    .ok()
+                    .and_then(|delay| DateTime::naive_now().checked_add_signed(delay))
+                    .map(DateTime::from);
+                tokio::time::sleep(delay).await;
+            }
+            first = false;
+
+            if let Ok(permit) = self.permits.clone().try_acquire_owned() {
+                self.execute(state.clone(), false, permit).await;
+            }
+        }
+    }
+
+    async fn execute(&self, state: Arc<AppState>, manual: bool, permit: OwnedSemaphorePermit) {
+        let started_at = DateTime::now();
+        let start = Instant::now();
+        // 在独立的任务中执行，避免任务中的 panic 终止调度
+        let result = match tokio::spawn((self.spec.run)(state)).await {
+            Ok(result) => result,
+            Err(e) => Err(e.to_string()),
+        };
+        drop(permit);
+
+        let success = result.is_ok();
+        let message = result.unwrap_or_else(|e| e);
+        if !success {
+            eprintln!("任务 {} 执行失败: {message}", self.spec.name);
+        }
+        let mut record = self.record.lock();
+        record.runs += 1;
+        record.failures += !success as u64;
+        record.last_run = Some(JobRun {
+            started_at,
+            duration_ms: start.elapsed().as_millis() as u64,
+            success,
+            message,
+            manual,
+        });
+    }
+
+    fn status(&self) -> JobStatus {
+        let record = self.record.lock();
+        JobStatus {
+            name: self.spec.name,
+            description: self.spec.description,
+            schedule: self.schedule.as_ref().map(ToString::to_string),
+            jitter: self.jitter.as_secs(),
+            concurrency: self.concurrency,
+            running: self.concurrency - self.permits.available_permits(),
+            next_run: record.next_run,
+            runs: record.runs,
+            failures: record.failures,
+            last_run: record.last_run.clone(),
+        }
+    }
+}
+
+/// 读取任务配置并开始定时执行，只应调用一次
+pub fn start(state: Arc<AppState>) {
+    let scheduler = SCHEDULER.get_or_init(|| Scheduler {
+        state,
+        jobs: builtin::JOBS.iter().map(Job::from_env).collect(),
+    });
+    for job in &scheduler.jobs {
+        if job.schedule.is_some() {
+            tokio::spawn(job.run_loop(scheduler.state.clone()));
+        }
+    }
+}
+
+/// 所有任务的当前状态
+pub fn snapshot() -> Vec<JobStatus> {
+    SCHEDULER
+        .get()
+        .map(|scheduler| scheduler.jobs.iter().map(Job::status).collect())
+        .unwrap_or_default()
+}
+
+/// 立即在后台执行一次任务
+pub fn trigger(name: &str) -> Result<(), TriggerError> {
+    let scheduler = SCHEDULER.get().ok_or(TriggerError::NotFound)?;
+    let job = scheduler
+        .jobs
+        .iter()
+        .find(|job| job.spec.name == name)
+        .ok_or(TriggerError::NotFound)?;
+    let permit = job
+        .permits
+        .clone()
+        .try_acquire_owned()
+        .map_err(|_| TriggerError::Busy)?;
+    tokio::spawn(job.execute(scheduler.state.clone(), true, permit));
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! 内置任务
+
+use super::JobSpec;
+use crate::{
+    app::{
+        lazy::{BACKUP_INTERVAL, LOG_RETENTION_INTERVAL, PROXY_PROBE_INTERVAL},
+        model::{AppState, ExtToken, proxy_pool},
+    },
+    common::utils::get_token_profile,
+};
+use std::{sync::Arc, time::Duration};
+
+/// 间隔设置对应的执行计划，未启用时为 `0`
+fn interval_secs(interval: Option<Duration>) -> String {
+    interval
+        .map_or(0, |interval| interval.as_secs())
+        .to_string()
+}
+
+pub(super) static JOBS: [JobSpec; 5] = [
+    JobSpec {
+        name: "autosave",
+        description: "保存令牌、日志、代理与管理员数据",
+        default_schedule: || "600".to_string(),
+        run_at_start: false,
+        run: |state| {
+            Box::pin(async move {
+                state
+                    .save()
+                    .await
+                    .map(|()| "已保存".to_string())
+                    .map_err(|e| e.to_string())
+            })
+        },
+    },
+    JobSpec {
+        name: "profile_refresh",
+        description: "刷新已启用令牌的用户、订阅与会话信息",
+        default_schedule: || "0".to_string(),
+        run_at_start: false,
+        run: |state| Box::pin(refresh_profiles(state)),
+    },
+    JobSpec {
+        name: "log_prune",
+        description: "按保留策略清理日志",
+        default_schedule: || interval_secs(*LOG_RETENTION_INTERVAL),
+        run_at_start: true,
+        run: |state| {
+            Box::pin(async move {
+                let removed = state.apply_log_retention().await;
+                Ok(format!("已移除 {removed} 条日志"))
+            })
+        },
+    },
+    JobSpec {
+        name: "proxy_probe",
+        description: "探测代理的可用性",
+        default_schedule: || interval_secs(*PROXY_PROBE_INTERVAL),
+        run_at_start: true,
+        run: |_| {
+            Box::pin(async {
+                proxy_pool::health::probe_all().await;
+                Ok("已完成".to_string())
+            })
+        },
+    },
+    JobSpec {
+        name: "backup",
+        description: "在数据目录中创建备份",
+        default_schedule: || interval_secs(*BACKUP_INTERVAL),
+        run_at_start: false,
+        run: |state| {
+            Box::pin(async move {
+                state
+                    .backup_to_disk()
+                    .await
+                    .map(|name| format!("已创建 {name}"))
+                    .map_err(|e| e.to_string())
+            })
+        },
+    },
+];
+
+/// 在不持有写锁的情况下获取资料，期间被删除或替换的令牌会被跳过
+async fn refresh_profiles(state: Arc<AppState>) -> Result<String, String> {
+    let bundles: Vec<(usize, ExtToken)> = state
+        .token_manager
+        .read()
+        .await
+        .tokens()
+        .iter()
+        .enumerate()
+        .filter_map(|(id, info)| {
+            let info = info.as_ref().filter(|info| info.is_enabled())?;
+            Some((id, info.bundle.clone()))
+        })
+        .collect();
+
+    let mut profiles = Vec::with_capacity(bundles.len());
+    for (id, bundle) in bundles {
+        let profile = get_token_profile(
+            bundle.get_client(),
+            &bundle.primary_token,
+            bundle.secondary_token.as_ref(),
+            true,
+            true,
+            true,
+        )
+        .await;
+        profiles.push((id, bundle.primary_token, profile));
+    }
+
+    let mut token_manager = state.token_manager_write().await;
+    let (mut updated, mut failed) = (0usize, 0usize);
+    for (id, token, (user, stripe, sessions)) in profiles {
+        let Some(info) = token_manager
+            .tokens_mut()
+            .get_mut(id)
+            .and_then(Option::as_mut)
+            .filter(|info| info.bundle.primary_token == token)
+        else {
+            continue;
+        };
+        // 获取失败时保留原有信息
+        if user.is_none() && stripe.is_none() && sessions.is_none() {
+            failed += 1;
+            continue;
+        }
+        if user.is_some() {
+            info.bundle.user = user;
+        }
+        if stripe.is_some() {
+            info.stripe = stripe;
+        }
+        if let Some(sessions) = sessions {
+            info.sessions = sessions;
+        }
+        updated += 1;
+    }
+    if updated > 0 {
+        token_manager.save().await.map_err(|e| e.to_string())?;
+    }
+    Ok(format!("已更新 {updated} 个令牌，{failed} 个失败"))
+}
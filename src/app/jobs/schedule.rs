@@ -0,0 +1,230 @@
+//! 任务的执行计划：固定间隔（秒）或 5 段 cron 表达式
+
+use chrono::{Datelike as _, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Timelike as _};
+use std::{fmt, time::Duration};
+
+/// 向后查找匹配时刻的最大步数，足以覆盖闰年的 2 月 29 日
+const MAX_STEPS: usize = 100_000;
+
+pub enum Schedule {
+    /// 上次执行结束后等待的间隔
+    Every(Duration),
+    Cron(Cron),
+}
+
+impl Schedule {
+    /// 解析执行计划，空值或 `0` 表示不定时执行
+    pub fn parse(s: &str) -> Result<Option<Self>, String> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(None);
+        }
+        if let Ok(secs) = s.parse::<u64>() {
+            return Ok((secs != 0).then(|| Self::Every(Duration::from_secs(secs))));
+        }
+        Cron::parse(s).map(|cron| Some(Self::Cron(cron)))
+    }
+
+    /// 距下次执行的等待时间
+    pub fn delay_from<Tz: TimeZone>(&self, now: &chrono::DateTime<Tz>) -> Option<Duration> {
+        match self {
+            Self::Every(interval) => Some(*interval),
+            Self::Cron(cron) => (cron.next_after(now)? - now.clone()).to_std().ok(),
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Every(interval) => write!(f, "{}", interval.as_secs()),
+            Self::Cron(cron) => f.write_str(&cron.source),
+        }
+    }
+}
+
+/// 分 时 日 月 周，支持 `*`、`a-b`、`*/n`、`a-b/n` 与逗号分隔的列表，周日为 0 或 7
+pub struct Cron {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// 日与周均被限制时满足其一即可
+    days_any: bool,
+    weekdays_any: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|&step| step != 0)
+                    .ok_or_else(|| format!("无效的步长: {part}"))?,
+            ),
+            None => (part, 1),
+        };
+        let parse = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|v| (min..=max).contains(v))
+                .ok_or_else(|| format!("超出范围 {min}-{max}: {part}"))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse(start)?, parse(end)?),
+                // `a/n` 表示从 a 开始每隔 n
+                None if part.contains('/') => (parse(range)?, max),
+                None => {
+                    let value = parse(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("无效的范围: {part}"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl Cron {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!("应为秒数或 5 段 cron 表达式: {s}"));
+        };
+        let mut weekday_mask = parse_field(weekdays, 0, 7)?;
+        if weekday_mask & (1 << 7) != 0 {
+            weekday_mask = (weekday_mask | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            source: fields.join(" "),
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days: parse_field(days, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            weekdays: weekday_mask,
+            days_any: days == "*",
+            weekdays_any: weekdays == "*",
+        })
+    }
+
+    #[inline]
+    fn day_matches(&self, t: &NaiveDateTime) -> bool {
+        let day = self.days & (1 << t.day()) != 0;
+        let weekday = self.weekdays & (1 << t.weekday().num_days_from_sunday()) != 0;
+        if self.days_any || self.weekdays_any {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+
+    /// 严格晚于 `now` 的下一个匹配时刻，按 `now` 所在时区的本地时间计算
+    pub fn next_after<Tz: TimeZone>(
+        &self,
+        now: &chrono::DateTime<Tz>,
+    ) -> Option<chrono::DateTime<Tz>> {
+        let tz = now.timezone();
+        let mut t = now
+            .naive_local()
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(TimeDelta::minutes(1))?;
+
+        for _ in 0..MAX_STEPS {
+            if self.months & (1 << t.month()) == 0 {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.day_matches(&t) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if self.hours & (1 << t.hour()) == 0 {
+                t = t.with_minute(0)?.checked_add_signed(TimeDelta::hours(1))?;
+                continue;
+            }
+            if self.minutes & (1 << t.minute()) == 0 {
+                t = t.checked_add_signed(TimeDelta::minutes(1))?;
+                continue;
+            }
+            // 跳过夏令时造成的不存在或已经过去的本地时间
+            match tz.from_local_datetime(&t).earliest() {
+                Some(next) if next > *now => return Some(next),
+                _ => t = t.checked_add_signed(TimeDelta::minutes(1))?,
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(s: &str) -> chrono::DateTime<Utc> { s.parse().unwrap() }
+
+    #[test]
+    fn test_cron_next_after() {
+        let next = |expr: &str, now: &str| Cron::parse(expr).unwrap().next_after(&at(now)).unwrap();
+
+        assert_eq!(
+            next("*/15 * * * *", "2025-01-01T10:07:30Z"),
+            at("2025-01-01T10:15:00Z")
+        );
+        assert_eq!(
+            next("30 2 * * *", "2025-01-01T02:30:00Z"),
+            at("2025-01-02T02:30:00Z")
+        );
+        // 日与周均被限制时满足其一即可
+        assert_eq!(
+            next("0 0 13 * 5", "2025-06-01T00:00:00Z"),
+            at("2025-06-06T00:00:00Z")
+        );
+        assert_eq!(
+            next("0 0 * * 7", "2025-06-02T00:00:00Z"),
+            at("2025-06-08T00:00:00Z")
+        );
+        assert_eq!(
+            next("0 12 29 2 *", "2025-03-01T00:00:00Z"),
+            at("2028-02-29T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_schedule_parse() {
+        assert!(Schedule::parse("").unwrap().is_none());
+        assert!(Schedule::parse("0").unwrap().is_none());
+        assert!(matches!(
+            Schedule::parse("300").unwrap(),
+            Some(Schedule::Every(d)) if d == Duration::from_secs(300)
+        ));
+        assert_eq!(
+            Schedule::parse(" 0  3 * * 1-5 ")
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "0 3 * * 1-5"
+        );
+        assert!(Schedule::parse("60 * * * *").is_err());
+        assert!(Schedule::parse("*/0 * * * *").is_err());
+        assert!(Schedule::parse("* * *").is_err());
+    }
+}
@@ -1,9 +1,10 @@
 pub mod log;
 
+use ::arc_swap::{ArcSwap, Guard};
 use ::std::{
     borrow::Cow,
     path::PathBuf,
-    sync::{LazyLock, OnceLock},
+    sync::{Arc, LazyLock, OnceLock},
 };
 
 use super::{
@@ -11,7 +12,11 @@ use super::{
         CURSOR_API2_HOST, CURSOR_API4_HOST, CURSOR_GCPP_ASIA_HOST, CURSOR_GCPP_EU_HOST,
         CURSOR_GCPP_US_HOST, CURSOR_HOST, EMPTY_STRING, HTTPS_PREFIX,
     },
-    model::{DateTime, GcppHost},
+    model::{
+        DateTime, GcppHost,
+        access::{AccessList, IpList},
+    },
+    settings,
 };
 use crate::common::utils::parse_from_env;
 
@@ -21,14 +26,48 @@ macro_rules! def_pub_static {
         pub static $name: LazyLock<String> = LazyLock::new(|| $value);
     };
 
-    // 环境变量版本
-    ($name:ident,env: $env_key:expr,default: $default:expr) => {
+    // 环境变量版本，默认值见设置表
+    ($name:ident,env: $env_key:expr) => {
         pub static $name: LazyLock<Cow<'static, str>> =
-            LazyLock::new(|| parse_from_env($env_key, $default));
+            LazyLock::new(|| settings::get::<&str>($env_key));
     };
 }
 
-def_pub_static!(AUTH_TOKEN, env: "AUTH_TOKEN", default: EMPTY_STRING);
+/// 可热重载的设置，首次读取时从环境变量加载
+pub struct Reloadable<T: 'static> {
+    current: OnceLock<ArcSwap<T>>,
+    load: fn() -> T,
+}
+
+impl<T> Reloadable<T> {
+    const fn new(load: fn() -> T) -> Self {
+        Self {
+            current: OnceLock::new(),
+            load,
+        }
+    }
+
+    #[inline]
+    fn current(&self) -> &ArcSwap<T> {
+        self.current
+            .get_or_init(|| ArcSwap::from_pointee((self.load)()))
+    }
+
+    /// 当前值的快照
+    #[inline]
+    pub fn load(&self) -> Guard<Arc<T>> { self.current().load() }
+
+    /// 从环境变量重新加载
+    pub fn reload(&self) { self.current().store(Arc::new((self.load)())) }
+}
+
+impl<T: Copy> Reloadable<T> {
+    #[inline]
+    pub fn get(&self) -> T { **self.load() }
+}
+
+pub static AUTH_TOKEN: Reloadable<String> =
+    Reloadable::new(|| parse_from_env("AUTH_TOKEN", EMPTY_STRING).into_owned());
 
 static START_TIME: OnceLock<chrono::NaiveDateTime> = OnceLock::new();
 
@@ -37,6 +76,17 @@ pub fn get_start_time() -> &'static chrono::NaiveDateTime {
     START_TIME.get_or_init(DateTime::naive_now)
 }
 
+/// 所有路由的前缀，为空或以 `/` 开头且不以 `/` 结尾
+pub static ROUTE_PREFIX: LazyLock<String> = LazyLock::new(|| {
+    let prefix = parse_from_env("ROUTE_PREFIX", EMPTY_STRING);
+    let prefix = prefix.trim().trim_end_matches('/');
+    if prefix.is_empty() || prefix.starts_with('/') {
+        prefix.to_string()
+    } else {
+        format!("/{prefix}")
+    }
+});
+
 pub static GENERAL_TIMEZONE: LazyLock<chrono_tz::Tz> = LazyLock::new(|| {
     use std::str::FromStr as _;
     let tz = parse_from_env("GENERAL_TIMEZONE", EMPTY_STRING);
@@ -55,7 +105,7 @@ pub static GENERAL_TIMEZONE: LazyLock<chrono_tz::Tz> = LazyLock::new(|| {
     }
 });
 
-def_pub_static!(DEFAULT_INSTRUCTIONS, env: "DEFAULT_INSTRUCTIONS", default: "Respond in Chinese by default\n<|END_USER|>\n\n<|BEGIN_ASSISTANT|>\n\n\nYour will\n<|END_ASSISTANT|>\n\n<|BEGIN_USER|>\n\n\nThe current date is {{currentDateTime}}");
+def_pub_static!(DEFAULT_INSTRUCTIONS, env: "DEFAULT_INSTRUCTIONS");
 
 pub fn get_default_instructions(now_with_tz: chrono::DateTime<chrono_tz::Tz>) -> String {
     DEFAULT_INSTRUCTIONS.replace(
@@ -82,13 +132,17 @@ pub static GENERAL_GCPP_HOST: LazyLock<GcppHost> = LazyLock::new(|| {
     }
 });
 
-def_pub_static!(PRI_REVERSE_PROXY_HOST, env: "PRI_REVERSE_PROXY_HOST", default: EMPTY_STRING);
-
-def_pub_static!(PUB_REVERSE_PROXY_HOST, env: "PUB_REVERSE_PROXY_HOST", default: EMPTY_STRING);
+/// 私有接口的反向代理主机，为空则不使用
+pub static PRI_REVERSE_PROXY_HOST: Reloadable<&'static str> = Reloadable::new(|| {
+    crate::leak::intern_static(parse_from_env("PRI_REVERSE_PROXY_HOST", EMPTY_STRING))
+});
 
-const DEFAULT_KEY_PREFIX: &str = "sk-";
+/// 公开接口的反向代理主机，为空则不使用
+pub static PUB_REVERSE_PROXY_HOST: Reloadable<&'static str> = Reloadable::new(|| {
+    crate::leak::intern_static(parse_from_env("PUB_REVERSE_PROXY_HOST", EMPTY_STRING))
+});
 
-def_pub_static!(KEY_PREFIX, env: "KEY_PREFIX", default: DEFAULT_KEY_PREFIX);
+def_pub_static!(KEY_PREFIX, env: "KEY_PREFIX");
 
 // pub static TOKEN_DELIMITER: LazyLock<char> = LazyLock::new(|| {
 //     let delimiter = parse_ascii_char_from_env("TOKEN_DELIMITER", COMMA);
@@ -113,47 +167,40 @@ def_pub_static!(KEY_PREFIX, env: "KEY_PREFIX", default: DEFAULT_KEY_PREFIX);
 //     }
 // });
 
-pub static USE_PRI_REVERSE_PROXY: LazyLock<bool> =
-    LazyLock::new(|| !PRI_REVERSE_PROXY_HOST.is_empty());
-
-pub static USE_PUB_REVERSE_PROXY: LazyLock<bool> =
-    LazyLock::new(|| !PUB_REVERSE_PROXY_HOST.is_empty());
+/// 按主机缓存的接口地址，反向代理主机变化时重新生成
+fn cursor_api_url(
+    cache: &::parking_lot::RwLock<(&'static str, &'static str)>,
+    host: &'static str,
+    path: &'static str,
+) -> &'static str {
+    let (cached_host, url) = *cache.read();
+    if !url.is_empty() && cached_host == host {
+        return url;
+    }
+    let mut url = String::with_capacity(HTTPS_PREFIX.len() + host.len() + path.len());
+    url.push_str(HTTPS_PREFIX);
+    url.push_str(host);
+    url.push_str(path);
+    let url = crate::leak::intern_static(url);
+    *cache.write() = (host, url);
+    url
+}
 
 macro_rules! def_cursor_api_url {
     // 单个API URL定义
     ($name:ident, $api_host:ident, $path:expr) => {
         #[doc = $path]
         pub fn $name(is_pri: bool) -> &'static str {
-            static URL_PRI: OnceLock<String> = OnceLock::new();
-            static URL_PUB: OnceLock<String> = OnceLock::new();
-
-            if is_pri {
-                URL_PRI.get_or_init(|| {
-                    let host = if *USE_PRI_REVERSE_PROXY {
-                        &PRI_REVERSE_PROXY_HOST
-                    } else {
-                        $api_host
-                    };
-                    let mut url = String::with_capacity(HTTPS_PREFIX.len() + host.len() + $path.len());
-                    url.push_str(HTTPS_PREFIX);
-                    url.push_str(host);
-                    url.push_str($path);
-                    url
-                })
+            static URL_PRI: ::parking_lot::RwLock<(&str, &str)> = ::parking_lot::RwLock::new(("", ""));
+            static URL_PUB: ::parking_lot::RwLock<(&str, &str)> = ::parking_lot::RwLock::new(("", ""));
+
+            let (cache, host) = if is_pri {
+                (&URL_PRI, PRI_REVERSE_PROXY_HOST.get())
             } else {
-                URL_PUB.get_or_init(|| {
-                    let host = if *USE_PUB_REVERSE_PROXY {
-                        &PUB_REVERSE_PROXY_HOST
-                    } else {
-                        $api_host
-                    };
-                    let mut url = String::with_capacity(HTTPS_PREFIX.len() + host.len() + $path.len());
-                    url.push_str(HTTPS_PREFIX);
-                    url.push_str(host);
-                    url.push_str($path);
-                    url
-                })
-            }
+                (&URL_PUB, PUB_REVERSE_PROXY_HOST.get())
+            };
+            let host = if host.is_empty() { $api_host } else { host };
+            cursor_api_url(cache, host, $path)
         }
     };
 
@@ -277,7 +324,7 @@ def_cursor_api_url!(
 );
 
 static DATA_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
-    let data_dir = parse_from_env("DATA_DIR", "data");
+    let data_dir = settings::get::<&str>("DATA_DIR");
     let path = std::env::current_exe()
         .ok()
         .and_then(|exe_path| exe_path.parent().map(|p| p.to_path_buf()))
@@ -302,28 +349,164 @@ pub(super) static TOKENS_FILE_PATH: LazyLock<PathBuf> =
 pub(super) static PROXIES_FILE_PATH: LazyLock<PathBuf> =
     LazyLock::new(|| DATA_DIR.join("proxies.bin"));
 
+pub(super) static PROXY_GROUPS_FILE_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| DATA_DIR.join("proxy_groups.bin"));
+
+pub(super) static ADMINS_FILE_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| DATA_DIR.join("admins.bin"));
+
+pub(super) static AUDIT_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| DATA_DIR.join("audit.log"));
+
+pub(super) static TOKEN_STATS_FILE_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| DATA_DIR.join("token_stats.bin"));
+
+pub(super) static BACKUPS_DIR: LazyLock<PathBuf> = LazyLock::new(|| DATA_DIR.join("backups"));
+
 // TCP 和超时相关常量
-const DEFAULT_TCP_KEEPALIVE: usize = 90;
 const MAX_TCP_KEEPALIVE: u64 = 600;
 
-pub static TCP_KEEPALIVE: LazyLock<u64> = LazyLock::new(|| {
-    let keepalive = parse_from_env("TCP_KEEPALIVE", DEFAULT_TCP_KEEPALIVE);
+pub static TCP_KEEPALIVE: Reloadable<u64> = Reloadable::new(|| {
+    let keepalive = settings::get::<usize>("TCP_KEEPALIVE");
     u64::try_from(keepalive)
         .map(|t| t.min(MAX_TCP_KEEPALIVE))
-        .unwrap_or(DEFAULT_TCP_KEEPALIVE as u64)
+        .unwrap_or(MAX_TCP_KEEPALIVE)
 });
 
-const DEFAULT_SERVICE_TIMEOUT: usize = 30;
 const MAX_SERVICE_TIMEOUT: u64 = 600;
 
-pub static SERVICE_TIMEOUT: LazyLock<u64> = LazyLock::new(|| {
-    let timeout = parse_from_env("SERVICE_TIMEOUT", DEFAULT_SERVICE_TIMEOUT);
+pub static SERVICE_TIMEOUT: Reloadable<u64> = Reloadable::new(|| {
+    let timeout = settings::get::<usize>("SERVICE_TIMEOUT");
     u64::try_from(timeout)
         .map(|t| t.min(MAX_SERVICE_TIMEOUT))
-        .unwrap_or(DEFAULT_SERVICE_TIMEOUT as u64)
+        .unwrap_or(MAX_SERVICE_TIMEOUT)
 });
 
-pub static REAL_USAGE: LazyLock<bool> = LazyLock::new(|| parse_from_env("REAL_USAGE", true));
+// 流式响应保活相关常量
+const MAX_STREAM_KEEPALIVE_INTERVAL: u64 = 300;
+
+/// 流式响应无输出时发送保活数据的间隔，为0则禁用
+pub static STREAM_KEEPALIVE_INTERVAL: Reloadable<Option<::core::time::Duration>> =
+    Reloadable::new(|| {
+        let interval = settings::get::<usize>("STREAM_KEEPALIVE_INTERVAL");
+        let interval = u64::try_from(interval)
+            .map(|t| t.min(MAX_STREAM_KEEPALIVE_INTERVAL))
+            .unwrap_or(MAX_STREAM_KEEPALIVE_INTERVAL);
+        if interval == 0 {
+            None
+        } else {
+            Some(::core::time::Duration::from_secs(interval))
+        }
+    });
+
+/// 流式响应是否在收到首个结果前立即返回响应头
+pub static STREAM_EARLY_HEADERS: LazyLock<bool> =
+    LazyLock::new(|| settings::get::<bool>("STREAM_EARLY_HEADERS"));
+
+// 代理健康检查相关常量
+const MAX_PROXY_PROBE_INTERVAL: u64 = 3600;
+
+/// 代理健康探测间隔，为0则禁用
+pub static PROXY_PROBE_INTERVAL: LazyLock<Option<::core::time::Duration>> = LazyLock::new(|| {
+    let interval = settings::get::<usize>("PROXY_PROBE_INTERVAL");
+    let interval = u64::try_from(interval)
+        .map(|t| t.min(MAX_PROXY_PROBE_INTERVAL))
+        .unwrap_or(MAX_PROXY_PROBE_INTERVAL);
+    if interval == 0 {
+        None
+    } else {
+        Some(::core::time::Duration::from_secs(interval))
+    }
+});
+
+def_pub_static!(PROXY_PROBE_URL, env: "PROXY_PROBE_URL");
+
+// 代理不可用时的备用代理名称，为空则回退到通用代理
+pub static PROXY_FALLBACK: Reloadable<String> =
+    Reloadable::new(|| parse_from_env("PROXY_FALLBACK", EMPTY_STRING).into_owned());
+
+/// 管理接口的地址允许与拒绝列表
+pub static ADMIN_ACCESS: Reloadable<AccessList> =
+    Reloadable::new(|| AccessList::from_env("ADMIN_ALLOW_CIDRS", "ADMIN_DENY_CIDRS"));
+
+/// 对话与模型等 API 接口的地址允许与拒绝列表
+pub static API_ACCESS: Reloadable<AccessList> =
+    Reloadable::new(|| AccessList::from_env("API_ALLOW_CIDRS", "API_DENY_CIDRS"));
+
+/// 可信代理，仅来自这些地址的转发头会被采用，值无效时不信任任何代理
+pub static TRUSTED_PROXIES: Reloadable<IpList> = Reloadable::new(|| {
+    IpList::from_env("TRUSTED_PROXIES").unwrap_or_else(|e| {
+        eprintln!("{e}，已不信任任何代理");
+        IpList::default()
+    })
+});
+
+// 日志保留策略相关常量
+const MAX_LOG_RETENTION_INTERVAL: u64 = 86400;
+
+/// 定期应用日志保留策略的间隔，为0则仅在保存时应用
+pub static LOG_RETENTION_INTERVAL: LazyLock<Option<::core::time::Duration>> = LazyLock::new(|| {
+    let interval = settings::get::<usize>("LOG_RETENTION_INTERVAL");
+    let interval = u64::try_from(interval)
+        .map(|t| t.min(MAX_LOG_RETENTION_INTERVAL))
+        .unwrap_or(MAX_LOG_RETENTION_INTERVAL);
+    if interval == 0 {
+        None
+    } else {
+        Some(::core::time::Duration::from_secs(interval))
+    }
+});
+
+// 定时备份相关常量
+const MIN_BACKUP_INTERVAL: u64 = 60;
+
+/// 定时备份的间隔，为0则不备份
+pub static BACKUP_INTERVAL: LazyLock<Option<::core::time::Duration>> = LazyLock::new(|| {
+    let interval = settings::get::<usize>("BACKUP_INTERVAL");
+    let interval = u64::try_from(interval).unwrap_or(u64::MAX);
+    if interval == 0 {
+        None
+    } else {
+        Some(::core::time::Duration::from_secs(
+            interval.max(MIN_BACKUP_INTERVAL),
+        ))
+    }
+});
+
+/// 数据目录中保留的备份数量，至少为1
+pub(super) static BACKUP_RETENTION: LazyLock<usize> =
+    LazyLock::new(|| settings::get::<usize>("BACKUP_RETENTION").max(1));
+
+// HTTPS 证书相关常量
+const MAX_TLS_RELOAD_INTERVAL: u64 = 3600;
+
+/// 检查证书文件变化的间隔，为0则仅在收到 SIGHUP 时重新加载
+pub static TLS_RELOAD_INTERVAL: LazyLock<Option<::core::time::Duration>> = LazyLock::new(|| {
+    let interval = settings::get::<usize>("TLS_RELOAD_INTERVAL");
+    let interval = u64::try_from(interval)
+        .map(|t| t.min(MAX_TLS_RELOAD_INTERVAL))
+        .unwrap_or(MAX_TLS_RELOAD_INTERVAL);
+    if interval == 0 {
+        None
+    } else {
+        Some(::core::time::Duration::from_secs(interval))
+    }
+});
+
+// 对话捕获相关常量
+
+/// 是否捕获聊天请求与响应，用于回放对比
+pub static CAPTURE_CONVERSATIONS: LazyLock<bool> =
+    LazyLock::new(|| settings::get::<bool>("CAPTURE_CONVERSATIONS"));
+
+/// 单次请求体超过该大小时不捕获
+pub static CAPTURE_MAX_SIZE: Reloadable<usize> =
+    Reloadable::new(|| settings::get::<usize>("CAPTURE_MAX_SIZE"));
+
+/// 捕获内容的总字节预算，超出时从最旧的捕获开始移除
+pub static CAPTURE_MAX_BYTES: Reloadable<usize> =
+    Reloadable::new(|| settings::get::<usize>("CAPTURE_MAX_BYTES"));
+
+pub static REAL_USAGE: Reloadable<bool> = Reloadable::new(|| settings::get::<bool>("REAL_USAGE"));
 
 // pub static TOKEN_VALIDITY_RANGE: LazyLock<TokenValidityRange> = LazyLock::new(|| {
 //     let short = if let Ok(Ok(validity)) = std::env::var("TOKEN_SHORT_VALIDITY")
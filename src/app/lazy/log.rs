@@ -11,7 +11,7 @@ use ::tokio::{
     task::JoinHandle,
 };
 
-use crate::{common::utils::parse_from_env, leak::manually_init::ManuallyInit};
+use crate::{app::settings, leak::manually_init::ManuallyInit};
 
 // --- 全局配置 ---
 
@@ -23,8 +23,8 @@ static DEBUG_LOG_FILE: ManuallyInit<Cow<'static, str>> = ManuallyInit::new();
 #[forbid(unused)]
 pub fn init() {
     unsafe {
-        DEBUG.init(parse_from_env("DEBUG", true));
-        DEBUG_LOG_FILE.init(parse_from_env("DEBUG_LOG_FILE", "debug.log"));
+        DEBUG.init(settings::get::<bool>("DEBUG"));
+        DEBUG_LOG_FILE.init(settings::get::<&str>("DEBUG_LOG_FILE"));
     }
 }
 
@@ -1,15 +1,22 @@
+pub mod access;
+pub mod admin;
 mod alias;
+pub mod audit;
 mod build_key;
 mod checksum;
 mod config;
+mod capture;
 mod cpp;
 mod fetch_model;
 mod hash;
 mod log;
+mod log_retention;
 mod proxy;
+pub mod redaction;
 mod state;
 mod timestamp_header;
 mod token;
+mod token_stats;
 mod usage_check;
 // mod validity_range;
 mod tz;
@@ -42,8 +49,8 @@ pub use fetch_model::FetchMode;
 pub use hash::Hash;
 pub use timestamp_header::TimestampHeader;
 pub use token::{
-    Duration as TokenDuration, Randomness, RawToken, RawTokenHelper, Subject, Token, TokenKey,
-    UserId,
+    Duration as TokenDuration, Randomness, RawToken, RawTokenHelper, Subject, SubjectError, Token,
+    TokenKey, UserId, providers,
 };
 pub use usage_check::UsageCheck;
 pub use vision_ability::VisionAbility;
@@ -53,11 +60,17 @@ pub use build_key::{
     UsageCheckModelType,
 };
 pub use proxy::{
-    ProxiesDeleteRequest, ProxiesDeleteResponse, ProxyAddRequest, ProxyInfoResponse,
-    ProxyUpdateRequest, SetGeneralProxyRequest,
+    ProxiesDeleteRequest, ProxiesDeleteResponse, ProxyAddRequest, ProxyGroupsDeleteRequest,
+    ProxyGroupsResponse, ProxyGroupsSetRequest, ProxyInfoResponse, ProxyUpdateRequest,
+    SetGeneralProxyRequest,
+};
+pub use capture::{Capture, CaptureEnabled, CaptureKind, CapturedResponse};
+pub use log_retention::LogRetention;
+pub use state::{
+    AppState, LogEvent, LogManager, PageContent, Pages, TokenError, TokenManager, backup,
 };
-pub use state::{AppState, PageContent, Pages, TokenError, TokenManager};
 // pub use validity_range::ValidityRange;
+pub use token_stats::{TokenStatsSort, TokenStatsStore, TokenStatsView, UsageRecorder};
 pub use tz::DateTime;
 
 use super::constant::{EMPTY_STRING, STATUS_FAILURE, STATUS_PENDING, STATUS_SUCCESS};
@@ -111,6 +124,9 @@ pub struct RequestLog {
     pub stream: bool,
     pub status: LogStatus,
     pub error: ErrorInfo,
+    /// 解析后的客户端地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<std::net::IpAddr>,
 }
 
 impl RequestLog {
@@ -273,6 +289,13 @@ pub enum ErrorInfo {
         error: &'static str,
         details: &'static str,
     },
+    /// 代理连接失败，与上游模型错误区分
+    Proxy {
+        proxy: &'static str,
+        error: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        details: Option<&'static str>,
+    },
 }
 
 impl ErrorInfo {
@@ -287,6 +310,15 @@ impl ErrorInfo {
         }
     }
 
+    #[inline]
+    pub fn new_proxy<S: Borrow<str>>(proxy: S, e: S) -> Self {
+        Self::Proxy {
+            proxy: crate::leak::intern_static(proxy),
+            error: crate::leak::intern_static(e),
+            details: None,
+        }
+    }
+
     #[inline]
     pub fn add_detail<S: Borrow<str>>(&mut self, detail: S) {
         match self {
@@ -303,6 +335,10 @@ impl ErrorInfo {
             ErrorInfo::Details { details, .. } => {
                 *details = crate::leak::intern_static(detail);
             }
+            // 保留代理名称
+            ErrorInfo::Proxy { details, .. } => {
+                *details = Some(crate::leak::intern_static(detail));
+            }
         }
     }
 
@@ -311,9 +347,20 @@ impl ErrorInfo {
             Self::None => false,
             Self::Error(error) => error.contains(pat),
             Self::Details { error, details } => error.contains(pat) || details.contains(pat),
+            Self::Proxy {
+                proxy,
+                error,
+                details,
+            } =>
+                proxy.contains(pat)
+                    || error.contains(pat)
+                    || details.is_some_and(|details| details.contains(pat)),
         }
     }
 
+    #[inline(always)]
+    pub const fn is_proxy(&self) -> bool { matches!(*self, Self::Proxy { .. }) }
+
     #[inline(always)]
     pub const fn is_none(&self) -> bool { matches!(*self, Self::None) }
 
@@ -333,7 +380,7 @@ pub struct ExtToken {
     pub client_key: Hash,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config_version: Option<uuid::Uuid>,
-    #[serde(skip_serializing_if = "uuid::Uuid::is_nil")]
+    #[serde(skip_serializing_if = "uuid::Uuid::is_nil", default)]
     pub session_id: uuid::Uuid,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy: Option<String>,
@@ -380,7 +427,13 @@ impl ExtToken {
 
     /// 获取适用于此 token 的 HTTP 客户端
     #[inline]
-    pub fn get_client(&self) -> Client { get_client_or_general(self.proxy.as_deref()) }
+    pub fn get_client(&self) -> Client {
+        get_client_or_general(self.proxy.as_deref(), self.sticky_hash())
+    }
+
+    /// 用于代理组粘性分配的令牌哈希
+    #[inline]
+    pub fn sticky_hash(&self) -> u64 { proxy_pool::group::sticky_hash(self.primary_token.key()) }
 
     /// 获取此 token 关联的时区
     #[inline]
@@ -418,6 +471,9 @@ pub struct TokenInfo {
     pub stripe: Option<StripeProfile>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub sessions: Vec<Session>,
+    /// 使用统计，由 `TokenStatsStore` 维护，仅在列出令牌时填充
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<TokenStatsView>,
 }
 
 #[derive(Archive, RkyvSerialize, RkyvDeserialize)]
@@ -500,6 +556,7 @@ impl TokenInfoHelper {
                 status: self.status,
                 stripe: self.stripe,
                 sessions: self.sessions,
+                stats: None,
             },
             self.alias,
         )
@@ -525,7 +582,9 @@ where
     serializer.serialize_str(&key.to_string())
 }
 
-#[derive(Default, Clone, Copy, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[derive(
+    Default, Clone, Copy, PartialEq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize,
+)]
 #[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum TokenStatus {
@@ -569,6 +628,12 @@ pub struct TokensAddRequest {
     pub tokens: Vec<TokensAddRequestTokenInfo>,
     #[serde(default)]
     pub status: TokenStatus,
+    /// 仅校验并返回诊断信息，不导入
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 校验时请求用户信息，确认令牌可用
+    #[serde(default)]
+    pub probe: bool,
 }
 
 #[derive(Deserialize)]
@@ -659,6 +724,13 @@ pub struct TokensProxySetRequest {
     pub proxy: Option<String>,
 }
 
+/// 将令牌依次轮流分配到多个代理或代理组
+#[derive(Deserialize)]
+pub struct TokensProxyAssignRequest {
+    pub aliases: Vec<String>,
+    pub proxies: Vec<String>,
+}
+
 #[derive(Deserialize)]
 pub struct TokensTimezoneSetRequest {
     pub aliases: Vec<String>,
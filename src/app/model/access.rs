@@ -0,0 +1,259 @@
+//! 客户端地址解析与按网段的访问控制
+
+use crate::{app::constant::EMPTY_STRING, common::utils::parse_from_env};
+use http::{HeaderMap, HeaderName, HeaderValue, Method};
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_REAL_IP: &str = "x-real-ip";
+
+/// 解析后的客户端地址，由中间件写入请求扩展
+#[derive(Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+/// 以逗号分隔的网段列表，单个地址视为仅包含该地址的网段
+#[derive(Default)]
+pub struct IpList(Vec<IpNet>);
+
+impl IpList {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<IpNet>()
+                    .or_else(|_| s.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| format!("无效的地址或网段: {s}"))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// 读取设置
+    pub fn from_env(key: &str) -> Result<Self, String> {
+        Self::parse(&parse_from_env(key, EMPTY_STRING)).map_err(|e| format!("{key} 无效: {e}"))
+    }
+
+    /// 包含全部地址
+    fn all() -> Self {
+        Self(vec![
+            IpNet::V4(Default::default()),
+            IpNet::V6(Default::default()),
+        ])
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    #[inline]
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.0.iter().any(|net| net.contains(&ip))
+    }
+}
+
+/// 一组路由的允许与拒绝列表
+#[derive(Default)]
+pub struct AccessList {
+    pub allow: IpList,
+    pub deny: IpList,
+}
+
+impl AccessList {
+    /// 解析允许与拒绝列表，任一列表无效时拒绝所有地址
+    pub fn parse(allow: &str, deny: &str) -> Result<Self, (String, Self)> {
+        match (IpList::parse(allow), IpList::parse(deny)) {
+            (Ok(allow), Ok(deny)) => Ok(Self { allow, deny }),
+            (Err(e), _) | (_, Err(e)) => Err((e, Self::deny_all())),
+        }
+    }
+
+    /// 读取设置，值无效时打印错误并拒绝所有地址
+    pub fn from_env(allow_key: &str, deny_key: &str) -> Self {
+        let allow = parse_from_env(allow_key, EMPTY_STRING);
+        let deny = parse_from_env(deny_key, EMPTY_STRING);
+        Self::parse(&allow, &deny).unwrap_or_else(|(e, list)| {
+            eprintln!("{allow_key} / {deny_key} 无效，已拒绝所有地址: {e}");
+            list
+        })
+    }
+
+    #[inline]
+    fn deny_all() -> Self {
+        Self {
+            allow: IpList::default(),
+            deny: IpList::all(),
+        }
+    }
+
+    /// 未设置任何网段
+    #[inline]
+    pub fn is_unrestricted(&self) -> bool { self.allow.is_empty() && self.deny.is_empty() }
+
+    /// 拒绝列表优先，允许列表为空时允许所有地址
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        !self.deny.contains(ip) && (self.allow.is_empty() || self.allow.contains(ip))
+    }
+}
+
+/// 解析客户端地址
+///
+/// 仅当直接连接的对端是可信代理时才读取 `X-Forwarded-For` 与 `X-Real-IP`。
+/// `X-Forwarded-For` 从右向左跳过可信代理，第一个不可信的地址即为客户端地址。
+pub fn resolve_client_ip(peer: IpAddr, headers: &HeaderMap, trusted: &IpList) -> IpAddr {
+    let peer = peer.to_canonical();
+    if !trusted.contains(peer) {
+        return peer;
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    if !forwarded.is_empty() {
+        let mut client = peer;
+        for hop in forwarded.into_iter().rev() {
+            // 无法解析的地址之前的内容不可信
+            let Ok(ip) = hop.parse::<IpAddr>() else { break };
+            client = ip.to_canonical();
+            if !trusted.contains(client) {
+                break;
+            }
+        }
+        return client;
+    }
+
+    headers
+        .get(X_REAL_IP)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<IpAddr>().ok())
+        .map_or(peer, |ip| ip.to_canonical())
+}
+
+/// CORS 设置项，`*` 表示任意值
+pub enum CorsList<T> {
+    Any,
+    List(Vec<T>),
+}
+
+fn parse_cors_list<T>(
+    s: &str,
+    parse: impl Fn(&str) -> Option<T>,
+    what: &str,
+) -> Result<CorsList<T>, String> {
+    let s = s.trim();
+    if s.is_empty() || s == "*" {
+        return Ok(CorsList::Any);
+    }
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|item| parse(item).ok_or_else(|| format!("无效的{what}: {item}")))
+        .collect::<Result<_, _>>()
+        .map(CorsList::List)
+}
+
+/// 允许的来源，如 `https://example.com`
+pub fn cors_origins(s: &str) -> Result<CorsList<HeaderValue>, String> {
+    parse_cors_list(
+        s,
+        |item| {
+            item.contains("://")
+                .then(|| HeaderValue::from_str(item.trim_end_matches('/')).ok())
+                .flatten()
+        },
+        "来源",
+    )
+}
+
+pub fn cors_methods(s: &str) -> Result<CorsList<Method>, String> {
+    parse_cors_list(
+        s,
+        |item| Method::from_bytes(item.to_ascii_uppercase().as_bytes()).ok(),
+        "方法",
+    )
+}
+
+pub fn cors_headers(s: &str) -> Result<CorsList<HeaderName>, String> {
+    parse_cors_list(
+        s,
+        |item| HeaderName::from_bytes(item.as_bytes()).ok(),
+        "请求头",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let trusted = IpList::parse("10.0.0.0/8, 127.0.0.1").unwrap();
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.append(*name, value.parse().unwrap());
+            }
+            headers
+        };
+
+        // 不可信的对端不读取转发头
+        let forged = headers(&[(X_FORWARDED_FOR, "1.1.1.1")]);
+        assert_eq!(
+            resolve_client_ip(ip("8.8.8.8"), &forged, &trusted),
+            ip("8.8.8.8")
+        );
+
+        // 跳过可信代理，伪造的最左侧地址被忽略
+        let chain = headers(&[
+            (X_FORWARDED_FOR, "6.6.6.6, 2.2.2.2"),
+            (X_FORWARDED_FOR, "10.0.0.2"),
+        ]);
+        assert_eq!(
+            resolve_client_ip(ip("127.0.0.1"), &chain, &trusted),
+            ip("2.2.2.2")
+        );
+
+        let real_ip = headers(&[(X_REAL_IP, "3.3.3.3")]);
+        assert_eq!(
+            resolve_client_ip(ip("::ffff:10.1.2.3"), &real_ip, &trusted),
+            ip("3.3.3.3")
+        );
+
+        let invalid = headers(&[(X_FORWARDED_FOR, "2.2.2.2, bogus, 10.0.0.2")]);
+        assert_eq!(
+            resolve_client_ip(ip("10.0.0.1"), &invalid, &trusted),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn test_access_list() {
+        let access = AccessList {
+            allow: IpList::parse("192.168.0.0/16,::1").unwrap(),
+            deny: IpList::parse("192.168.1.0/24").unwrap(),
+        };
+        assert!(access.permits("192.168.2.1".parse().unwrap()));
+        assert!(access.permits("::1".parse().unwrap()));
+        assert!(!access.permits("192.168.1.1".parse().unwrap()));
+        assert!(!access.permits("1.2.3.4".parse().unwrap()));
+        assert!(AccessList::default().permits("1.2.3.4".parse().unwrap()));
+        assert!(IpList::parse("10.0.0.0/33").is_err());
+
+        // 无效的值不能变为允许所有地址
+        for (allow, deny) in [("10.0.0.0/8, bogus", ""), ("", "10.0.0.0/33")] {
+            let Err((_, access)) = AccessList::parse(allow, deny) else {
+                panic!("{allow} / {deny}");
+            };
+            assert!(!access.is_unrestricted());
+            assert!(!access.permits("10.0.0.1".parse().unwrap()));
+            assert!(!access.permits("::ffff:8.8.8.8".parse().unwrap()));
+            assert!(!access.permits("2001:db8::1".parse().unwrap()));
+        }
+    }
+}
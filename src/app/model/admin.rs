@@ -0,0 +1,250 @@
+//! 具有角色的管理员凭据
+//!
+//! `AUTH_TOKEN` 始终具有全部权限，此处的凭据仅用于管理接口。
+//! 只保存密钥的 SHA-256 摘要，密钥在创建时返回一次。
+
+use super::{DateTime, state::storage};
+use crate::app::lazy::{ADMINS_FILE_PATH, AUTH_TOKEN};
+use ahash::HashMap;
+use arc_swap::ArcSwap;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+use std::sync::{Arc, OnceLock};
+use subtle::ConstantTimeEq as _;
+
+/// 管理员名称到凭据的映射
+static ADMINS: OnceLock<ArcSwap<HashMap<String, Admin>>> = OnceLock::new();
+
+const KEY_PREFIX: &str = "adm-";
+const KEY_RANDOM_LEN: usize = 24;
+const MAX_NAME_LEN: usize = 64;
+
+/// `AUTH_TOKEN` 在审计记录中的名称，不能用作管理员名称
+pub const ROOT_NAME: &str = "AUTH_TOKEN";
+
+/// 管理员角色
+#[derive(
+    Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Archive, RkyvDeserialize, RkyvSerialize,
+)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Role {
+    /// 只读日志
+    LogViewer,
+    /// 管理令牌与生成密钥
+    TokenOperator,
+    /// 管理配置与代理
+    ConfigAdmin,
+    /// 全部权限
+    Admin,
+}
+
+/// 管理接口所需的权限
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// `/logs/*` 的只读接口
+    ReadLogs,
+    /// `/tokens/*`
+    ManageTokens,
+    /// `/proxies/*`
+    ManageProxies,
+    /// `/config/*`
+    ManageConfig,
+    /// `/build-key`
+    BuildKey,
+    /// 备份、日志重放、审计与管理员管理
+    Full,
+}
+
+impl Role {
+    /// 角色是否具有指定权限
+    pub const fn allows(self, permission: Permission) -> bool {
+        match self {
+            Self::Admin => true,
+            Self::LogViewer => matches!(permission, Permission::ReadLogs),
+            Self::TokenOperator => matches!(
+                permission,
+                Permission::ReadLogs | Permission::ManageTokens | Permission::BuildKey
+            ),
+            Self::ConfigAdmin => matches!(
+                permission,
+                Permission::ReadLogs | Permission::ManageConfig | Permission::ManageProxies
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Archive, RkyvDeserialize, RkyvSerialize)]
+pub struct Admin {
+    pub role: Role,
+    /// 密钥的 SHA-256 摘要
+    key_hash: [u8; 32],
+    /// 毫秒时间戳
+    created_at: i64,
+}
+
+/// 对外展示的管理员信息，不含密钥
+#[derive(Serialize)]
+pub struct AdminInfo {
+    pub name: String,
+    pub role: Role,
+    pub created_at: Option<DateTime>,
+}
+
+#[inline]
+fn hash_key(key: &str) -> [u8; 32] { sha2::Sha256::digest(key.as_bytes()).into() }
+
+impl Admin {
+    /// 创建凭据，返回凭据及其密钥
+    pub fn generate(role: Role) -> (Self, String) {
+        let key = format!(
+            "{KEY_PREFIX}{}",
+            hex::encode(rand::random::<[u8; KEY_RANDOM_LEN]>())
+        );
+        let admin = Self {
+            role,
+            key_hash: hash_key(&key),
+            created_at: DateTime::utc_now().timestamp_millis(),
+        };
+        (admin, key)
+    }
+
+    fn info(&self, name: &str) -> AdminInfo {
+        AdminInfo {
+            name: name.to_string(),
+            role: self.role,
+            created_at: chrono::DateTime::from_timestamp_millis(self.created_at)
+                .map(|dt| DateTime::from_naive(&dt.naive_utc())),
+        }
+    }
+}
+
+/// 名称仅允许字母、数字与 `-_.`
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != ROOT_NAME
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+#[derive(Default, Archive, RkyvDeserialize, RkyvSerialize)]
+pub struct Admins {
+    admins: HashMap<String, Admin>,
+}
+
+impl Admins {
+    #[inline]
+    pub fn init(self) { let _ = ADMINS.set(ArcSwap::from_pointee(self.admins)); }
+
+    /// 替换全局管理员凭据
+    #[inline]
+    pub fn update_global(self) { store(self.admins) }
+
+    /// 序列化当前的管理员凭据
+    pub fn to_bytes() -> Result<::rkyv::util::AlignedVec, ::rkyv::rancor::Error> {
+        ::rkyv::to_bytes::<::rkyv::rancor::Error>(&Self { admins: snapshot() })
+    }
+
+    /// 从序列化数据恢复管理员凭据
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ::rkyv::rancor::Error> {
+        ::rkyv::from_bytes::<Self, ::rkyv::rancor::Error>(bytes)
+    }
+
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        let bytes = Self::to_bytes()?;
+        storage::write(&ADMINS_FILE_PATH, &bytes).await
+    }
+
+    pub async fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let Some(bytes) = storage::read(&ADMINS_FILE_PATH).await? else {
+            return Ok(Self::default());
+        };
+        Self::from_bytes(&bytes).map_err(Into::into)
+    }
+}
+
+#[inline]
+fn admins() -> &'static ArcSwap<HashMap<String, Admin>> {
+    ADMINS.get().expect("admins does not init")
+}
+
+/// 获取所有管理员凭据
+pub fn snapshot() -> HashMap<String, Admin> { (**admins().load()).clone() }
+
+/// 替换所有管理员凭据
+#[inline]
+pub fn store(admins: HashMap<String, Admin>) { self::admins().store(Arc::new(admins)); }
+
+/// 按名称排序的管理员列表
+pub fn list() -> Vec<AdminInfo> {
+    let admins = admins().load();
+    let mut list: Vec<_> = admins
+        .iter()
+        .map(|(name, admin)| admin.info(name))
+        .collect();
+    list.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+/// 调用方的管理员身份
+#[derive(Clone)]
+pub struct Identity {
+    pub name: String,
+    pub role: Role,
+}
+
+/// 识别管理员凭据，`AUTH_TOKEN` 视为具有全部权限的 [`ROOT_NAME`]
+///
+/// 以常数时间比较密钥的摘要，耗时与密钥内容无关。
+pub fn identify(token: &str) -> Option<Identity> {
+    let hash = hash_key(token);
+    if bool::from(hash.ct_eq(&hash_key(AUTH_TOKEN.load().as_str()))) {
+        return Some(Identity {
+            name: ROOT_NAME.to_string(),
+            role: Role::Admin,
+        });
+    }
+    find(&hash).map(|(name, role)| Identity { name, role })
+}
+
+/// 查找摘要对应的管理员，返回名称与角色
+///
+/// 与全部凭据逐一比较，不提前返回。
+fn find(hash: &[u8; 32]) -> Option<(String, Role)> {
+    let admins = admins().load();
+    let mut found = None;
+    for (name, admin) in admins.iter() {
+        if bool::from(admin.key_hash.ct_eq(hash)) {
+            found = Some((name, admin.role));
+        }
+    }
+    found.map(|(name, role)| (name.clone(), role))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_permissions() {
+        use Permission::*;
+        let all = [
+            ReadLogs,
+            ManageTokens,
+            ManageProxies,
+            ManageConfig,
+            BuildKey,
+            Full,
+        ];
+        let allowed =
+            |role: Role| -> Vec<_> { all.into_iter().filter(|p| role.allows(*p)).collect() };
+        assert!(allowed(Role::LogViewer) == [ReadLogs]);
+        assert!(allowed(Role::TokenOperator) == [ReadLogs, ManageTokens, BuildKey]);
+        assert!(allowed(Role::ConfigAdmin) == [ReadLogs, ManageProxies, ManageConfig]);
+        assert!(allowed(Role::Admin) == all);
+    }
+}
@@ -0,0 +1,251 @@
+//! 管理操作的审计记录
+//!
+//! 每条记录以一行 JSON 追加到 `audit.log`，与请求日志分开保存，
+//! 不提供修改或删除接口。备份中包含全部记录，恢复时只补回缺失的记录。
+
+use super::{DateTime, admin::Role};
+use crate::app::lazy::AUDIT_FILE_PATH;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt as _, sync::Mutex};
+
+/// 串行化追加写入，避免并发写入的行交错
+static WRITE_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// 一次管理操作
+#[derive(Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime,
+    /// 管理员名称，`AUTH_TOKEN` 记为 [`super::admin::ROOT_NAME`]
+    pub actor: String,
+    pub role: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<IpAddr>,
+    pub route: String,
+    /// 响应状态码
+    pub status: u16,
+    /// 操作对象，如令牌别名、代理名称或管理员名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<serde_json::Value>,
+}
+
+/// 审计记录的过滤条件
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct AuditQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// 反向排序，默认从旧到新
+    pub reverse: Option<bool>,
+    pub from_date: Option<DateTime>,
+    pub to_date: Option<DateTime>,
+    /// 按管理员名称精确匹配
+    pub actor: Option<String>,
+    /// 按路由前缀匹配
+    pub route: Option<String>,
+    /// 按操作对象精确匹配
+    pub target: Option<String>,
+    pub address: Option<IpAddr>,
+    /// 是否成功（2xx）
+    pub success: Option<bool>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        if let Some(from_date) = self.from_date
+            && entry.timestamp < from_date
+        {
+            return false;
+        }
+        if let Some(to_date) = self.to_date
+            && entry.timestamp > to_date
+        {
+            return false;
+        }
+        if let Some(actor) = &self.actor
+            && entry.actor != *actor
+        {
+            return false;
+        }
+        if let Some(route) = &self.route
+            && !entry.route.starts_with(route.as_str())
+        {
+            return false;
+        }
+        if let Some(target) = &self.target
+            && !entry.targets.contains(target)
+        {
+            return false;
+        }
+        if self.address.is_some() && entry.address != self.address {
+            return false;
+        }
+        if let Some(success) = self.success
+            && (200..300).contains(&entry.status) != success
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// 追加一条记录，写入失败仅打印错误
+pub async fn append(entry: &AuditEntry) {
+    let mut line = match serde_json::to_vec(entry) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("无法序列化审计记录: {e}");
+            return;
+        }
+    };
+    line.push(b'\n');
+
+    let _guard = WRITE_LOCK.lock().await;
+    let result = async {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&*AUDIT_FILE_PATH)
+            .await?;
+        file.write_all(&line).await?;
+        file.flush().await
+    }
+    .await;
+    if let Err(e) = result {
+        eprintln!("无法写入审计记录: {e}");
+    }
+}
+
+/// 读取文件内容，文件不存在时为空
+async fn read_file() -> std::io::Result<Vec<u8>> {
+    match tokio::fs::read(&*AUDIT_FILE_PATH).await {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// 可以解析的记录行及其时间
+fn entries(content: &[u8]) -> impl Iterator<Item = (&[u8], DateTime)> {
+    content
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            serde_json::from_slice::<AuditEntry>(line)
+                .ok()
+                .map(|entry| (line, entry.timestamp))
+        })
+}
+
+/// 读取全部记录的原始内容，用于备份
+pub async fn snapshot() -> std::io::Result<Vec<u8>> {
+    let _guard = WRITE_LOCK.lock().await;
+    read_file().await
+}
+
+/// 将备份中早于现有最早记录的部分补在现有记录之前，现有记录保持不变
+fn merge(backup: &[u8], current: &[u8]) -> Vec<u8> {
+    let first = entries(current).next().map(|(_, timestamp)| timestamp);
+    let mut merged = Vec::with_capacity(backup.len() + current.len());
+    for (line, _) in
+        entries(backup).filter(|&(_, timestamp)| first.is_none_or(|first| timestamp < first))
+    {
+        merged.extend_from_slice(line);
+        merged.push(b'\n');
+    }
+    merged.extend_from_slice(current);
+    merged
+}
+
+/// 从备份恢复记录
+///
+/// 记录只追加不删除，恢复不会移除或覆盖已有的记录，仅补回备份中更早的部分。
+pub async fn restore(backup: &[u8]) -> std::io::Result<()> {
+    let _guard = WRITE_LOCK.lock().await;
+    let current = read_file().await?;
+    let merged = merge(backup, &current);
+    if merged.len() == current.len() {
+        return Ok(());
+    }
+    let tmp = AUDIT_FILE_PATH.with_extension("log.tmp");
+    tokio::fs::write(&tmp, &merged).await?;
+    tokio::fs::rename(&tmp, &*AUDIT_FILE_PATH).await
+}
+
+/// 读取匹配的记录，返回总数与分页后的记录
+pub async fn query(query: &AuditQuery) -> std::io::Result<(usize, Vec<AuditEntry>)> {
+    let content = read_file().await?;
+
+    // 跳过无法解析的行，例如写入中断留下的残缺行
+    let mut entries: Vec<AuditEntry> = content
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| serde_json::from_slice(line).ok())
+        .filter(|entry| query.matches(entry))
+        .collect();
+    let total = entries.len();
+
+    if query.reverse.unwrap_or(false) {
+        entries.reverse();
+    }
+    let entries = entries
+        .into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok((total, entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(timestamp: DateTime, route: &str) -> Vec<u8> {
+        let mut line = serde_json::to_vec(&AuditEntry {
+            timestamp,
+            actor: "root".to_string(),
+            role: Role::Admin,
+            address: None,
+            route: route.to_string(),
+            status: 200,
+            targets: Vec::new(),
+            before: None,
+            after: None,
+        })
+        .unwrap();
+        line.push(b'\n');
+        line
+    }
+
+    #[test]
+    fn test_merge() {
+        static TZ_INIT: std::sync::Once = std::sync::Once::new();
+        TZ_INIT.call_once(crate::app::model::tz::__init);
+
+        let now = DateTime::naive_now();
+        let at = |secs| DateTime::from_naive(&(now + chrono::Duration::seconds(secs)));
+        let backup = [
+            line(at(0), "/a"),
+            line(at(1), "/b"),
+            b"{\"broken\n".to_vec(),
+            line(at(2), "/c"),
+        ]
+        .concat();
+        let current = [line(at(2), "/c"), line(at(3), "/restore")].concat();
+
+        // 已有记录保持不变，只补回更早的记录
+        let merged = merge(&backup, &current);
+        let routes: Vec<_> = entries(&merged)
+            .map(|(line, _)| serde_json::from_slice::<AuditEntry>(line).unwrap().route)
+            .collect();
+        assert_eq!(routes, ["/a", "/b", "/c", "/restore"]);
+        assert!(merged.ends_with(&current));
+
+        // 没有记录时恢复备份中的全部记录
+        assert_eq!(entries(&merge(&backup, &[])).count(), 3);
+    }
+}
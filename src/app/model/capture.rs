@@ -0,0 +1,121 @@
+use super::{ChainUsage, RequestLog};
+use crate::app::lazy::CAPTURE_MAX_BYTES;
+use bytes::Bytes;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// 捕获请求的来源接口
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureKind {
+    ChatCompletions,
+    Messages,
+}
+
+/// 由中间件标记需要捕获的请求，重放产生的请求不带此标记
+#[derive(Clone, Copy)]
+pub struct CaptureEnabled;
+
+/// 拼接后的完整响应
+#[derive(Serialize, Clone)]
+pub struct CapturedResponse {
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub think: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ChainUsage>,
+    pub total_time: f64,
+}
+
+impl CapturedResponse {
+    /// 从已完成的日志中提取响应
+    pub fn from_log(log: &RequestLog) -> Self {
+        let chain = log.chain.as_ref();
+        Self {
+            content: chain
+                .and_then(|c| c.delays.as_ref())
+                .map(|(text, _)| text.clone())
+                .unwrap_or_default(),
+            think: chain.and_then(|c| c.think.clone()),
+            usage: chain.and_then(|c| c.usage),
+            total_time: log.timing.total,
+        }
+    }
+
+    #[inline]
+    fn size(&self) -> usize { self.content.len() + self.think.as_ref().map_or(0, String::len) }
+}
+
+/// 单次对话的捕获记录，与日志ID一一对应
+#[derive(Clone)]
+pub struct Capture {
+    pub id: u64,
+    pub kind: CaptureKind,
+    /// 规范化后的请求，已按日志规则脱敏
+    pub request: Bytes,
+    /// 请求未完成或失败时为空
+    pub response: Option<CapturedResponse>,
+}
+
+impl Capture {
+    #[inline]
+    fn size(&self) -> usize {
+        self.request.len() + self.response.as_ref().map_or(0, CapturedResponse::size)
+    }
+}
+
+/// 按日志ID有序保存的捕获记录，受 `CAPTURE_MAX_BYTES` 限制
+#[derive(Default)]
+pub struct CaptureStore {
+    captures: VecDeque<Capture>,
+    bytes: usize,
+}
+
+impl CaptureStore {
+    #[inline]
+    fn position(&self, id: u64) -> Result<usize, usize> {
+        self.captures.binary_search_by_key(&id, |c| c.id)
+    }
+
+    /// 超出字节预算时从最旧的捕获开始移除
+    fn evict(&mut self) {
+        while self.bytes > CAPTURE_MAX_BYTES.get()
+            && let Some(capture) = self.captures.pop_front()
+        {
+            self.bytes -= capture.size();
+        }
+    }
+
+    pub fn insert(&mut self, capture: Capture) {
+        self.bytes += capture.size();
+        match self.position(capture.id) {
+            Ok(index) => {
+                let old = ::core::mem::replace(&mut self.captures[index], capture);
+                self.bytes -= old.size();
+            }
+            // 并发请求的插入顺序可能与ID顺序不同
+            Err(index) => self.captures.insert(index, capture),
+        }
+        self.evict();
+    }
+
+    #[inline]
+    pub fn contains(&self, id: u64) -> bool { self.position(id).is_ok() }
+
+    #[inline]
+    pub fn get(&self, id: u64) -> Option<&Capture> {
+        self.position(id).ok().map(|index| &self.captures[index])
+    }
+
+    /// 记录请求完成后的响应
+    pub fn complete(&mut self, id: u64, response: CapturedResponse) {
+        if let Ok(index) = self.position(id) {
+            let capture = &mut self.captures[index];
+            self.bytes += response.size();
+            if let Some(old) = capture.response.replace(response) {
+                self.bytes -= old.size();
+            }
+            self.evict();
+        }
+    }
+}
@@ -2,7 +2,7 @@ use ::memmap2::{MmapMut, MmapOptions};
 use ::parking_lot::RwLock;
 use ::std::fs::OpenOptions;
 
-use super::{PageContent, Pages, UsageCheck, VisionAbility};
+use super::{LogRetention, PageContent, Pages, UsageCheck, VisionAbility};
 use crate::{
     app::{
         constant::{
@@ -29,6 +29,26 @@ pub struct AppConfig {
     share_token: String,
     web_refs: bool,
     fetch_models: FetchMode,
+    /// 环境变量中的保留策略
+    log_retention: LogRetention,
+    /// 通过 `/config` 设置的保留策略，优先于环境变量并随配置保存
+    log_retention_override: Option<LogRetention>,
+}
+
+/// 配置文件头：`MAGIC | 版本(u32 LE) | 保留`，长度为 16 以保持其后数据的对齐
+const MAGIC: &[u8; 8] = b"CAPICONF";
+const HEADER_LEN: usize = 16;
+/// 配置文件的版本
+///
+/// - 无版本头：仅包含页面内容
+/// - 1：增加保留策略
+const VERSION: u32 = 1;
+
+/// 需要持久化的配置
+#[derive(Default, ::rkyv::Archive, ::rkyv::Deserialize, ::rkyv::Serialize)]
+pub struct PersistedConfig {
+    pages: Pages,
+    log_retention: Option<LogRetention>,
 }
 
 // 全局配置实例
@@ -124,14 +144,23 @@ impl AppConfig {
         let mut config = APP_CONFIG.write();
         config.vision_ability =
             VisionAbility::from_str(&parse_from_env("VISION_ABILITY", EMPTY_STRING));
-        config.slow_pool = parse_from_env("ENABLE_SLOW_POOL", false);
-        config.long_context = parse_from_env("ENABLE_LONG_CONTEXT", false);
+        config.slow_pool = crate::app::settings::get::<bool>("ENABLE_SLOW_POOL");
+        config.long_context = crate::app::settings::get::<bool>("ENABLE_LONG_CONTEXT");
         config.usage_check = UsageCheck::from_str(&parse_from_env("USAGE_CHECK", EMPTY_STRING));
-        config.dynamic_key = parse_from_env("DYNAMIC_KEY", false);
+        config.dynamic_key = crate::app::settings::get::<bool>("DYNAMIC_KEY");
         config.share_token = parse_from_env("SHARED_TOKEN", EMPTY_STRING).into_owned();
-        config.web_refs = parse_from_env("INCLUDE_WEB_REFERENCES", false);
+        config.web_refs = crate::app::settings::get::<bool>("INCLUDE_WEB_REFERENCES");
         config.fetch_models =
             FetchMode::from_str(&parse_from_env("FETCH_RAW_MODELS", EMPTY_STRING));
+        config.log_retention = LogRetention::from_env();
+        super::redaction::init();
+    }
+
+    /// 以默认值初始化，仅用于测试
+    #[cfg(test)]
+    pub fn __init_default() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| unsafe { APP_CONFIG.init(RwLock::new(AppConfig::default())) });
     }
 
     config_methods! {
@@ -147,12 +176,35 @@ impl AppConfig {
         usage_check: UsageCheck, UsageCheck::default();
     }
 
+    /// 生效的保留策略
+    #[inline]
+    pub fn get_log_retention() -> LogRetention {
+        let config = APP_CONFIG.read();
+        config
+            .log_retention_override
+            .unwrap_or(config.log_retention)
+    }
+
+    /// 设置保留策略，优先于环境变量
+    #[inline]
+    pub fn update_log_retention(value: LogRetention) {
+        APP_CONFIG.write().log_retention_override = Some(value)
+    }
+
+    /// 恢复为环境变量中的保留策略
+    #[inline]
+    pub fn reset_log_retention() { APP_CONFIG.write().log_retention_override = None }
+
+    /// 重新读取环境变量中的保留策略，由 `/config/reload` 调用
+    #[inline]
+    pub fn reload_log_retention() { APP_CONFIG.write().log_retention = LogRetention::from_env() }
+
     pub fn get_share_token() -> String { APP_CONFIG.read().share_token.clone() }
 
     pub fn share_token_eq(s: &str) -> bool { APP_CONFIG.read().share_token == s }
 
     pub fn update_share_token(value: String) {
-        if Self::share_token_eq(&value) {
+        if !Self::share_token_eq(&value) {
             APP_CONFIG.write().share_token = value;
         }
     }
@@ -221,9 +273,53 @@ impl AppConfig {
         false
     }
 
+    /// 序列化需要持久化的配置
+    pub fn to_bytes() -> Result<::rkyv::util::AlignedVec, ::rkyv::rancor::Error> {
+        let persisted = {
+            let config = APP_CONFIG.read();
+            PersistedConfig {
+                pages: config.pages.clone(),
+                log_retention: config.log_retention_override,
+            }
+        };
+        let data = ::rkyv::to_bytes::<::rkyv::rancor::Error>(&persisted)?;
+        let mut bytes = ::rkyv::util::AlignedVec::with_capacity(HEADER_LEN + data.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.resize(HEADER_LEN, 0);
+        bytes.extend_from_slice(&data);
+        Ok(bytes)
+    }
+
+    /// 从序列化数据恢复配置，没有版本头的文件仅包含页面内容
+    pub fn from_bytes(bytes: &[u8]) -> Result<PersistedConfig, Box<dyn std::error::Error>> {
+        let Some(rest) = bytes.strip_prefix(MAGIC) else {
+            let pages = ::rkyv::from_bytes::<Pages, ::rkyv::rancor::Error>(bytes)?;
+            return Ok(PersistedConfig {
+                pages,
+                log_retention: None,
+            });
+        };
+        let version = rest
+            .first_chunk::<4>()
+            .map(|version| u32::from_le_bytes(*version))
+            .ok_or("配置文件已损坏")?;
+        if version != VERSION {
+            return Err(format!("不支持的配置格式版本 {version}，请升级程序").into());
+        }
+        let data = bytes.get(HEADER_LEN..).ok_or("配置文件已损坏")?;
+        Ok(::rkyv::from_bytes::<PersistedConfig, ::rkyv::rancor::Error>(data)?)
+    }
+
+    /// 替换持久化的配置
+    pub fn set_persisted(persisted: PersistedConfig) {
+        let mut config = APP_CONFIG.write();
+        config.pages = persisted.pages;
+        config.log_retention_override = persisted.log_retention;
+    }
+
     pub fn save() -> Result<(), Box<dyn std::error::Error>> {
-        let pages = APP_CONFIG.read().pages.clone();
-        let bytes = ::rkyv::to_bytes::<::rkyv::rancor::Error>(&pages)?;
+        let bytes = Self::to_bytes()?;
 
         let file = OpenOptions::new()
             .read(true)
@@ -262,9 +358,7 @@ impl AppConfig {
 
         let mmap = unsafe { MmapOptions::new().map(&file)? };
 
-        let pages = unsafe { ::rkyv::from_bytes_unchecked::<Pages, ::rkyv::rancor::Error>(&mmap) }?;
-        let mut config = APP_CONFIG.write();
-        config.pages = pages;
+        Self::set_persisted(Self::from_bytes(&mmap)?);
 
         Ok(())
     }
@@ -9,9 +9,7 @@ use crate::common::utils::hex::HEX_CHARS;
 
 static mut SAFE_HASH: bool = false;
 
-pub(super) fn init_hash() {
-    unsafe { SAFE_HASH = crate::common::utils::parse_from_env("SAFE_HASH", true) }
-}
+pub(super) fn init_hash() { unsafe { SAFE_HASH = crate::app::settings::get::<bool>("SAFE_HASH") } }
 
 #[derive(Debug)]
 pub enum HashError {
@@ -4,7 +4,15 @@ use crate::core::{constant::get_static_id, model::Role};
 enum ErrorInfoHelper {
     None,
     Error(String),
-    Details { error: String, details: String },
+    Details {
+        error: String,
+        details: String,
+    },
+    Proxy {
+        proxy: String,
+        error: String,
+        details: Option<String>,
+    },
 }
 impl From<ErrorInfoHelper> for super::ErrorInfo {
     #[inline]
@@ -13,6 +21,17 @@ impl From<ErrorInfoHelper> for super::ErrorInfo {
             ErrorInfoHelper::None => Self::None,
             ErrorInfoHelper::Error(e) => Self::new(e),
             ErrorInfoHelper::Details { error, details } => Self::new_details(error, details),
+            ErrorInfoHelper::Proxy {
+                proxy,
+                error,
+                details,
+            } => {
+                let mut info = Self::new_proxy(proxy, error);
+                if let Some(details) = details {
+                    info.add_detail(details);
+                }
+                info
+            }
         }
     }
 }
@@ -26,6 +45,15 @@ impl From<super::ErrorInfo> for ErrorInfoHelper {
                 error: error.to_string(),
                 details: details.to_string(),
             },
+            super::ErrorInfo::Proxy {
+                proxy,
+                error,
+                details,
+            } => Self::Proxy {
+                proxy: proxy.to_string(),
+                error: error.to_string(),
+                details: details.map(str::to_string),
+            },
         }
     }
 }
@@ -40,6 +68,7 @@ pub(super) struct RequestLogHelper {
     stream: bool,
     status: super::LogStatus,
     error: ErrorInfoHelper,
+    client_ip: Option<std::net::IpAddr>,
 }
 impl RequestLogHelper {
     #[inline]
@@ -54,6 +83,7 @@ impl RequestLogHelper {
             stream: self.stream,
             status: self.status,
             error: self.error.into(),
+            client_ip: self.client_ip,
         }
     }
 }
@@ -70,6 +100,7 @@ impl From<&super::RequestLog> for RequestLogHelper {
             stream: log.stream,
             status: log.status,
             error: log.error.into(),
+            client_ip: log.client_ip,
         }
     }
 }
@@ -153,3 +184,55 @@ impl From<super::Chain> for ChainHelper {
         }
     }
 }
+
+/// 没有版本头的旧日志格式，此时错误信息没有代理变体，也不记录客户端地址
+pub(super) mod legacy {
+    use super::ChainHelper;
+    use crate::core::constant::get_static_id;
+
+    #[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+    pub(in super::super) enum ErrorInfoHelper {
+        None,
+        Error(String),
+        Details { error: String, details: String },
+    }
+    impl From<ErrorInfoHelper> for super::super::ErrorInfo {
+        #[inline]
+        fn from(helper: ErrorInfoHelper) -> Self {
+            match helper {
+                ErrorInfoHelper::None => Self::None,
+                ErrorInfoHelper::Error(e) => Self::new(e),
+                ErrorInfoHelper::Details { error, details } => Self::new_details(error, details),
+            }
+        }
+    }
+    #[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+    pub(in super::super) struct RequestLogHelper {
+        pub(in super::super) id: u64,
+        pub(in super::super) timestamp: chrono::NaiveDateTime,
+        pub(in super::super) model: String,
+        pub(in super::super) token_info: super::super::LogTokenInfo,
+        pub(in super::super) chain: Option<ChainHelper>,
+        pub(in super::super) timing: super::super::TimingInfo,
+        pub(in super::super) stream: bool,
+        pub(in super::super) status: super::super::LogStatus,
+        pub(in super::super) error: ErrorInfoHelper,
+    }
+    impl RequestLogHelper {
+        #[inline]
+        pub(in super::super) fn into_request_log(self) -> super::super::RequestLog {
+            super::super::RequestLog {
+                id: self.id,
+                timestamp: self.timestamp.into(),
+                model: get_static_id(self.model),
+                token_info: self.token_info,
+                chain: self.chain.map(Into::into),
+                timing: self.timing,
+                stream: self.stream,
+                status: self.status,
+                error: self.error.into(),
+                client_ip: None,
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use super::{LogStatus, Prompt, RequestLog};
+use crate::app::settings;
+use serde::{Deserialize, Serialize};
+
+/// 单条日志除内容外的大致占用字节数
+const LOG_BASE_SIZE: usize = 160;
+
+/// 请求日志保留策略
+///
+/// 时长单位为秒，0 表示不限制
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Default,
+    Serialize,
+    Deserialize,
+    ::rkyv::Archive,
+    ::rkyv::Deserialize,
+    ::rkyv::Serialize,
+)]
+#[serde(default)]
+pub struct LogRetention {
+    /// 所有日志的最长保留时间
+    pub max_age: u64,
+    /// 成功日志的保留时间，为0时使用 `max_age`
+    pub success_max_age: u64,
+    /// 失败日志的保留时间，为0时使用 `max_age`
+    pub failure_max_age: u64,
+    /// 提示词、思考与回复文本的保留时间，超过后只保留元数据
+    pub content_max_age: u64,
+    /// 日志内容的字节预算，超出时从最旧的日志开始移除
+    pub max_bytes: u64,
+}
+
+impl LogRetention {
+    pub fn from_env() -> Self {
+        Self {
+            max_age: settings::get::<usize>("LOG_MAX_AGE") as u64,
+            success_max_age: settings::get::<usize>("LOG_SUCCESS_MAX_AGE") as u64,
+            failure_max_age: settings::get::<usize>("LOG_FAILURE_MAX_AGE") as u64,
+            content_max_age: settings::get::<usize>("LOG_CONTENT_MAX_AGE") as u64,
+            max_bytes: settings::get::<usize>("LOG_MAX_BYTES") as u64,
+        }
+    }
+
+    /// 是否未设置任何限制
+    #[inline]
+    pub fn is_unlimited(&self) -> bool { *self == Self::default() }
+
+    /// 指定状态日志的保留时间
+    #[inline]
+    pub fn max_age_of(&self, status: LogStatus) -> Option<u64> {
+        let age = match status {
+            LogStatus::Success if self.success_max_age != 0 => self.success_max_age,
+            LogStatus::Failure if self.failure_max_age != 0 => self.failure_max_age,
+            _ => self.max_age,
+        };
+        (age != 0).then_some(age)
+    }
+}
+
+impl RequestLog {
+    /// 估算日志持久化后占用的字节数
+    pub fn estimated_size(&self) -> usize {
+        let Some(chain) = &self.chain else {
+            return LOG_BASE_SIZE;
+        };
+        let prompt = match &chain.prompt {
+            Prompt::None => 0,
+            Prompt::Origin(s) => s.len(),
+            Prompt::Parsed(messages) => messages.iter().map(|m| m.content.0.len() + 8).sum(),
+        };
+        let delays = chain
+            .delays
+            .as_ref()
+            .map_or(0, |(text, delays)| text.len() + delays.len() * 8);
+        let think = chain.think.as_ref().map_or(0, String::len);
+        LOG_BASE_SIZE + prompt + delays + think
+    }
+
+    /// 移除提示词、思考与回复文本，保留用量与延迟等元数据，返回是否有内容被移除
+    pub fn strip_content(&mut self) -> bool {
+        let Some(chain) = &mut self.chain else {
+            return false;
+        };
+        let mut stripped = false;
+        if chain.prompt.is_some() {
+            chain.prompt = Prompt::None;
+            stripped = true;
+        }
+        if chain.think.take().is_some() {
+            stripped = true;
+        }
+        if let Some((text, _)) = &mut chain.delays
+            && !text.is_empty()
+        {
+            *text = String::new();
+            stripped = true;
+        }
+        stripped
+    }
+}
@@ -3,7 +3,7 @@ use std::{borrow::Cow, sync::Arc};
 
 use super::{
     ApiStatus, DeleteResponseExpectation,
-    proxy_pool::{Proxies, SingleProxy},
+    proxy_pool::{Proxies, SingleProxy, group::ProxyGroup, health::ProxyHealth},
 };
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +17,8 @@ pub struct ProxyInfoResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub general_proxy: Option<Arc<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<HashMap<String, ProxyHealth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<Cow<'static, str>>,
 }
 
@@ -53,3 +55,23 @@ pub struct ProxiesDeleteResponse {
 pub struct SetGeneralProxyRequest {
     pub name: String,
 }
+
+// 代理组信息响应
+#[derive(Serialize)]
+pub struct ProxyGroupsResponse {
+    pub status: ApiStatus,
+    pub groups: HashMap<String, ProxyGroup>,
+}
+
+// 设置代理组请求，同名代理组将被覆盖
+#[derive(Deserialize)]
+pub struct ProxyGroupsSetRequest {
+    pub groups: HashMap<String, ProxyGroup>,
+}
+
+// 删除代理组请求
+#[derive(Deserialize)]
+pub struct ProxyGroupsDeleteRequest {
+    #[serde(default)]
+    pub names: HashSet<String>,
+}
@@ -1,4 +1,4 @@
-use crate::app::lazy::{PROXIES_FILE_PATH, SERVICE_TIMEOUT, TCP_KEEPALIVE};
+use crate::app::lazy::{PROXIES_FILE_PATH, PROXY_FALLBACK, SERVICE_TIMEOUT, TCP_KEEPALIVE};
 use ahash::{HashMap, HashSet};
 use arc_swap::{ArcSwap, ArcSwapAny};
 use memmap2::{MmapMut, MmapOptions};
@@ -11,7 +11,10 @@ use std::{
     time::Duration,
 };
 use tokio::fs::OpenOptions;
+pub mod group;
+pub mod health;
 mod proxy_url;
+mod tls;
 use proxy_url::ProxyUrl;
 
 // 代理值常量
@@ -61,8 +64,12 @@ impl Default for Proxies {
 }
 
 impl Proxies {
+    /// 所有代理名称
     #[inline]
-    pub fn init(mut self) {
+    pub fn names(&self) -> impl Iterator<Item = &String> { self.proxies.keys() }
+
+    /// 初始化代理池，TLS 配置无效或无法创建客户端时返回错误
+    pub fn init(mut self) -> Result<(), Box<dyn std::error::Error>> {
         if self.proxies.is_empty() {
             self.proxies = HashMap::from_iter([(SYS_PROXY.to_string(), SingleProxy::Sys)]);
             if self.general.as_str() != SYS_PROXY {
@@ -71,11 +78,12 @@ impl Proxies {
         } else if !self.proxies.contains_key(&self.general) {
             self.general = __unwrap!(self.proxies.keys().next()).clone();
         }
+        tls::reload()?;
         let proxies = self.proxies.values().collect::<HashSet<_>>();
         let mut clients =
             HashMap::with_capacity_and_hasher(proxies.len(), ::ahash::RandomState::new());
         for proxy in proxies {
-            proxy.insert_to(&mut clients);
+            proxy.insert_to(&mut clients)?;
         }
         let _ = GENERAL_CLIENT.set(ArcSwapAny::from(
             clients
@@ -90,6 +98,7 @@ impl Proxies {
         let _ = CLIENTS.set(ArcSwap::from_pointee(clients));
         let _ = PROXIES.set(ArcSwap::from_pointee(self.proxies));
         let _ = GENERAL_NAME.set(ArcSwap::from_pointee(self.general));
+        Ok(())
     }
 
     #[inline]
@@ -121,25 +130,24 @@ impl Proxies {
         // 1. 收集当前配置中的所有唯一代理
         let current_proxies: HashSet<&SingleProxy> = proxies.values().collect();
 
-        // 2. 首先移除不再使用的客户端
-        let to_remove: Vec<SingleProxy> = clients
-            .keys()
-            .filter(|proxy| !current_proxies.contains(proxy))
-            .cloned()
-            .collect();
-
-        for proxy in to_remove {
-            clients.remove(&proxy);
+        // 2. 首先移除不再使用的客户端，TLS配置变化时重建所有客户端
+        if tls::reload()? {
+            clients.clear();
+        } else {
+            clients.retain(|proxy, _| current_proxies.contains(proxy));
         }
 
         // 3. 然后为新的代理配置创建客户端
         for proxy in current_proxies {
             if !clients.contains_key(proxy) {
                 // 创建新的客户端
-                proxy.insert_to(&mut clients);
+                proxy.insert_to(&mut clients)?;
             }
         }
 
+        // 清理已移除代理的健康状态
+        health::retain(&clients);
+
         self::clients().store(Arc::new(clients));
 
         // 4. 设置通用名称
@@ -151,11 +159,39 @@ impl Proxies {
         Ok(())
     }
 
-    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
-        let bytes = ::rkyv::to_bytes::<::rkyv::rancor::Error>(&Self {
+    /// 重新读取 TLS 与连接设置并重建全部客户端，进行中的请求继续使用原客户端
+    ///
+    /// 设置无效时返回错误，原客户端保持不变
+    pub fn rebuild_clients() -> Result<(), Box<dyn std::error::Error>> {
+        tls::reload()?;
+        let proxies = proxies().load();
+        let mut clients =
+            HashMap::with_capacity_and_hasher(proxies.len(), ::ahash::RandomState::new());
+        for proxy in proxies.values().collect::<HashSet<_>>() {
+            proxy.insert_to(&mut clients)?;
+        }
+        health::retain(&clients);
+        self::clients().store(Arc::new(clients));
+        set_general();
+        Ok(())
+    }
+
+    /// 序列化当前的代理配置
+    pub fn to_bytes() -> Result<::rkyv::util::AlignedVec, ::rkyv::rancor::Error> {
+        ::rkyv::to_bytes::<::rkyv::rancor::Error>(&Self {
             proxies: (*proxies().load_full()).clone(),
             general: (*general_name().load_full()).clone(),
-        })?;
+        })
+    }
+
+    /// 从序列化数据恢复代理配置
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ::rkyv::rancor::Error> {
+        ::rkyv::from_bytes::<Self, ::rkyv::rancor::Error>(bytes)
+    }
+
+    pub async fn save() -> Result<(), Box<dyn std::error::Error>> {
+        let bytes = Self::to_bytes()?;
 
         let file = OpenOptions::new()
             .read(true)
@@ -195,9 +231,7 @@ impl Proxies {
         }
 
         let mmap = unsafe { MmapOptions::new().map(&file)? };
-        unsafe {
-            ::rkyv::from_bytes_unchecked::<Self, ::rkyv::rancor::Error>(&mmap).map_err(Into::into)
-        }
+        Self::from_bytes(&mmap).map_err(Into::into)
     }
 
     // 更新全局代理池并保存配置
@@ -221,45 +255,29 @@ pub enum SingleProxy {
 
 impl SingleProxy {
     #[inline]
-    fn insert_to(&self, clients: &mut HashMap<SingleProxy, Client>) {
+    fn insert_to(&self, clients: &mut HashMap<SingleProxy, Client>) -> Result<(), String> {
         // 创建新的客户端
-        match self {
-            SingleProxy::Non => {
-                clients.insert(
-                    SingleProxy::Non,
-                    Client::builder()
-                        .https_only(true)
-                        .tcp_keepalive(Duration::from_secs(*TCP_KEEPALIVE))
-                        .connect_timeout(Duration::from_secs(*SERVICE_TIMEOUT))
-                        .no_proxy()
-                        .build()
-                        .expect("创建无代理客户端失败"),
-                );
-            }
-            SingleProxy::Sys => {
-                clients.insert(
-                    SingleProxy::Sys,
-                    Client::builder()
-                        .https_only(true)
-                        .tcp_keepalive(Duration::from_secs(*TCP_KEEPALIVE))
-                        .connect_timeout(Duration::from_secs(*SERVICE_TIMEOUT))
-                        .build()
-                        .expect("创建默认客户端失败"),
-                );
-            }
-            SingleProxy::Url(url) => {
-                clients.insert(
-                    (*self).clone(),
-                    Client::builder()
-                        .https_only(true)
-                        .tcp_keepalive(Duration::from_secs(*TCP_KEEPALIVE))
-                        .connect_timeout(Duration::from_secs(*SERVICE_TIMEOUT))
-                        .proxy(url.to_proxy())
-                        .build()
-                        .expect("创建代理客户端失败"),
-                );
-            }
-        }
+        let builder = tls::apply(
+            Client::builder()
+                .https_only(true)
+                .tcp_keepalive(Duration::from_secs(TCP_KEEPALIVE.get()))
+                .connect_timeout(Duration::from_secs(SERVICE_TIMEOUT.get())),
+        );
+        let client = match self {
+            SingleProxy::Non => builder
+                .no_proxy()
+                .build()
+                .map_err(|e| format!("创建无代理客户端失败: {e}"))?,
+            SingleProxy::Sys => builder
+                .build()
+                .map_err(|e| format!("创建默认客户端失败: {e}"))?,
+            SingleProxy::Url(url) => builder
+                .proxy(url.to_proxy())
+                .build()
+                .map_err(|e| format!("创建代理客户端失败: {e}"))?,
+        };
+        clients.insert(self.clone(), client);
+        Ok(())
     }
 }
 
@@ -333,11 +351,34 @@ impl FromStr for SingleProxy {
     }
 }
 
+/// 按名称解析实际使用的代理
+///
+/// 代理不可用时依次回退到备用代理与通用代理，均不可用时仍使用原代理
+fn resolve<'a>(
+    proxies: &'a HashMap<String, SingleProxy>,
+    name: &str,
+) -> Option<(&'a String, &'a SingleProxy)> {
+    let origin = proxies.get_key_value(name)?;
+    if health::is_healthy(origin.1) {
+        return Some(origin);
+    }
+
+    let general = general_name().load();
+    let fallback = PROXY_FALLBACK.load();
+    [fallback.as_str(), general.as_str()]
+        .into_iter()
+        .filter_map(|name| proxies.get_key_value(name))
+        .find(|(_, proxy)| health::is_healthy(proxy))
+        .or(Some(origin))
+}
+
 // 获取客户端
 #[inline]
-pub fn get_client(url: &str) -> Client {
-    // 先通过名称查找代理配置
-    if let Some(proxy) = proxies().load().get(url) {
+pub fn get_client(url: &str, sticky: u64) -> Client {
+    // 先通过名称查找代理配置，代理组则先选出成员
+    let proxies = proxies().load();
+    let member = group::select_member(url, sticky, &proxies);
+    if let Some((_, proxy)) = resolve(&proxies, member.as_deref().unwrap_or(url)) {
         // 然后通过代理配置查找客户端
         if let Some(client) = clients().load().get(proxy) {
             return client.clone();
@@ -354,13 +395,42 @@ pub fn get_general_client() -> Client { general_client().load_full() }
 
 // 获取客户端或通用客户端
 #[inline]
-pub fn get_client_or_general(url: Option<&str>) -> Client {
+pub fn get_client_or_general(url: Option<&str>, sticky: u64) -> Client {
     match url {
-        Some(url) => get_client(url),
-        None => get_general_client(),
+        Some(url) => get_client(url, sticky),
+        None => get_client(&general_name().load(), sticky),
+    }
+}
+
+/// 将代理组名称解析为本次选中的成员名称，其余名称原样返回
+pub fn pin(url: Option<&str>, sticky: u64) -> Option<String> {
+    let url = url?;
+    group::select_member(url, sticky, &proxies().load()).or_else(|| Some(url.to_string()))
+}
+
+/// 记录经过代理的请求成功
+pub fn report_success(url: Option<&str>) {
+    let proxies = proxies().load();
+    let general = general_name().load();
+    if let Some((_, proxy)) = url
+        .and_then(|url| resolve(&proxies, url))
+        .or_else(|| resolve(&proxies, &general))
+    {
+        health::report_success(proxy);
     }
 }
 
+/// 记录代理连接失败，返回实际使用的代理名称
+pub fn report_failure(url: Option<&str>, error: String) -> Option<String> {
+    let proxies = proxies().load();
+    let general = general_name().load();
+    let (name, proxy) = url
+        .and_then(|url| resolve(&proxies, url))
+        .or_else(|| resolve(&proxies, &general))?;
+    health::report_failure(proxy, error);
+    Some(name.clone())
+}
+
 /// 设置通用客户端
 #[inline]
 fn set_general() {
@@ -0,0 +1,269 @@
+use super::health;
+use crate::app::{lazy::PROXY_GROUPS_FILE_PATH, model::state::storage};
+use ahash::HashMap;
+use arc_swap::ArcSwap;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt,
+    hash::Hash,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// 组名到代理组的映射
+static GROUPS: OnceLock<ArcSwap<HashMap<String, GroupEntry>>> = OnceLock::new();
+
+/// 代理组尚未初始化
+#[derive(Debug)]
+pub struct NotInitialized;
+
+impl fmt::Display for NotInitialized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("代理组未初始化")
+    }
+}
+
+impl Error for NotInitialized {}
+
+/// 代理组成员的选择策略
+#[derive(
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+    Archive,
+    RkyvDeserialize,
+    RkyvSerialize,
+)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum GroupStrategy {
+    /// 每次请求依次轮换
+    #[default]
+    Rotate,
+    /// 每次请求随机选择
+    Random,
+    /// 按令牌哈希固定到某个成员
+    Sticky,
+}
+
+#[derive(Clone, Deserialize, Serialize, Archive, RkyvDeserialize, RkyvSerialize)]
+pub struct ProxyGroup {
+    /// 成员代理名称
+    pub members: Vec<String>,
+    /// 选择策略
+    #[serde(default)]
+    pub strategy: GroupStrategy,
+}
+
+/// 运行时的代理组，附带轮换游标
+struct GroupEntry {
+    group: ProxyGroup,
+    cursor: AtomicUsize,
+}
+
+impl From<ProxyGroup> for GroupEntry {
+    #[inline]
+    fn from(group: ProxyGroup) -> Self {
+        Self {
+            group,
+            cursor: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl ProxyGroup {
+    /// 按策略选择成员，优先选择可用的成员
+    fn select<'a>(
+        &'a self,
+        cursor: &AtomicUsize,
+        sticky: u64,
+        proxies: &HashMap<String, super::SingleProxy>,
+    ) -> Option<&'a str> {
+        let len = self.members.len();
+        if len == 0 {
+            return None;
+        }
+
+        let start = match self.strategy {
+            GroupStrategy::Rotate => cursor.fetch_add(1, Ordering::Relaxed) % len,
+            GroupStrategy::Random => rand::random_range(0..len),
+            GroupStrategy::Sticky => {
+                // 最高权重哈希：按 (键, 成员) 的哈希排序，增删成员时只有涉及该成员的键改变分配
+                let mut members: Vec<_> = self
+                    .members
+                    .iter()
+                    .map(|name| (sticky_hash((sticky, name)), name.as_str()))
+                    .collect();
+                members.sort_unstable_by_key(|&(hash, _)| ::core::cmp::Reverse(hash));
+                return first_available(members.into_iter().map(|(_, name)| name), proxies);
+            }
+        };
+
+        first_available(
+            (0..len).map(|i| self.members[(start + i) % len].as_str()),
+            proxies,
+        )
+    }
+}
+
+/// 按顺序查找第一个可用成员，都不可用时返回第一个存在的成员
+///
+/// 粘性分配因此在成员恢复后回到原成员
+fn first_available<'a>(
+    order: impl Iterator<Item = &'a str>,
+    proxies: &HashMap<String, super::SingleProxy>,
+) -> Option<&'a str> {
+    let mut first = None;
+    for name in order {
+        let Some(proxy) = proxies.get(name) else {
+            continue;
+        };
+        if health::is_healthy(proxy) {
+            return Some(name);
+        }
+        first.get_or_insert(name);
+    }
+    first
+}
+
+#[derive(Default, Archive, RkyvDeserialize, RkyvSerialize)]
+pub struct ProxyGroups {
+    groups: HashMap<String, ProxyGroup>,
+}
+
+impl ProxyGroups {
+    #[inline]
+    pub fn init(self) { let _ = GROUPS.set(ArcSwap::from_pointee(into_entries(self.groups))); }
+
+    /// 替换全局代理组
+    #[inline]
+    pub fn update_global(self) -> Result<(), NotInitialized> { store(self.groups) }
+
+    /// 序列化当前的代理组
+    pub fn to_bytes() -> Result<::rkyv::util::AlignedVec, Box<dyn Error>> {
+        Ok(::rkyv::to_bytes::<::rkyv::rancor::Error>(&Self {
+            groups: snapshot()?,
+        })?)
+    }
+
+    /// 从序列化数据恢复代理组
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ::rkyv::rancor::Error> {
+        ::rkyv::from_bytes::<Self, ::rkyv::rancor::Error>(bytes)
+    }
+
+    pub async fn save() -> Result<(), Box<dyn Error>> {
+        let bytes = Self::to_bytes()?;
+        storage::write(&PROXY_GROUPS_FILE_PATH, &bytes).await
+    }
+
+    pub async fn load() -> Result<Self, Box<dyn Error>> {
+        let Some(bytes) = storage::read(&PROXY_GROUPS_FILE_PATH).await? else {
+            return Ok(Self::default());
+        };
+        Self::from_bytes(&bytes).map_err(Into::into)
+    }
+}
+
+#[inline]
+fn into_entries(groups: HashMap<String, ProxyGroup>) -> HashMap<String, GroupEntry> {
+    groups
+        .into_iter()
+        .map(|(name, group)| (name, group.into()))
+        .collect()
+}
+
+#[inline]
+fn groups() -> Result<&'static ArcSwap<HashMap<String, GroupEntry>>, NotInitialized> {
+    GROUPS.get().ok_or(NotInitialized)
+}
+
+/// 获取所有代理组
+pub fn snapshot() -> Result<HashMap<String, ProxyGroup>, NotInitialized> {
+    Ok(groups()?
+        .load()
+        .iter()
+        .map(|(name, entry)| (name.clone(), entry.group.clone()))
+        .collect())
+}
+
+/// 替换所有代理组
+#[inline]
+pub fn store(groups: HashMap<String, ProxyGroup>) -> Result<(), NotInitialized> {
+    self::groups()?.store(Arc::new(into_entries(groups)));
+    Ok(())
+}
+
+/// 代理组是否存在，未初始化时视为没有任何代理组
+#[inline]
+pub fn contains(name: &str) -> bool {
+    groups().is_ok_and(|groups| groups.load().contains_key(name))
+}
+
+/// 计算粘性分配所用的哈希，使用固定种子以保证重启后分配不变
+#[inline]
+pub fn sticky_hash<T: Hash>(key: T) -> u64 {
+    ::ahash::RandomState::with_seeds(0, 0, 0, 0).hash_one(key)
+}
+
+/// 若名称为代理组，则按策略选出成员名称
+pub(super) fn select_member(
+    name: &str,
+    sticky: u64,
+    proxies: &HashMap<String, super::SingleProxy>,
+) -> Option<String> {
+    let groups = groups().ok()?.load();
+    let entry = groups.get(name)?;
+    entry
+        .group
+        .select(&entry.cursor, sticky, proxies)
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sticky_membership() {
+        let group = |members: &[&str]| ProxyGroup {
+            members: members.iter().map(ToString::to_string).collect(),
+            strategy: GroupStrategy::Sticky,
+        };
+        let proxies: HashMap<_, _> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|name| (name.to_string(), super::super::SingleProxy::Non))
+            .collect();
+        let cursor = AtomicUsize::new(0);
+
+        let before = group(&["a", "b", "c"]);
+        let after = group(&["a", "b", "c", "d"]);
+        let removed = group(&["a", "c"]);
+        let mut moved = 0;
+        for key in 0..1000 {
+            let sticky = sticky_hash(key);
+            let old = before.select(&cursor, sticky, &proxies).unwrap();
+            // 同一个键始终分配到同一成员
+            assert_eq!(before.select(&cursor, sticky, &proxies), Some(old));
+            // 新增成员只会接走部分键
+            let new = after.select(&cursor, sticky, &proxies).unwrap();
+            if new != old {
+                assert_eq!(new, "d");
+                moved += 1;
+            }
+            // 移除成员只影响原本分配给它的键
+            if old != "b" {
+                assert_eq!(removed.select(&cursor, sticky, &proxies), Some(old));
+            }
+        }
+        assert!(moved > 0 && moved < 500, "{moved}");
+    }
+}
@@ -0,0 +1,196 @@
+use super::{SingleProxy, clients};
+use crate::app::{lazy::PROXY_PROBE_URL, model::DateTime};
+use ahash::HashMap;
+use parking_lot::RwLock;
+use reqwest::Client;
+use serde::Serialize;
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+/// 连续失败达到该次数后视为不可用
+const FAILURE_THRESHOLD: u32 = 2;
+
+/// 不可用的代理经过该时长后重新尝试，请求成功即恢复
+const RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// 单次探测超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 代理配置到健康状态的映射
+///
+/// 未记录的代理视为可用
+static HEALTH: LazyLock<RwLock<HashMap<SingleProxy, ProxyHealth>>> =
+    LazyLock::new(Default::default);
+
+#[derive(Clone, Serialize)]
+pub struct ProxyHealth {
+    /// 是否可用
+    pub healthy: bool,
+    /// 最近一次成功探测的延迟(毫秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// 连续失败次数
+    pub failures: u32,
+    /// 最近一次失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// 最近更新时间
+    pub checked_at: DateTime,
+    /// 不可用时允许重新尝试的时间
+    #[serde(skip)]
+    retry_at: Option<Instant>,
+}
+
+impl Default for ProxyHealth {
+    fn default() -> Self {
+        Self {
+            healthy: true,
+            latency_ms: None,
+            failures: 0,
+            last_error: None,
+            checked_at: DateTime::now(),
+            retry_at: None,
+        }
+    }
+}
+
+impl ProxyHealth {
+    #[inline]
+    fn record_success(&mut self, latency: Duration) {
+        self.healthy = true;
+        self.latency_ms = Some(latency.as_millis() as u64);
+        self.failures = 0;
+        self.last_error = None;
+        self.checked_at = DateTime::now();
+        self.retry_at = None;
+    }
+
+    #[inline]
+    fn record_failure(&mut self, error: String) {
+        self.failures = self.failures.saturating_add(1);
+        if self.failures >= FAILURE_THRESHOLD {
+            self.healthy = false;
+            self.retry_at = Some(Instant::now() + RETRY_AFTER);
+        }
+        self.last_error = Some(error);
+        self.checked_at = DateTime::now();
+    }
+}
+
+/// 代理当前是否可用，不可用的代理到达重试时间后视为可用
+#[inline]
+pub fn is_healthy(proxy: &SingleProxy) -> bool {
+    HEALTH.read().get(proxy).is_none_or(|health| {
+        health.healthy || health.retry_at.is_some_and(|at| Instant::now() >= at)
+    })
+}
+
+/// 获取所有代理的健康状态
+#[inline]
+pub fn snapshot() -> HashMap<SingleProxy, ProxyHealth> { HEALTH.read().clone() }
+
+/// 记录请求过程中遇到的代理失败
+#[inline]
+pub(super) fn report_failure(proxy: &SingleProxy, error: String) {
+    HEALTH
+        .write()
+        .entry(proxy.clone())
+        .or_default()
+        .record_failure(error);
+}
+
+/// 请求经过代理成功，清除失败记录
+#[inline]
+pub(super) fn report_success(proxy: &SingleProxy) {
+    if HEALTH
+        .read()
+        .get(proxy)
+        .is_none_or(|health| health.failures == 0)
+    {
+        return;
+    }
+    if let Some(health) = HEALTH.write().get_mut(proxy) {
+        health.failures = 0;
+        health.healthy = true;
+        health.last_error = None;
+        health.checked_at = DateTime::now();
+        health.retry_at = None;
+    }
+}
+
+/// 移除已不在代理池中的健康状态
+#[inline]
+pub(super) fn retain(clients: &HashMap<SingleProxy, Client>) {
+    HEALTH
+        .write()
+        .retain(|proxy, _| clients.contains_key(proxy));
+}
+
+/// 探测单个客户端的连通性，返回延迟
+///
+/// 只要收到任意HTTP响应即视为连通
+async fn probe(client: &Client) -> Result<Duration, String> {
+    let start = Instant::now();
+    match client
+        .head(&**PROXY_PROBE_URL)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(_) => Ok(start.elapsed()),
+        Err(e) => Err(e.without_url().to_string()),
+    }
+}
+
+/// 探测代理池中的所有代理并更新健康状态
+pub async fn probe_all() {
+    let clients = clients().load_full();
+    let results = futures::future::join_all(
+        clients
+            .iter()
+            .map(|(proxy, client)| async move { (proxy, probe(client).await) }),
+    )
+    .await;
+
+    let mut health = HEALTH.write();
+    for (proxy, result) in results {
+        let entry = health.entry(proxy.clone()).or_default();
+        match result {
+            Ok(latency) => entry.record_success(latency),
+            Err(e) => {
+                if entry.healthy && entry.failures + 1 >= FAILURE_THRESHOLD {
+                    eprintln!("代理 {proxy} 不可用: {e}");
+                }
+                entry.record_failure(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovery() {
+        static TZ_INIT: std::sync::Once = std::sync::Once::new();
+        TZ_INIT.call_once(crate::app::model::tz::__init);
+
+        let proxy = SingleProxy::Non;
+        for _ in 0..FAILURE_THRESHOLD {
+            report_failure(&proxy, "connect error".to_string());
+        }
+        assert!(!is_healthy(&proxy));
+
+        // 到达重试时间后重新尝试
+        HEALTH.write().get_mut(&proxy).unwrap().retry_at = Some(Instant::now());
+        assert!(is_healthy(&proxy));
+
+        // 请求成功后恢复
+        report_success(&proxy);
+        let health = HEALTH.read().get(&proxy).cloned().unwrap();
+        assert!(health.healthy && health.failures == 0 && health.retry_at.is_none());
+    }
+}
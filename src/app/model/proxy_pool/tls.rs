@@ -0,0 +1,191 @@
+use crate::{app::constant::EMPTY_STRING, common::utils::parse_from_env};
+use ahash::HashMap;
+use arc_swap::ArcSwap;
+use reqwest::{Certificate, ClientBuilder, Identity, tls::Version};
+use std::sync::{Arc, LazyLock};
+
+/// 当前生效的上游 TLS 配置
+static OPTIONS: LazyLock<ArcSwap<TlsOptions>> = LazyLock::new(Default::default);
+
+/// 上游连接的 TLS 配置
+#[derive(Default)]
+struct TlsOptions {
+    /// 额外信任的根证书
+    roots: Vec<Certificate>,
+    /// 客户端证书
+    identity: Option<Identity>,
+    /// 最低 TLS 版本
+    min_version: Option<Version>,
+    /// 主机名到证书 SHA-256 指纹的映射
+    pins: Arc<HashMap<String, Vec<[u8; 32]>>>,
+    /// 加载来源的摘要，用于判断配置是否变化
+    digest: [u8; 32],
+}
+
+impl TlsOptions {
+    /// 读取 TLS 配置，任一设置无效时返回错误
+    fn load() -> Result<Self, String> {
+        use sha2::Digest as _;
+
+        let ca_certs = parse_from_env("UPSTREAM_CA_CERTS", EMPTY_STRING);
+        let client_cert = parse_from_env("UPSTREAM_CLIENT_CERT", EMPTY_STRING);
+        let client_key = parse_from_env("UPSTREAM_CLIENT_KEY", EMPTY_STRING);
+        let min_version = parse_from_env("UPSTREAM_MIN_TLS_VERSION", EMPTY_STRING);
+        let cert_pins = parse_from_env("UPSTREAM_CERT_PINS", EMPTY_STRING);
+
+        let mut hasher = sha2::Sha256::new();
+        for value in [
+            &ca_certs,
+            &client_cert,
+            &client_key,
+            &min_version,
+            &cert_pins,
+        ] {
+            hasher.update(value.as_bytes());
+            hasher.update([0]);
+        }
+
+        // 根证书，多个文件以逗号分隔
+        let mut roots = Vec::new();
+        for path in split_list(&ca_certs, ',') {
+            let pem = read_file(path)?;
+            hasher.update(&pem);
+            let certs = Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("解析根证书 {path} 失败: {e}"))?;
+            roots.extend(certs);
+        }
+
+        // 客户端证书，私钥未单独指定时从证书文件读取
+        let identity = if client_cert.is_empty() {
+            if !client_key.is_empty() {
+                return Err("设置了 UPSTREAM_CLIENT_KEY 但未设置 UPSTREAM_CLIENT_CERT".to_string());
+            }
+            None
+        } else {
+            let mut pem = read_file(&client_cert)?;
+            if !client_key.is_empty() {
+                pem.push(b'\n');
+                pem.extend(read_file(&client_key)?);
+            }
+            hasher.update(&pem);
+            Some(Identity::from_pem(&pem).map_err(|e| format!("解析客户端证书失败: {e}"))?)
+        };
+
+        let min_version = match &*min_version {
+            "" => None,
+            "1.0" => Some(Version::TLS_1_0),
+            "1.1" => Some(Version::TLS_1_1),
+            "1.2" => Some(Version::TLS_1_2),
+            "1.3" => Some(Version::TLS_1_3),
+            other => return Err(format!("无效的最低TLS版本: {other}")),
+        };
+
+        Ok(Self {
+            roots,
+            identity,
+            min_version,
+            pins: Arc::new(parse_pins(&cert_pins)?),
+            digest: hasher.finalize().into(),
+        })
+    }
+}
+
+#[inline]
+fn split_list(s: &str, sep: char) -> impl Iterator<Item = &str> {
+    s.split(sep).map(str::trim).filter(|s| !s.is_empty())
+}
+
+#[inline]
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("读取证书文件 {path} 失败: {e}"))
+}
+
+/// 解析证书指纹配置
+///
+/// 格式为 `host=指纹[,指纹];host=指纹`，指纹为证书 DER 的 SHA-256 十六进制，可包含冒号
+fn parse_pins(s: &str) -> Result<HashMap<String, Vec<[u8; 32]>>, String> {
+    let mut pins = HashMap::default();
+    for entry in split_list(s, ';') {
+        let Some((host, fingerprints)) = entry.split_once('=') else {
+            return Err(format!("无效的证书指纹配置: {entry}"));
+        };
+        let host = host.trim().to_ascii_lowercase();
+        let fingerprints = split_list(fingerprints, ',')
+            .map(|fingerprint| {
+                let mut buf = [0; 32];
+                hex::decode_to_slice(fingerprint.replace(':', ""), &mut buf)
+                    .map_err(|_| format!("无效的证书指纹: {fingerprint}"))?;
+                Ok(buf)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if host.is_empty() || fingerprints.is_empty() {
+            return Err(format!("无效的证书指纹配置: {entry}"));
+        }
+        pins.entry(host)
+            .or_insert_with(Vec::new)
+            .extend(fingerprints);
+    }
+    Ok(pins)
+}
+
+/// 重新加载 TLS 配置，返回配置是否发生变化
+///
+/// 配置无效时返回错误并保留原配置，避免在缺少证书或指纹校验的情况下连接上游
+pub(super) fn reload() -> Result<bool, String> {
+    let options = TlsOptions::load()?;
+    let changed = OPTIONS.load().digest != options.digest;
+    OPTIONS.store(Arc::new(options));
+    Ok(changed)
+}
+
+/// 将当前 TLS 配置应用到客户端构建器
+pub(super) fn apply(mut builder: ClientBuilder) -> ClientBuilder {
+    let options = OPTIONS.load();
+
+    for cert in &options.roots {
+        builder = builder.add_root_certificate(cert.clone());
+    }
+    if let Some(identity) = &options.identity {
+        builder = builder.identity(identity.clone());
+    }
+    if let Some(version) = options.min_version {
+        builder = builder.min_tls_version(version);
+    }
+    if !options.pins.is_empty() {
+        let pins = options.pins.clone();
+        builder = builder.tls_server_cert_check(move |host, cert| {
+            use sha2::Digest as _;
+            match pins.get(host) {
+                Some(fingerprints) => {
+                    let digest: [u8; 32] = sha2::Sha256::digest(cert).into();
+                    fingerprints.contains(&digest)
+                }
+                None => true,
+            }
+        });
+    }
+
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pins() {
+        let digest = "ab".repeat(32);
+        let pins = parse_pins(&format!(
+            "Example.com={digest}, {}",
+            "AB:".repeat(31) + "AB"
+        ))
+        .unwrap();
+        assert_eq!(pins["example.com"], vec![[0xab; 32]; 2]);
+
+        assert!(parse_pins("").unwrap().is_empty());
+        assert!(parse_pins("example.com").is_err());
+        assert!(parse_pins("example.com=").is_err());
+        assert!(parse_pins("example.com=abcd").is_err());
+        assert!(parse_pins(&format!("={digest}")).is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! 日志与上游请求中的敏感信息脱敏
+
+use crate::{
+    app::{constant::EMPTY_STRING, settings},
+    common::utils::parse_from_env,
+};
+use arc_swap::ArcSwap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    sync::{Arc, LazyLock},
+};
+
+const API_KEY_PATTERN: &str = concat!(
+    r"\b(?:sk-(?:ant-|proj-)?[A-Za-z0-9_\-]{16,}",
+    r"|AKIA[0-9A-Z]{16}",
+    r"|gh[pousr]_[A-Za-z0-9]{36,}",
+    r"|xox[abpr]-[A-Za-z0-9\-]{10,}",
+    r"|AIza[0-9A-Za-z_\-]{35}",
+    r"|eyJ[A-Za-z0-9_\-]{10,}\.[A-Za-z0-9_\-]{10,}\.[A-Za-z0-9_\-]{10,})"
+);
+const EMAIL_PATTERN: &str =
+    r"\b[A-Za-z0-9._%+\-]+@[A-Za-z0-9\-]+(?:\.[A-Za-z0-9\-]+)*\.[A-Za-z]{2,}\b";
+const PHONE_PATTERN: &str = concat!(
+    r"(?:\+\d{1,3}[\s\-]?)?(?:\(\d{3}\)\s?|\b\d{3}[\s.\-]?)\d{3}[\s.\-]?\d{4}\b",
+    r"|\b1[3-9]\d{9}\b"
+);
+
+/// 内置检测器
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Detector {
+    ApiKey,
+    Email,
+    Phone,
+}
+
+impl Detector {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "api_key" => Some(Self::ApiKey),
+            "email" => Some(Self::Email),
+            "phone" => Some(Self::Phone),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    const fn pattern(self) -> &'static str {
+        match self {
+            Self::ApiKey => API_KEY_PATTERN,
+            Self::Email => EMAIL_PATTERN,
+            Self::Phone => PHONE_PATTERN,
+        }
+    }
+
+    #[inline]
+    const fn replacement(self) -> &'static str {
+        match self {
+            Self::ApiKey => "[REDACTED:api_key]",
+            Self::Email => "[REDACTED:email]",
+            Self::Phone => "[REDACTED:phone]",
+        }
+    }
+}
+
+/// 脱敏设置
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Redaction {
+    /// 写入日志前脱敏提示词、思考与回复文本
+    pub logs: bool,
+    /// 发送到上游前脱敏请求中的消息文本
+    pub outbound: bool,
+    /// 启用的内置检测器
+    pub detectors: Vec<Detector>,
+    /// 自定义正则表达式
+    pub patterns: Vec<String>,
+}
+
+impl Redaction {
+    fn from_env() -> Self {
+        let detectors = settings::get::<&str>("REDACT_DETECTORS");
+        let patterns = parse_from_env("REDACT_PATTERNS", EMPTY_STRING);
+        Self {
+            logs: settings::get::<bool>("REDACT_LOGS"),
+            outbound: settings::get::<bool>("REDACT_OUTBOUND"),
+            detectors: detectors
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .filter_map(|s| {
+                    let detector = Detector::from_str(s);
+                    if detector.is_none() {
+                        eprintln!("未知的脱敏检测器: {s}");
+                    }
+                    detector
+                })
+                .collect(),
+            // 正则中可能包含逗号，以 JSON 数组配置
+            patterns: if patterns.is_empty() {
+                Vec::new()
+            } else {
+                serde_json::from_str(&patterns).unwrap_or_else(|e| {
+                    eprintln!("解析 REDACT_PATTERNS 失败: {e}");
+                    Vec::new()
+                })
+            },
+        }
+    }
+}
+
+/// 编译后的规则
+struct Redactor {
+    settings: Redaction,
+    rules: Vec<(Regex, &'static str)>,
+}
+
+impl Redactor {
+    fn compile(settings: Redaction) -> Result<Self, regex::Error> {
+        let mut rules = Vec::with_capacity(settings.detectors.len() + settings.patterns.len());
+        for &detector in &settings.detectors {
+            rules.push((Regex::new(detector.pattern())?, detector.replacement()));
+        }
+        for pattern in &settings.patterns {
+            rules.push((Regex::new(pattern)?, "[REDACTED]"));
+        }
+        Ok(Self { settings, rules })
+    }
+
+    /// 查找所有需要替换的区间，重叠时保留先出现且较长的一个
+    fn spans(&self, text: &str) -> Vec<(usize, usize, &'static str)> {
+        let mut spans: Vec<_> = self
+            .rules
+            .iter()
+            .flat_map(|(regex, replacement)| {
+                regex
+                    .find_iter(text)
+                    .filter(|m| !m.is_empty())
+                    .map(|m| (m.start(), m.end(), *replacement))
+            })
+            .collect();
+        spans.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        let mut end = 0;
+        spans.retain(|&(start, stop, _)| {
+            let keep = start >= end;
+            if keep {
+                end = stop;
+            }
+            keep
+        });
+        spans
+    }
+
+    fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.rules.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let spans = self.spans(text);
+        if spans.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end, replacement) in spans {
+            out.push_str(&text[last..start]);
+            out.push_str(replacement);
+            last = end;
+        }
+        out.push_str(&text[last..]);
+        Cow::Owned(out)
+    }
+
+    /// 脱敏回复文本并重新计算每段的字符数，使延迟信息与文本保持一致
+    fn redact_delays(&self, text: &str, delays: &mut [(u32, f32)]) -> Option<String> {
+        let spans = self.spans(text);
+        if spans.is_empty() {
+            return None;
+        }
+
+        // 原文中各分段的结束位置（字节）
+        let mut ends = text.char_indices().map(|(i, c)| i + c.len_utf8());
+        let mut offset = 0;
+        let boundaries: Vec<usize> = delays
+            .iter()
+            .map(|&(count, _)| {
+                if count != 0 {
+                    offset = ends.nth(count as usize - 1).unwrap_or(text.len());
+                }
+                offset
+            })
+            .collect();
+
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        let mut shifts = Vec::with_capacity(spans.len());
+        for &(start, end, replacement) in &spans {
+            out.push_str(&text[last..start]);
+            out.push_str(replacement);
+            shifts.push((start, end, out.len()));
+            last = end;
+        }
+        out.push_str(&text[last..]);
+
+        // 将原文边界映射到脱敏后的文本，落在替换区间内的边界移到替换内容之后
+        let map = |pos: usize| {
+            let mut delta = 0isize;
+            for &(start, end, new_end) in &shifts {
+                if pos <= start {
+                    break;
+                }
+                if pos < end {
+                    return new_end;
+                }
+                delta = new_end as isize - end as isize;
+            }
+            (pos as isize + delta) as usize
+        };
+        let mut previous = 0;
+        for (delay, boundary) in delays.iter_mut().zip(boundaries) {
+            let mapped = map(boundary).max(previous);
+            delay.0 = out[previous..mapped].chars().count() as u32;
+            previous = mapped;
+        }
+        Some(out)
+    }
+}
+
+static REDACTOR: LazyLock<ArcSwap<Redactor>> = LazyLock::new(|| {
+    let settings = Redaction::from_env();
+    let redactor = Redactor::compile(settings.clone()).unwrap_or_else(|e| {
+        eprintln!("脱敏规则无效，已忽略自定义规则: {e}");
+        __unwrap!(Redactor::compile(Redaction {
+            patterns: Vec::new(),
+            ..settings
+        }))
+    });
+    ArcSwap::from_pointee(redactor)
+});
+
+/// 启动时加载环境变量中的规则
+#[inline]
+pub fn init() { LazyLock::force(&REDACTOR); }
+
+/// 重新读取环境变量中的规则，规则无效时保持原设置
+pub fn reload() -> Result<(), regex::Error> { update(Redaction::from_env()) }
+
+/// 当前脱敏设置
+#[inline]
+pub fn get() -> Redaction { REDACTOR.load().settings.clone() }
+
+/// 更新脱敏设置，规则无效时保持原设置
+pub fn update(settings: Redaction) -> Result<(), regex::Error> {
+    REDACTOR.store(Arc::new(Redactor::compile(settings)?));
+    Ok(())
+}
+
+/// 关闭脱敏
+#[inline]
+pub fn reset() { REDACTOR.store(Arc::new(__unwrap!(Redactor::compile(Redaction::default())))) }
+
+/// 写入日志前脱敏文本
+pub fn log_text(text: String) -> String {
+    let redactor = REDACTOR.load();
+    if !redactor.settings.logs {
+        return text;
+    }
+    match redactor.redact(&text) {
+        Cow::Borrowed(_) => text,
+        Cow::Owned(redacted) => redacted,
+    }
+}
+
+/// 写入日志前脱敏回复文本及其延迟信息
+pub fn log_delays(delays: Option<(String, Vec<(u32, f32)>)>) -> Option<(String, Vec<(u32, f32)>)> {
+    let redactor = REDACTOR.load();
+    if !redactor.settings.logs {
+        return delays;
+    }
+    delays.map(
+        |(text, mut delays)| match redactor.redact_delays(&text, &mut delays) {
+            Some(redacted) => (redacted, delays),
+            None => (text, delays),
+        },
+    )
+}
+
+/// 捕获请求前脱敏文本，与日志使用相同的开关
+pub fn capture_text(text: &mut String) {
+    let redactor = REDACTOR.load();
+    if redactor.settings.logs
+        && let Cow::Owned(redacted) = redactor.redact(text)
+    {
+        *text = redacted;
+    }
+}
+
+/// 发送到上游前脱敏文本
+pub fn outbound_text(text: &mut String) {
+    let redactor = REDACTOR.load();
+    if redactor.settings.outbound
+        && let Cow::Owned(redacted) = redactor.redact(text)
+    {
+        *text = redacted;
+    }
+}
+
+/// 是否需要脱敏上游请求
+#[inline]
+pub fn is_outbound() -> bool { REDACTOR.load().settings.outbound }
+
+/// 是否需要脱敏日志与捕获的请求
+#[inline]
+pub fn is_logs() -> bool { REDACTOR.load().settings.logs }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor() -> Redactor {
+        __unwrap!(Redactor::compile(Redaction {
+            logs: true,
+            outbound: false,
+            detectors: vec![Detector::ApiKey, Detector::Email, Detector::Phone],
+            patterns: vec![r"secret-\d+".to_string()],
+        }))
+    }
+
+    #[test]
+    fn test_builtin_detectors() {
+        let redactor = redactor();
+        assert_eq!(
+            redactor
+                .redact("mail a.b@example.com or call 555-123-4567, key sk-abcdefghijklmnop1234"),
+            "mail [REDACTED:email] or call [REDACTED:phone], key [REDACTED:api_key]"
+        );
+        assert_eq!(
+            redactor.redact("code secret-42 done"),
+            "code [REDACTED] done"
+        );
+        assert!(matches!(
+            redactor.redact("nothing here 12"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_delays_follow_redaction() {
+        let redactor = redactor();
+        let text = "hi a@b.io ok";
+        // 分段: "hi a", "@b.", "io ok"
+        let mut delays = vec![(4, 0.1), (3, 0.2), (5, 0.3)];
+        let redacted = __unwrap!(redactor.redact_delays(text, &mut delays));
+        assert_eq!(redacted, "hi [REDACTED:email] ok");
+        let total: u32 = delays.iter().map(|d| d.0).sum();
+        assert_eq!(total as usize, redacted.chars().count());
+        assert_eq!(delays[0].0, 3 + "[REDACTED:email]".len() as u32);
+    }
+}
@@ -1,36 +1,78 @@
+pub mod backup;
 mod log;
 mod page;
+pub(super) mod storage;
 mod token;
 
 use std::sync::atomic::{AtomicU64, Ordering};
-use tokio::sync::{Mutex, RwLock};
-
-use super::{RequestLog, proxy_pool::Proxies};
+use tokio::sync::{Mutex, RwLock, broadcast};
+
+use super::{
+    AppConfig, Capture, CaptureKind, CapturedResponse, RequestLog, TokenStatsStore,
+    admin::Admins,
+    capture::CaptureStore,
+    proxy_pool::{Proxies, group::ProxyGroups},
+};
 pub use log::LogManager;
 pub use page::{PageContent, Pages};
 pub use token::{TokenError, TokenManager};
 
+/// 日志事件通道容量，订阅者落后超过该数量时丢弃旧事件
+const LOG_EVENTS_CAPACITY: usize = 256;
+
+/// 日志变更事件
+#[derive(Clone)]
+pub enum LogEvent {
+    Created(RequestLog),
+    Updated(RequestLog),
+}
+
 pub struct AppState {
     pub token_manager: RwLock<TokenManager>,
     pub log_manager: Mutex<LogManager>,
     pub total_requests: AtomicU64,
     pub active_requests: AtomicU64,
     pub error_requests: AtomicU64,
+    pub log_events: broadcast::Sender<LogEvent>,
+    pub captures: parking_lot::Mutex<CaptureStore>,
+    pub token_stats: parking_lot::Mutex<TokenStatsStore>,
 }
 
 impl AppState {
     pub async fn load() -> Result<Self, Box<dyn core::error::Error>> {
         // 并行加载日志、令牌和代理
-        let (log_manager_result, token_manager_result, proxies_result) =
-            tokio::join!(LogManager::load(), TokenManager::load(), Proxies::load());
+        let (
+            log_manager_result,
+            token_manager_result,
+            proxies_result,
+            groups_result,
+            token_stats_result,
+            admins_result,
+        ) = tokio::join!(
+            LogManager::load(),
+            TokenManager::load(),
+            Proxies::load(),
+            ProxyGroups::load(),
+            TokenStatsStore::load(),
+            Admins::load()
+        );
 
         // 获取结果，处理错误
         let log_manager = log_manager_result?;
         let token_manager = token_manager_result?;
+        // 凭据无法读取时拒绝启动，避免覆盖原有凭据
+        admins_result?.init();
 
         // 处理代理
         let proxies = proxies_result.unwrap_or_default();
-        proxies.init();
+        // TLS 配置无效时拒绝启动，避免在缺少证书或指纹校验的情况下连接上游
+        proxies.init()?;
+        // 代理组可能已加密，无法读取时拒绝启动，避免以空状态覆盖
+        groups_result?.init();
+        let token_stats = token_stats_result.unwrap_or_else(|e| {
+            eprintln!("加载令牌统计失败: {e}");
+            TokenStatsStore::default()
+        });
 
         // 计算初始统计信息
         let error_count = log_manager.error_count();
@@ -42,6 +84,9 @@ impl AppState {
             total_requests: AtomicU64::new(total_count),
             active_requests: AtomicU64::new(0),
             error_requests: AtomicU64::new(error_count),
+            log_events: broadcast::Sender::new(LOG_EVENTS_CAPACITY),
+            captures: parking_lot::Mutex::new(CaptureStore::default()),
+            token_stats: parking_lot::Mutex::new(token_stats),
         })
     }
 
@@ -70,10 +115,12 @@ impl AppState {
     /// 向请求日志添加新记录
     #[inline]
     pub async fn push_log(&self, log: RequestLog, token: super::ExtToken) {
-        self.log_manager
-            .lock()
-            .await
-            .push_log_with_token(log, token);
+        let mut log_manager = self.log_manager.lock().await;
+        // 持锁发送以保证事件顺序与日志修改顺序一致
+        if log_manager.is_enabled() && self.log_events.receiver_count() != 0 {
+            let _ = self.log_events.send(LogEvent::Created(log.clone()));
+        }
+        log_manager.push_log_with_token(log, token);
     }
 
     /// 获取下一个日志ID
@@ -86,9 +133,48 @@ impl AppState {
     where
         F: FnOnce(&mut RequestLog),
     {
-        self.log_manager.lock().await.update_log(id, f);
+        let mut log_manager = self.log_manager.lock().await;
+        if let Some(log) = log_manager.update_log(id, f)
+            && self.log_events.receiver_count() != 0
+        {
+            let _ = self.log_events.send(LogEvent::Updated(log.clone()));
+        }
     }
 
+    /// 订阅日志变更事件
+    #[inline]
+    pub fn subscribe_logs(&self) -> broadcast::Receiver<LogEvent> { self.log_events.subscribe() }
+
+    /// 保存日志对应的原始请求
+    #[inline]
+    pub fn capture_request(&self, id: u64, kind: CaptureKind, request: bytes::Bytes) {
+        self.captures.lock().insert(Capture {
+            id,
+            kind,
+            request,
+            response: None,
+        });
+    }
+
+    /// 请求完成后从日志中提取响应写入捕获记录
+    pub async fn finish_capture(&self, id: u64) {
+        if !self.captures.lock().contains(id) {
+            return;
+        }
+        let response = {
+            let log_manager = self.log_manager.lock().await;
+            match log_manager.find_log_with_token(id) {
+                Some((log, _)) => CapturedResponse::from_log(log),
+                None => return,
+            }
+        };
+        self.captures.lock().complete(id, response);
+    }
+
+    /// 获取指定日志的捕获记录
+    #[inline]
+    pub fn get_capture(&self, id: u64) -> Option<Capture> { self.captures.lock().get(id).cloned() }
+
     /// 获取TokenManager的读锁
     #[inline]
     pub async fn token_manager_read(&self) -> tokio::sync::RwLockReadGuard<'_, TokenManager> {
@@ -102,22 +188,53 @@ impl AppState {
     }
 
     pub async fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // 并行保存日志、令牌和代理
-        let (log_result, tokens_result, proxies_result) =
-            tokio::join!(self.save_logs(), self.save_tokens(), Proxies::save());
+        // 并行保存日志、令牌和代理，错误转为字符串以便在后台任务中保存
+        let to_string = |e: Box<dyn std::error::Error>| e.to_string();
+        let (log_result, tokens_result, proxies_result, groups_result, admins_result) = tokio::join!(
+            async { self.save_logs().await.map_err(to_string) },
+            async { self.save_tokens().await.map_err(to_string) },
+            async { Proxies::save().await.map_err(to_string) },
+            async { ProxyGroups::save().await.map_err(to_string) },
+            async { Admins::save().await.map_err(to_string) }
+        );
 
         log_result?;
         tokens_result?;
         proxies_result?;
+        groups_result?;
+        admins_result?;
         Ok(())
     }
 
     async fn save_logs(&self) -> Result<(), Box<dyn std::error::Error>> {
-        self.log_manager.lock().await.save().await
+        let mut log_manager = self.log_manager.lock().await;
+        log_manager.apply_retention(&AppConfig::get_log_retention());
+        log_manager.save().await
+    }
+
+    /// 按当前保留策略清理日志，返回移除的日志数量
+    pub async fn apply_log_retention(&self) -> usize {
+        let removed = self
+            .log_manager
+            .lock()
+            .await
+            .apply_retention(&AppConfig::get_log_retention());
+        if removed != 0 {
+            println!("日志保留策略已移除 {removed} 条日志");
+        }
+        removed
     }
 
     async fn save_tokens(&self) -> Result<(), Box<dyn std::error::Error>> {
-        self.token_manager.read().await.save().await
+        let token_manager = self.token_manager.read().await;
+        token_manager.save().await?;
+        // 统计随令牌一同保存，并清理已删除令牌的记录
+        let bytes = {
+            let mut token_stats = self.token_stats.lock();
+            token_stats.retain(|key| token_manager.id_map().contains_key(key));
+            token_stats.to_bytes()?
+        };
+        TokenStatsStore::save(&bytes).await
     }
 
     /// 更新token manager中的client key
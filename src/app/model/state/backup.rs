@@ -0,0 +1,354 @@
+//! 全部持久化状态的在线备份与恢复
+
+use super::{AppState, LogManager, TokenManager, storage};
+use crate::app::{
+    constant::PKG_VERSION,
+    lazy::{BACKUP_RETENTION, BACKUPS_DIR},
+    model::{
+        AppConfig, DateTime, TokenStatsStore,
+        admin::Admins,
+        audit,
+        proxy_pool::{Proxies, group::ProxyGroups},
+    },
+};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use rkyv::util::AlignedVec;
+use sha2::Digest as _;
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+const BACKUP_FORMAT: &str = "cursor-api-backup";
+/// 备份格式版本，结构变化时递增
+const BACKUP_VERSION: u32 = 3;
+const BACKUP_PREFIX: &str = "backup-";
+const BACKUP_SUFFIX: &str = ".backup";
+
+// 备份中的文件名，与数据目录中的文件一致
+const TOKENS: &str = "tokens.bin";
+const LOGS: &str = "logs.bin";
+const TOKEN_STATS: &str = "token_stats.bin";
+const PROXIES: &str = "proxies.bin";
+const PROXY_GROUPS: &str = "proxy_groups.bin";
+const CONFIG: &str = "config.bin";
+const ADMINS: &str = "admins.bin";
+const AUDIT: &str = "audit.log";
+
+#[derive(Debug)]
+pub enum BackupError {
+    /// 备份文件无效，当前状态未被修改
+    Invalid(Cow<'static, str>),
+    /// 备份不存在
+    NotFound,
+    Internal(String),
+}
+
+impl std::error::Error for BackupError {}
+
+impl std::fmt::Display for BackupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(message) => f.write_str(message),
+            Self::NotFound => f.write_str("备份不存在"),
+            Self::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+#[inline]
+fn internal(e: impl std::fmt::Display) -> BackupError { BackupError::Internal(e.to_string()) }
+
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+struct BackupFile {
+    name: String,
+    /// 十六进制
+    sha256: String,
+    /// Base64
+    data: String,
+}
+
+/// 全部持久化状态的快照
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+pub struct BackupArchive {
+    format: String,
+    version: u32,
+    /// 数据布局随程序版本变化，仅能恢复同一版本生成的备份
+    app_version: String,
+    created_at: DateTime,
+    files: Vec<BackupFile>,
+}
+
+#[inline]
+fn sha256(data: &[u8]) -> String { hex::encode(sha2::Sha256::digest(data)) }
+
+impl BackupArchive {
+    fn new(files: [(&str, AlignedVec); 8]) -> Self {
+        Self {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            app_version: PKG_VERSION.to_string(),
+            created_at: DateTime::now(),
+            files: files
+                .into_iter()
+                .map(|(name, data)| BackupFile {
+                    name: name.to_string(),
+                    sha256: sha256(&data),
+                    data: STANDARD.encode(&data),
+                })
+                .collect(),
+        }
+    }
+
+    /// 校验并取出文件内容，按对齐要求复制
+    fn into_files(self) -> Result<ahash::HashMap<String, AlignedVec>, BackupError> {
+        let invalid = |message: String| BackupError::Invalid(Cow::Owned(message));
+        if self.format != BACKUP_FORMAT {
+            return Err(invalid(format!("未知格式: {}", self.format)));
+        }
+        if self.version != BACKUP_VERSION {
+            return Err(invalid(format!("不支持的备份版本: {}", self.version)));
+        }
+        if self.app_version != PKG_VERSION {
+            return Err(invalid(format!(
+                "备份由 v{} 生成，与当前版本 v{PKG_VERSION} 不一致",
+                self.app_version
+            )));
+        }
+
+        let mut files = ahash::HashMap::default();
+        for file in self.files {
+            let data = STANDARD
+                .decode(&file.data)
+                .map_err(|e| invalid(format!("{}: {e}", file.name)))?;
+            if sha256(&data) != file.sha256.to_ascii_lowercase() {
+                return Err(invalid(format!("{} 校验失败", file.name)));
+            }
+            let mut bytes = AlignedVec::with_capacity(data.len());
+            bytes.extend_from_slice(&data);
+            files.insert(file.name, bytes);
+        }
+        if let Some(name) = [
+            TOKENS,
+            LOGS,
+            TOKEN_STATS,
+            PROXIES,
+            PROXY_GROUPS,
+            CONFIG,
+            ADMINS,
+            AUDIT,
+        ]
+        .into_iter()
+        .find(|name| !files.contains_key(*name))
+        {
+            return Err(invalid(format!("缺少 {name}")));
+        }
+        Ok(files)
+    }
+}
+
+/// 恢复后的状态概要
+#[derive(::serde::Serialize)]
+pub struct RestoreSummary {
+    created_at: DateTime,
+    tokens_count: usize,
+    logs_count: u64,
+}
+
+impl AppState {
+    /// 生成所有持久化状态的时间点一致的快照
+    pub async fn backup(&self) -> Result<BackupArchive, BackupError> {
+        // 同时持有令牌与日志的锁，期间的请求无法修改状态
+        let token_manager = self.token_manager.read().await;
+        let log_manager = self.log_manager.lock().await;
+        let tokens = token_manager.to_bytes().map_err(internal)?;
+        let logs = log_manager.to_bytes().map_err(internal)?;
+        let token_stats = self.token_stats.lock().to_bytes().map_err(internal)?;
+        let proxies = Proxies::to_bytes().map_err(internal)?;
+        let proxy_groups = ProxyGroups::to_bytes().map_err(internal)?;
+        let config = AppConfig::to_bytes().map_err(internal)?;
+        let admins = Admins::to_bytes().map_err(internal)?;
+        let mut audit = AlignedVec::new();
+        audit.extend_from_slice(&audit::snapshot().await.map_err(internal)?);
+        drop(log_manager);
+        drop(token_manager);
+
+        Ok(BackupArchive::new([
+            (TOKENS, tokens),
+            (LOGS, logs),
+            (TOKEN_STATS, token_stats),
+            (PROXIES, proxies),
+            (PROXY_GROUPS, proxy_groups),
+            (CONFIG, config),
+            (ADMINS, admins),
+            (AUDIT, audit),
+        ]))
+    }
+
+    /// 校验备份并替换当前状态，任一文件无效时不做任何修改
+    pub async fn restore(&self, archive: BackupArchive) -> Result<RestoreSummary, BackupError> {
+        let created_at = archive.created_at;
+        let mut files = archive.into_files()?;
+        let invalid = |name: &str, e: &dyn std::fmt::Display| {
+            BackupError::Invalid(Cow::Owned(format!("{name}: {e}")))
+        };
+
+        // 先解析全部数据
+        let logs_limit = self.log_manager.lock().await.logs_limit();
+        // 加载时校验归档结构，校验和只用于发现传输中的损坏
+        let (token_manager, log_manager, token_stats, proxies, proxy_groups, config, admins) = (
+            TokenManager::from_bytes(&files[TOKENS]).map_err(|e| invalid(TOKENS, &e))?,
+            if logs_limit.should_log() {
+                LogManager::from_bytes(&files[LOGS], logs_limit).map_err(|e| invalid(LOGS, &e))?
+            } else {
+                LogManager::new(logs_limit)
+            },
+            TokenStatsStore::from_bytes(&files[TOKEN_STATS])
+                .map_err(|e| invalid(TOKEN_STATS, &e))?,
+            Proxies::from_bytes(&files[PROXIES]).map_err(|e| invalid(PROXIES, &e))?,
+            ProxyGroups::from_bytes(&files[PROXY_GROUPS]).map_err(|e| invalid(PROXY_GROUPS, &e))?,
+            AppConfig::from_bytes(&files[CONFIG]).map_err(|e| invalid(CONFIG, &e))?,
+            Admins::from_bytes(&files[ADMINS]).map_err(|e| invalid(ADMINS, &e))?,
+        );
+        // 审计记录只补回缺失的部分，无需解析
+        let audit_log = files.remove(AUDIT).unwrap_or_default();
+        drop(files);
+
+        let summary = RestoreSummary {
+            created_at,
+            tokens_count: token_manager.tokens().iter().flatten().count(),
+            logs_count: log_manager.total_count(),
+        };
+
+        // 同时替换全部状态
+        {
+            let mut current_tokens = self.token_manager.write().await;
+            let mut current_logs = self.log_manager.lock().await;
+            // 唯一可能失败的替换放在最前，失败时其余状态保持不变
+            proxy_groups.update_global().map_err(internal)?;
+            self.total_requests
+                .store(log_manager.total_count(), Ordering::Relaxed);
+            self.error_requests
+                .store(log_manager.error_count(), Ordering::Relaxed);
+            *current_tokens = token_manager;
+            *current_logs = log_manager;
+            *self.token_stats.lock() = token_stats;
+            proxies.update_global();
+            AppConfig::set_persisted(config);
+            admins.update_global();
+        }
+
+        // 重建代理客户端并写入数据目录，逐个保存以便在请求处理中调用
+        Proxies::update_and_save().await.map_err(internal)?;
+        ProxyGroups::save().await.map_err(internal)?;
+        Admins::save().await.map_err(internal)?;
+        self.token_manager
+            .read()
+            .await
+            .save()
+            .await
+            .map_err(internal)?;
+        let token_stats = self.token_stats.lock().to_bytes().map_err(internal)?;
+        TokenStatsStore::save(&token_stats)
+            .await
+            .map_err(internal)?;
+        self.log_manager
+            .lock()
+            .await
+            .save()
+            .await
+            .map_err(internal)?;
+        AppConfig::save().map_err(internal)?;
+        audit::restore(&audit_log).await.map_err(internal)?;
+
+        Ok(summary)
+    }
+
+    /// 在数据目录中创建备份，并按保留数量清理旧备份
+    pub async fn backup_to_disk(&self) -> Result<String, BackupError> {
+        let archive = self.backup().await?;
+        let bytes = serde_json::to_vec(&archive).map_err(internal)?;
+        // 使用精确到毫秒的 UTC 时间命名，保证按名称排序即按时间排序
+        let name = format!(
+            "{BACKUP_PREFIX}{}{BACKUP_SUFFIX}",
+            backup_time().format("%Y%m%d-%H%M%S-%3f")
+        );
+        tokio::fs::create_dir_all(&*BACKUPS_DIR)
+            .await
+            .map_err(internal)?;
+        storage::write(&BACKUPS_DIR.join(&name), &bytes)
+            .await
+            .map_err(internal)?;
+
+        let backups = list_backups().await?;
+        for backup in backups.iter().skip(*BACKUP_RETENTION) {
+            if let Err(e) = tokio::fs::remove_file(BACKUPS_DIR.join(&backup.name)).await {
+                eprintln!("删除旧备份 {} 失败: {e}", backup.name);
+            }
+        }
+        Ok(name)
+    }
+}
+
+/// 备份时间，同一毫秒内的多次备份依次顺延，避免覆盖
+fn backup_time() -> chrono::DateTime<chrono::Utc> {
+    static LAST: AtomicI64 = AtomicI64::new(i64::MIN);
+    let now = DateTime::utc_now().timestamp_millis();
+    let next = |last: i64| now.max(last.saturating_add(1));
+    let previous = LAST
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+            Some(next(last))
+        })
+        .unwrap_or_else(|last| last);
+    chrono::DateTime::from_timestamp_millis(next(previous)).unwrap_or_default()
+}
+
+/// 数据目录中的备份
+#[derive(::serde::Serialize)]
+pub struct BackupEntry {
+    name: String,
+    size: u64,
+}
+
+#[inline]
+fn is_backup_name(name: &str) -> bool {
+    name.starts_with(BACKUP_PREFIX)
+        && name.ends_with(BACKUP_SUFFIX)
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+}
+
+/// 列出数据目录中的备份，新的在前
+pub async fn list_backups() -> Result<Vec<BackupEntry>, BackupError> {
+    let mut dir = match tokio::fs::read_dir(&*BACKUPS_DIR).await {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(internal(e)),
+    };
+    let mut backups = Vec::new();
+    while let Some(entry) = dir.next_entry().await.map_err(internal)? {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if is_backup_name(&name) {
+            let size = entry.metadata().await.map_err(internal)?.len();
+            backups.push(BackupEntry { name, size });
+        }
+    }
+    // 名称包含创建时间
+    backups.sort_unstable_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// 读取数据目录中的备份
+pub async fn read_backup(name: &str) -> Result<BackupArchive, BackupError> {
+    if !is_backup_name(name) {
+        return Err(BackupError::NotFound);
+    }
+    let bytes = storage::read(&BACKUPS_DIR.join(name))
+        .await
+        .map_err(internal)?
+        .ok_or(BackupError::NotFound)?;
+    serde_json::from_slice(&bytes).map_err(|e| BackupError::Invalid(Cow::Owned(e.to_string())))
+}
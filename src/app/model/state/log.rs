@@ -1,16 +1,26 @@
 use ahash::HashMap;
-use memmap2::{MmapMut, MmapOptions};
 use rkyv::{
     Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize, rancor::Error as RkyvError,
 };
 use std::collections::VecDeque;
-use tokio::fs::OpenOptions;
 
 use crate::app::{
     lazy::LOGS_FILE_PATH,
-    model::{ExtToken, ExtTokenHelper, RequestLog, TokenKey, log::RequestLogHelper},
+    model::{
+        DateTime, ExtToken, ExtTokenHelper, LogRetention, LogStatus, RequestLog, TokenKey,
+        log::{RequestLogHelper, legacy},
+    },
 };
 
+/// 日志文件头：`MAGIC | 版本(u32 LE) | 保留`，长度为 16 以保持其后数据的对齐
+const MAGIC: &[u8; 8] = b"CAPILOGS";
+const HEADER_LEN: usize = 16;
+/// 日志格式的版本，修改 [`RequestLogHelper`] 的布局时递增并保留旧版本的读取
+///
+/// - 无版本头：最初的格式，错误信息没有代理变体，没有客户端地址
+/// - 1：增加代理错误与客户端地址
+const VERSION: u32 = 1;
+
 /// 请求日志限制枚举
 #[derive(Debug, Clone, Copy)]
 pub enum RequestLogsLimit {
@@ -56,6 +66,13 @@ struct LogManagerHelper {
     tokens: HashMap<TokenKey, ExtTokenHelper>,
 }
 
+/// 没有版本头的旧格式
+#[derive(Archive, RkyvDeserialize, RkyvSerialize)]
+struct LegacyLogManagerHelper {
+    logs: Vec<legacy::RequestLogHelper>,
+    tokens: HashMap<TokenKey, ExtTokenHelper>,
+}
+
 /// 日志管理器，负责处理日志和token的集中管理
 pub struct LogManager {
     logs: VecDeque<RequestLog>,
@@ -79,20 +96,58 @@ impl LogManager {
     /// 从存储中加载日志
     #[inline(never)]
     pub async fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        let logs_limit = RequestLogsLimit::from_usize(crate::common::utils::parse_from_env(
-            "REQUEST_LOGS_LIMIT",
-            100,
-        ));
+        let logs_limit =
+            RequestLogsLimit::from_usize(crate::app::settings::get::<usize>("REQUEST_LOGS_LIMIT"));
 
         // 如果禁用日志，则返回空管理器
         if !logs_limit.should_log() {
             return Ok(Self::new(logs_limit));
         }
 
-        let (logs, tokens) = Self::load_data_from_file().await?;
+        let Some(bytes) = super::storage::read(&LOGS_FILE_PATH).await? else {
+            return Ok(Self::new(logs_limit));
+        };
+        Self::from_bytes(&bytes, logs_limit)
+    }
+
+    /// 从序列化数据恢复日志管理器
+    ///
+    /// 没有版本头的文件按最初的格式读取
+    #[inline(never)]
+    pub fn from_bytes(
+        bytes: &[u8],
+        logs_limit: RequestLogsLimit,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (logs, tokens): (VecDeque<RequestLog>, _) = match bytes.strip_prefix(MAGIC) {
+            Some(rest) => {
+                let version = rest
+                    .first_chunk::<4>()
+                    .map(|version| u32::from_le_bytes(*version))
+                    .ok_or("日志文件已损坏")?;
+                if version != VERSION {
+                    return Err(format!("不支持的日志格式版本 {version}，请升级程序").into());
+                }
+                let helper =
+                    ::rkyv::from_bytes::<LogManagerHelper, RkyvError>(&bytes[HEADER_LEN..])?;
+                let logs = helper
+                    .logs
+                    .into_iter()
+                    .map(RequestLogHelper::into_request_log);
+                (logs.collect(), helper.tokens)
+            }
+            None => {
+                let helper = ::rkyv::from_bytes::<LegacyLogManagerHelper, RkyvError>(bytes)?;
+                let logs = helper
+                    .logs
+                    .into_iter()
+                    .map(legacy::RequestLogHelper::into_request_log);
+                (logs.collect(), helper.tokens)
+            }
+        };
+
         let mut manager = Self {
             logs,
-            tokens,
+            tokens: tokens.into_iter().map(|(k, v)| (k, v.extract())).collect(),
             token_ref_counts: HashMap::default(),
             logs_limit,
         };
@@ -103,39 +158,9 @@ impl LogManager {
         Ok(manager)
     }
 
-    /// 从文件加载数据
-    #[inline(never)]
-    async fn load_data_from_file()
-    -> Result<(VecDeque<RequestLog>, HashMap<TokenKey, ExtToken>), Box<dyn std::error::Error>> {
-        let file = match OpenOptions::new().read(true).open(&*LOGS_FILE_PATH).await {
-            Ok(file) => file,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return Ok((VecDeque::new(), HashMap::default()));
-            }
-            Err(e) => return Err(Box::new(e)),
-        };
-
-        if file.metadata().await?.len() > usize::MAX as u64 {
-            return Err("日志文件过大".into());
-        }
-
-        let mmap = unsafe { MmapOptions::new().map(&file)? };
-        let helper = unsafe { ::rkyv::from_bytes_unchecked::<LogManagerHelper, RkyvError>(&mmap) }?;
-
-        let logs = helper
-            .logs
-            .into_iter()
-            .map(RequestLogHelper::into_request_log)
-            .collect();
-
-        let tokens = helper
-            .tokens
-            .into_iter()
-            .map(|(k, v)| (k, v.extract()))
-            .collect();
-
-        Ok((logs, tokens))
-    }
+    /// 获取日志限制
+    #[inline(always)]
+    pub fn logs_limit(&self) -> RequestLogsLimit { self.logs_limit }
 
     /// 重建token引用计数
     #[inline(never)]
@@ -221,14 +246,9 @@ impl LogManager {
         self.logs.push_back(log);
     }
 
-    /// 保存数据到文件
+    /// 序列化日志与相关token
     #[inline(never)]
-    pub async fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // 如果禁用日志，则跳过保存
-        if !self.logs_limit.should_log() {
-            return Ok(());
-        }
-
+    pub fn to_bytes(&self) -> Result<::rkyv::util::AlignedVec, Box<dyn std::error::Error>> {
         let helper = LogManagerHelper {
             logs: self.logs.iter().map(RequestLogHelper::from).collect(),
             tokens: self
@@ -238,26 +258,82 @@ impl LogManager {
                 .collect(),
         };
 
-        let bytes = ::rkyv::to_bytes::<RkyvError>(&helper)?;
-
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&*LOGS_FILE_PATH)
-            .await?;
-
+        let mut bytes = ::rkyv::util::AlignedVec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.resize(HEADER_LEN, 0);
+        let bytes = ::rkyv::api::high::to_bytes_in::<_, RkyvError>(&helper, bytes)?;
         if bytes.len() > usize::MAX >> 1 {
             return Err("日志数据过大".into());
         }
+        Ok(bytes)
+    }
+
+    /// 保存数据到文件
+    #[inline(never)]
+    pub async fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        // 如果禁用日志，则跳过保存
+        if !self.logs_limit.should_log() {
+            return Ok(());
+        }
+
+        let bytes = self.to_bytes()?;
+        super::storage::write(&LOGS_FILE_PATH, &bytes).await
+    }
+
+    /// 按保留策略清理日志，返回移除的日志数
+    #[inline(never)]
+    pub fn apply_retention(&mut self, policy: &LogRetention) -> usize {
+        if policy.is_unlimited() || self.logs.is_empty() {
+            return 0;
+        }
+
+        let now = DateTime::naive_now();
+        let age_of = |log: &RequestLog| {
+            now.signed_duration_since(log.timestamp.naive())
+                .num_seconds()
+                .max(0) as u64
+        };
+        let before = self.logs.len();
+        let mut removed = Vec::new();
+
+        // 按状态过期
+        self.logs.retain(|log| {
+            let expired = policy
+                .max_age_of(log.status)
+                .is_some_and(|max_age| age_of(log) > max_age);
+            if expired {
+                removed.push(log.token_key());
+            }
+            !expired
+        });
+
+        // 移除过期的大字段，进行中的请求仍在写入，跳过
+        if policy.content_max_age != 0 {
+            for log in &mut self.logs {
+                if log.status != LogStatus::Pending && age_of(log) > policy.content_max_age {
+                    log.strip_content();
+                }
+            }
+        }
 
-        file.set_len(bytes.len() as u64).await?;
-        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
-        mmap.copy_from_slice(&bytes);
-        mmap.flush()?;
+        // 按字节预算从最旧的日志开始移除
+        if policy.max_bytes != 0 {
+            let budget = policy.max_bytes as usize;
+            let mut size: usize = self.logs.iter().map(RequestLog::estimated_size).sum();
+            while size > budget
+                && let Some(log) = self.logs.pop_front()
+            {
+                size -= log.estimated_size();
+                removed.push(log.token_key());
+            }
+        }
 
-        Ok(())
+        for key in removed {
+            self.decrement_token_ref(key);
+        }
+
+        before - self.logs.len()
     }
 
     /// 获取日志的只读引用
@@ -288,15 +364,15 @@ impl LogManager {
     #[inline]
     pub fn next_log_id(&self) -> u64 { self.logs.back().map_or(1, |log| log.id + 1) }
 
-    /// 查找指定ID的日志并修改
+    /// 查找指定ID的日志并修改，返回修改后的日志
     #[inline]
-    pub fn update_log<F>(&mut self, id: u64, f: F)
+    pub fn update_log<F>(&mut self, id: u64, f: F) -> Option<&RequestLog>
     where
         F: FnOnce(&mut RequestLog),
     {
-        if let Some(log) = self.logs.iter_mut().rev().find(|log| log.id == id) {
-            f(log);
-        }
+        let log = self.logs.iter_mut().rev().find(|log| log.id == id)?;
+        f(log);
+        Some(log)
     }
 
     // /// 移除指定ID的日志
@@ -403,3 +479,89 @@ impl LogManager {
     //     })
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::model::{ErrorInfo, LogTokenInfo, Randomness, TimingInfo, UserId};
+
+    fn sample_log(error: ErrorInfo) -> RequestLog {
+        static TZ_INIT: std::sync::Once = std::sync::Once::new();
+        TZ_INIT.call_once(crate::app::model::tz::__init);
+
+        RequestLog {
+            id: 1,
+            timestamp: DateTime::now(),
+            model: "gpt-4o",
+            token_info: LogTokenInfo {
+                key: TokenKey {
+                    user_id: UserId::from_bytes([1; 16]),
+                    randomness: Randomness::from_bytes([2; 8]),
+                },
+                stripe: None,
+            },
+            chain: None,
+            timing: TimingInfo { total: 1.5 },
+            stream: false,
+            status: LogStatus::Failure,
+            error,
+            client_ip: Some([127, 0, 0, 1].into()),
+        }
+    }
+
+    #[test]
+    fn test_versioned_layout() {
+        let mut manager = LogManager::new(RequestLogsLimit::Unlimited);
+        manager.logs.push_back(sample_log(ErrorInfo::Proxy {
+            proxy: "hk",
+            error: "connect timeout",
+            details: Some("tcp connect error"),
+        }));
+        let bytes = manager.to_bytes().unwrap();
+        assert_eq!(&bytes[..MAGIC.len()], MAGIC);
+        assert_eq!(bytes[MAGIC.len()..MAGIC.len() + 4], VERSION.to_le_bytes());
+
+        let archived =
+            ::rkyv::access::<ArchivedLogManagerHelper, RkyvError>(&bytes[HEADER_LEN..]).unwrap();
+        // 字符串池在测试中未初始化，比较重新序列化的结果而不转换为 RequestLog
+        let log: RequestLogHelper = ::rkyv::deserialize::<_, RkyvError>(&archived.logs[0]).unwrap();
+        assert_eq!(
+            ::rkyv::to_bytes::<RkyvError>(&log).unwrap().as_slice(),
+            ::rkyv::to_bytes::<RkyvError>(&RequestLogHelper::from(&manager.logs[0]))
+                .unwrap()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_legacy_layout() {
+        let legacy = LegacyLogManagerHelper {
+            logs: vec![legacy::RequestLogHelper {
+                id: 1,
+                timestamp: chrono::NaiveDateTime::default(),
+                model: "default".to_string(),
+                token_info: sample_log(ErrorInfo::None).token_info,
+                chain: None,
+                timing: TimingInfo { total: 1.5 },
+                stream: false,
+                status: LogStatus::Failure,
+                error: legacy::ErrorInfoHelper::None,
+            }],
+            tokens: HashMap::default(),
+        };
+        let bytes = ::rkyv::to_bytes::<RkyvError>(&legacy).unwrap();
+
+        // 没有版本头的文件按旧格式读取，客户端地址为空
+        let manager = LogManager::from_bytes(&bytes, RequestLogsLimit::Unlimited).unwrap();
+        assert_eq!(manager.logs.len(), 1);
+        let log = &manager.logs[0];
+        assert_eq!((log.id, log.model), (1, "default"));
+        assert!(log.client_ip.is_none());
+
+        // 再次保存时写入版本头
+        let bytes = manager.to_bytes().unwrap();
+        assert_eq!(&bytes[..MAGIC.len()], MAGIC);
+        let manager = LogManager::from_bytes(&bytes, RequestLogsLimit::Unlimited).unwrap();
+        assert_eq!(manager.logs.len(), 1);
+    }
+}
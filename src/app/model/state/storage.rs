@@ -0,0 +1,201 @@
+//! 状态文件的读写，配置数据密钥时使用 AES-256-GCM 加密
+//!
+//! 加密文件格式：`MAGIC | 密钥指纹 | nonce | 密文与认证标签`，
+//! 文件名作为附加认证数据，防止不同的状态文件相互替换。
+
+use crate::{
+    app::constant::EMPTY_STRING,
+    common::utils::{
+        crypto::{self, KEY_LEN, NONCE_LEN},
+        parse_from_env,
+    },
+};
+use memmap2::{Mmap, MmapMut};
+use rkyv::util::AlignedVec;
+use std::{error::Error, ops::Deref, path::Path, sync::LazyLock};
+use tokio::fs::OpenOptions;
+
+const MAGIC: &[u8; 8] = b"CAPIENC1";
+const FINGERPRINT_LEN: usize = 8;
+const HEADER_LEN: usize = MAGIC.len() + FINGERPRINT_LEN + NONCE_LEN;
+/// 数据密钥应具有足够的随机性，固定盐仅用于将任意长度的密钥转换为 256 位
+const KEY_SALT: &[u8] = b"cursor-api/data-at-rest";
+
+struct DataKey {
+    key: [u8; KEY_LEN],
+    fingerprint: [u8; FINGERPRINT_LEN],
+}
+
+impl DataKey {
+    fn derive(secret: &str) -> Result<Self, String> {
+        let key = crypto::derive_key(secret.as_bytes(), KEY_SALT, crypto::DEFAULT_ITERATIONS)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            fingerprint: crypto::fingerprint(&key),
+            key,
+        })
+    }
+}
+
+struct DataKeys {
+    /// 写入时使用，为空则以明文保存
+    current: Option<DataKey>,
+    /// 仅用于读取，使用这些密钥的文件在下次保存时改用当前密钥
+    previous: Vec<DataKey>,
+}
+
+fn load_keys() -> Result<DataKeys, String> {
+    let secret = parse_from_env("DATA_ENCRYPTION_KEY", EMPTY_STRING);
+    let key_file = parse_from_env("DATA_ENCRYPTION_KEY_FILE", EMPTY_STRING);
+    let secret = match (secret.is_empty(), key_file.is_empty()) {
+        (true, true) => None,
+        (false, true) => Some(secret.into_owned()),
+        (true, false) => {
+            let content = std::fs::read_to_string(&*key_file)
+                .map_err(|e| format!("无法读取数据密钥文件 {key_file}: {e}"))?;
+            let content = content.trim();
+            if content.is_empty() {
+                return Err(format!("数据密钥文件 {key_file} 为空"));
+            }
+            Some(content.to_string())
+        }
+        (false, false) => {
+            return Err(
+                "DATA_ENCRYPTION_KEY 与 DATA_ENCRYPTION_KEY_FILE 只能设置其中之一".to_string(),
+            );
+        }
+    };
+
+    let previous = parse_from_env("DATA_ENCRYPTION_PREVIOUS_KEYS", EMPTY_STRING);
+    Ok(DataKeys {
+        current: secret.as_deref().map(DataKey::derive).transpose()?,
+        previous: previous
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(DataKey::derive)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+static DATA_KEYS: LazyLock<Result<DataKeys, String>> = LazyLock::new(load_keys);
+
+#[inline]
+fn data_keys() -> Result<&'static DataKeys, Box<dyn Error>> {
+    DATA_KEYS.as_ref().map_err(|e| e.clone().into())
+}
+
+#[inline]
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+}
+
+#[inline]
+fn aad(name: &str) -> Vec<u8> { [MAGIC.as_slice(), name.as_bytes()].concat() }
+
+/// 读取到的文件内容，满足 rkyv 的对齐要求
+pub enum Bytes {
+    Mapped(Mmap),
+    Decrypted(AlignedVec),
+}
+
+impl Deref for Bytes {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Mapped(mmap) => mmap,
+            Self::Decrypted(bytes) => bytes,
+        }
+    }
+}
+
+/// 读取状态文件，文件不存在时返回 `None`
+///
+/// 文件已加密但没有对应的密钥时返回错误，避免以空状态启动后覆盖原有数据
+pub async fn read(path: &Path) -> Result<Option<Bytes>, Box<dyn Error>> {
+    let keys = data_keys()?;
+    let name = file_name(path);
+    let file = match OpenOptions::new().read(true).open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Box::new(e)),
+    };
+
+    if file.metadata().await?.len() > usize::MAX as u64 {
+        return Err(format!("{name} 过大").into());
+    }
+
+    let mmap = unsafe { Mmap::map(&file)? };
+    if !mmap.starts_with(MAGIC) {
+        if keys.current.is_some() {
+            println!("{name} 未加密，将在下次保存时加密");
+        }
+        return Ok(Some(Bytes::Mapped(mmap)));
+    }
+    if mmap.len() < HEADER_LEN {
+        return Err(format!("{name} 已损坏").into());
+    }
+
+    let (fingerprint, rest) = mmap[MAGIC.len()..].split_at(FINGERPRINT_LEN);
+    let (nonce, data) = rest.split_at(NONCE_LEN);
+    let Some(key) = keys
+        .current
+        .iter()
+        .chain(&keys.previous)
+        .find(|key| key.fingerprint == fingerprint)
+    else {
+        return Err(if keys.current.is_none() && keys.previous.is_empty() {
+            format!("{name} 已加密，请设置 DATA_ENCRYPTION_KEY 或 DATA_ENCRYPTION_KEY_FILE")
+        } else {
+            format!(
+                "{name} 使用的数据密钥（指纹 {}）未配置，请检查 DATA_ENCRYPTION_KEY 与 DATA_ENCRYPTION_PREVIOUS_KEYS",
+                hex::encode(fingerprint)
+            )
+        }
+        .into());
+    };
+
+    let plaintext = crypto::open(&key.key, nonce, &aad(name), data.to_vec())
+        .map_err(|_| format!("{name} 解密失败，文件已损坏"))?;
+    match &keys.current {
+        Some(current) if current.fingerprint == key.fingerprint => {}
+        Some(_) => println!("{name} 使用旧密钥加密，将在下次保存时使用当前密钥重新加密"),
+        None => println!("{name} 将在下次保存时以明文保存"),
+    }
+
+    let mut bytes = AlignedVec::with_capacity(plaintext.len());
+    bytes.extend_from_slice(&plaintext);
+    Ok(Some(Bytes::Decrypted(bytes)))
+}
+
+/// 写入状态文件，配置了数据密钥时加密
+pub async fn write(path: &Path, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    let sealed;
+    let bytes = match &data_keys()?.current {
+        Some(key) => {
+            let (nonce, data) = crypto::seal(&key.key, &aad(file_name(path)), bytes.to_vec())?;
+            sealed = [MAGIC.as_slice(), &key.fingerprint, &nonce, &data].concat();
+            &sealed
+        }
+        None => bytes,
+    };
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .await?;
+    file.set_len(bytes.len() as u64).await?;
+
+    let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+    mmap.copy_from_slice(bytes);
+    mmap.flush()?;
+
+    Ok(())
+}
@@ -1,7 +1,5 @@
 use ahash::HashMap;
-use memmap2::{Mmap, MmapMut};
 use std::{borrow::Cow, collections::VecDeque, error::Error};
-use tokio::fs::OpenOptions;
 
 use crate::app::{
     constant::{UNNAMED, UNNAMED_PATTERN},
@@ -186,9 +184,9 @@ impl TokenManager {
 
     pub fn id_to_alias(&self) -> &Vec<Option<Alias>> { &self.id_to_alias }
 
-    /// 列出所有Token
-    #[inline(never)] // 涉及遍历和分配
-    pub fn list(&self) -> Vec<(usize, Alias, TokenInfo)> {
+    /// 遍历所有Token，不复制数据
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Alias, &TokenInfo)> {
         self.tokens
             .iter()
             .enumerate()
@@ -200,10 +198,9 @@ impl TokenManager {
                             .as_ref()
                             .unwrap_unchecked()
                     };
-                    (id, alias.clone(), token.clone())
+                    (id, alias, token)
                 })
             })
-            .collect()
     }
 
     #[inline(always)]
@@ -214,9 +211,8 @@ impl TokenManager {
         }
     }
 
-    /// 持久化Token管理器
-    #[inline(never)]
-    pub async fn save(&self) -> Result<(), Box<dyn Error>> {
+    /// 序列化Token管理器
+    pub fn to_bytes(&self) -> Result<::rkyv::util::AlignedVec, Box<dyn Error>> {
         let helpers: Vec<TokenInfoHelper> = self
             .tokens
             .iter()
@@ -240,42 +236,12 @@ impl TokenManager {
         if bytes.len() > usize::MAX >> 1 {
             return Err("Token数据过大".into());
         }
-
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&*TOKENS_FILE_PATH)
-            .await?;
-        file.set_len(bytes.len() as u64).await?;
-
-        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
-        mmap.copy_from_slice(&bytes);
-        mmap.flush()?;
-
-        Ok(())
+        Ok(bytes)
     }
 
-    /// 从持久化存储加载Token管理器
-    #[inline(never)]
-    pub async fn load() -> Result<Self, Box<dyn Error>> {
-        let file = match OpenOptions::new().read(true).open(&*TOKENS_FILE_PATH).await {
-            Ok(file) => file,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return Ok(Self::new(0));
-            }
-            Err(e) => return Err(Box::new(e)),
-        };
-
-        if file.metadata().await?.len() > usize::MAX as u64 {
-            return Err("Token文件过大".into());
-        }
-
-        let mmap = unsafe { Mmap::map(&file)? };
-        let helpers = unsafe {
-            ::rkyv::from_bytes_unchecked::<Vec<TokenInfoHelper>, ::rkyv::rancor::Error>(&mmap)
-        }?;
+    /// 从序列化数据恢复Token管理器
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let helpers = ::rkyv::from_bytes::<Vec<TokenInfoHelper>, ::rkyv::rancor::Error>(bytes)?;
         let mut manager = Self::new(helpers.len());
 
         for helper in helpers {
@@ -285,6 +251,22 @@ impl TokenManager {
 
         Ok(manager)
     }
+
+    /// 持久化Token管理器
+    #[inline(never)]
+    pub async fn save(&self) -> Result<(), Box<dyn Error>> {
+        let bytes = self.to_bytes()?;
+        super::storage::write(&TOKENS_FILE_PATH, &bytes).await
+    }
+
+    /// 从持久化存储加载Token管理器
+    #[inline(never)]
+    pub async fn load() -> Result<Self, Box<dyn Error>> {
+        let Some(bytes) = super::storage::read(&TOKENS_FILE_PATH).await? else {
+            return Ok(Self::new(0));
+        };
+        Self::from_bytes(&bytes)
+    }
 }
 
 #[inline]
@@ -6,7 +6,7 @@ use std::{io, str::FromStr};
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 pub(super) use cache::__init;
 pub use cache::{Token, TokenKey};
-pub use provider::{Provider, parse_providers};
+pub use provider::{Provider, parse_providers, providers};
 
 use crate::{
     app::constant::HEADER_B64,
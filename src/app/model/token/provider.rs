@@ -81,6 +81,10 @@ impl<'de> ::serde::Deserialize<'de> for Provider {
     }
 }
 
+/// 当前支持的提供者列表
+#[inline]
+pub fn providers() -> &'static [&'static str] { unsafe { PROVIDERS } }
+
 /// 从环境配置初始化支持的提供者列表
 ///
 /// 如果设置了环境变量 `ALLOWED_PROVIDERS`，则从中读取，否则保持默认提供者列表。
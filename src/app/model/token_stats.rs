@@ -0,0 +1,391 @@
+//! 令牌池中各令牌的使用统计
+
+use super::{AppState, ChainUsage, DateTime, TokenKey, state::storage};
+use crate::{app::lazy::TOKEN_STATS_FILE_PATH, common::utils::format_time_ms};
+use ahash::HashMap;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// 滚动窗口按小时分桶，最长覆盖 7 天
+const BUCKET_COUNT: u32 = 24 * 7;
+/// 最近错误标题的最大字符数
+const MAX_ERROR_CHARS: usize = 256;
+
+#[inline]
+fn now_ms() -> i64 { DateTime::utc_now().timestamp_millis() }
+
+#[inline]
+fn hour_of(ms: i64) -> u32 { (ms / 3_600_000) as u32 }
+
+#[inline]
+fn datetime_of(ms: i64) -> Option<DateTime> {
+    if ms == 0 {
+        return None;
+    }
+    chrono::DateTime::from_timestamp_millis(ms).map(|dt| DateTime::from_naive(&dt.naive_utc()))
+}
+
+#[derive(Clone, Copy, Default, Archive, RkyvSerialize, RkyvDeserialize)]
+struct HourBucket {
+    hour: u32,
+    requests: u32,
+    errors: u32,
+}
+
+/// 累计用量
+#[derive(Clone, Copy, Default, Serialize, Archive, RkyvSerialize, RkyvDeserialize)]
+pub struct UsageTotals {
+    pub input: i64,
+    pub output: i64,
+    pub cache_write: i64,
+    pub cache_read: i64,
+    pub cents: f64,
+}
+
+impl UsageTotals {
+    #[inline]
+    fn add(&mut self, usage: ChainUsage) {
+        self.input += usage.input as i64;
+        self.output += usage.output as i64;
+        self.cache_write += usage.cache_write as i64;
+        self.cache_read += usage.cache_read as i64;
+        self.cents += usage.cents as f64;
+    }
+}
+
+/// 单个令牌的累计统计
+#[derive(Clone, Default, Archive, RkyvSerialize, RkyvDeserialize)]
+pub struct TokenStats {
+    /// 最近使用时间（毫秒时间戳），0 表示从未使用
+    last_used: i64,
+    requests: u64,
+    errors: u64,
+    last_error: Option<String>,
+    last_error_at: i64,
+    latency_sum: f64,
+    latency_count: u64,
+    ttft_sum: f64,
+    ttft_count: u64,
+    usage: UsageTotals,
+    /// 以小时序号取模为下标的环形分桶
+    buckets: Vec<HourBucket>,
+}
+
+impl TokenStats {
+    fn record(&mut self, now: i64, latency: f64, outcome: Outcome) {
+        let hour = hour_of(now);
+        if self.buckets.is_empty() {
+            self.buckets = vec![HourBucket::default(); BUCKET_COUNT as usize];
+        }
+        let bucket = &mut self.buckets[(hour % BUCKET_COUNT) as usize];
+        if bucket.hour != hour {
+            *bucket = HourBucket {
+                hour,
+                requests: 0,
+                errors: 0,
+            };
+        }
+
+        self.last_used = now;
+        self.requests += 1;
+        bucket.requests += 1;
+        if let Some(error) = outcome.error {
+            self.errors += 1;
+            bucket.errors += 1;
+            self.last_error = Some(error);
+            self.last_error_at = now;
+        }
+        self.latency_sum += latency;
+        self.latency_count += 1;
+        if let Some(ttft) = outcome.ttft {
+            self.ttft_sum += ttft;
+            self.ttft_count += 1;
+        }
+        if let Some(usage) = outcome.usage {
+            self.usage.add(usage);
+        }
+    }
+
+    /// 统计最近 `hours` 小时内的请求
+    fn window(&self, now: i64, hours: u32) -> WindowStats {
+        let current = hour_of(now);
+        self.buckets
+            .iter()
+            .filter(|b| b.requests != 0 && b.hour <= current && current - b.hour < hours)
+            .fold(WindowStats::default(), |acc, b| WindowStats {
+                requests: acc.requests + b.requests as u64,
+                errors: acc.errors + b.errors as u64,
+            })
+    }
+
+    fn view(&self, now: i64) -> TokenStatsView {
+        let average =
+            |sum: f64, count: u64| (count != 0).then(|| format_time_ms(sum / count as f64));
+        TokenStatsView {
+            last_used: datetime_of(self.last_used),
+            requests: self.requests,
+            errors: self.errors,
+            last_24h: self.window(now, 24),
+            last_7d: self.window(now, BUCKET_COUNT),
+            last_error: self.last_error.clone(),
+            last_error_at: datetime_of(self.last_error_at),
+            avg_latency: average(self.latency_sum, self.latency_count),
+            avg_ttft: average(self.ttft_sum, self.ttft_count),
+            usage: self.usage,
+        }
+    }
+}
+
+/// 滚动窗口内的请求数
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct WindowStats {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+/// 返回给管理接口的统计
+#[derive(Clone, Default, Serialize)]
+pub struct TokenStatsView {
+    pub last_used: Option<DateTime>,
+    pub requests: u64,
+    pub errors: u64,
+    pub last_24h: WindowStats,
+    pub last_7d: WindowStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error_at: Option<DateTime>,
+    /// 平均总耗时（秒）
+    pub avg_latency: Option<f64>,
+    /// 平均首字耗时（秒）
+    pub avg_ttft: Option<f64>,
+    pub usage: UsageTotals,
+}
+
+impl TokenStatsView {
+    #[inline]
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+
+    /// 按指定字段比较，缺失的值排在最前
+    pub fn compare(&self, other: &Self, key: TokenStatsSort) -> Ordering {
+        let by_f64 = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        };
+        match key {
+            TokenStatsSort::LastUsed => self
+                .last_used
+                .map(|t| t.timestamp_millis())
+                .cmp(&other.last_used.map(|t| t.timestamp_millis())),
+            TokenStatsSort::Requests => self.requests.cmp(&other.requests),
+            TokenStatsSort::Errors => self.errors.cmp(&other.errors),
+            TokenStatsSort::Requests24h => self.last_24h.requests.cmp(&other.last_24h.requests),
+            TokenStatsSort::Errors24h => self.last_24h.errors.cmp(&other.last_24h.errors),
+            TokenStatsSort::Requests7d => self.last_7d.requests.cmp(&other.last_7d.requests),
+            TokenStatsSort::Errors7d => self.last_7d.errors.cmp(&other.last_7d.errors),
+            TokenStatsSort::ErrorRate => self.error_rate().total_cmp(&other.error_rate()),
+            TokenStatsSort::AvgLatency => by_f64(self.avg_latency, other.avg_latency),
+            TokenStatsSort::AvgTtft => by_f64(self.avg_ttft, other.avg_ttft),
+            TokenStatsSort::Cents => self.usage.cents.total_cmp(&other.usage.cents),
+        }
+    }
+}
+
+/// 可用于排序的统计字段
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenStatsSort {
+    LastUsed,
+    Requests,
+    Errors,
+    #[serde(rename = "requests_24h")]
+    Requests24h,
+    #[serde(rename = "errors_24h")]
+    Errors24h,
+    #[serde(rename = "requests_7d")]
+    Requests7d,
+    #[serde(rename = "errors_7d")]
+    Errors7d,
+    ErrorRate,
+    AvgLatency,
+    AvgTtft,
+    Cents,
+}
+
+/// 单次请求的结果
+#[derive(Default)]
+struct Outcome {
+    error: Option<String>,
+    ttft: Option<f64>,
+    usage: Option<ChainUsage>,
+}
+
+/// 所有令牌的统计，独立于 `tokens.bin` 持久化以保持其格式不变
+#[derive(Default)]
+pub struct TokenStatsStore {
+    stats: HashMap<TokenKey, TokenStats>,
+}
+
+#[derive(Archive, RkyvSerialize, RkyvDeserialize)]
+struct TokenStatsHelper {
+    entries: Vec<(TokenKey, TokenStats)>,
+}
+
+impl TokenStatsStore {
+    #[inline]
+    fn record(&mut self, key: TokenKey, latency: f64, outcome: Outcome) {
+        self.stats
+            .entry(key)
+            .or_default()
+            .record(now_ms(), latency, outcome);
+    }
+
+    /// 获取令牌的统计，未使用过的令牌返回空统计
+    #[inline]
+    pub fn view(&self, key: &TokenKey, now: i64) -> TokenStatsView {
+        self.stats
+            .get(key)
+            .map(|stats| stats.view(now))
+            .unwrap_or_default()
+    }
+
+    /// 当前时间，用于批量获取统计
+    #[inline]
+    pub fn now() -> i64 { now_ms() }
+
+    /// 移除已不在令牌池中的统计
+    #[inline]
+    pub fn retain(&mut self, mut f: impl FnMut(&TokenKey) -> bool) {
+        self.stats.retain(|key, _| f(key))
+    }
+
+    /// 序列化当前统计，写入文件前释放锁
+    pub fn to_bytes(&self) -> Result<::rkyv::util::AlignedVec, ::rkyv::rancor::Error> {
+        let helper = TokenStatsHelper {
+            entries: self
+                .stats
+                .iter()
+                .map(|(key, stats)| (*key, stats.clone()))
+                .collect(),
+        };
+        ::rkyv::to_bytes::<::rkyv::rancor::Error>(&helper)
+    }
+
+    pub async fn save(bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        storage::write(&TOKEN_STATS_FILE_PATH, bytes).await
+    }
+
+    pub async fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let Some(bytes) = storage::read(&TOKEN_STATS_FILE_PATH).await? else {
+            return Ok(Self::default());
+        };
+        Self::from_bytes(&bytes).map_err(Into::into)
+    }
+
+    /// 从序列化数据恢复统计
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ::rkyv::rancor::Error> {
+        let helper = ::rkyv::from_bytes::<TokenStatsHelper, ::rkyv::rancor::Error>(bytes)?;
+        Ok(Self {
+            stats: helper.entries.into_iter().collect(),
+        })
+    }
+}
+
+/// 记录一次请求的结果，在析构时计入令牌统计
+///
+/// 流式响应中随流一起析构，因此耗时覆盖整个响应过程。
+pub struct UsageRecorder {
+    state: Arc<AppState>,
+    /// 仅统计令牌池中的令牌
+    key: Option<TokenKey>,
+    start: Instant,
+    outcome: parking_lot::Mutex<Outcome>,
+}
+
+impl UsageRecorder {
+    #[inline]
+    pub fn new(state: Arc<AppState>, key: TokenKey, is_pri: bool) -> Self {
+        Self {
+            state,
+            key: is_pri.then_some(key),
+            start: Instant::now(),
+            outcome: parking_lot::Mutex::new(Outcome::default()),
+        }
+    }
+
+    /// 标记请求失败
+    pub fn fail(&self, title: &str) {
+        let title = match title.char_indices().nth(MAX_ERROR_CHARS) {
+            Some((end, _)) => &title[..end],
+            None => title,
+        };
+        self.outcome.lock().error = Some(title.to_string());
+    }
+
+    /// 记录首个内容片段的到达时刻
+    #[inline]
+    pub fn first_token(&self, at: Instant) {
+        self.outcome.lock().ttft = Some(at.saturating_duration_since(self.start).as_secs_f64());
+    }
+
+    /// 根据解码器的延迟信息记录首字耗时，`decode_start` 为解码器创建的时刻
+    #[inline]
+    pub fn first_token_from_delays(&self, decode_start: Instant, delays: &[(u32, f32)]) {
+        if let Some(&(_, delay)) = delays.first() {
+            self.first_token(decode_start + Duration::from_secs_f32(delay));
+        }
+    }
+
+    #[inline]
+    pub fn set_usage(&self, usage: ChainUsage) { self.outcome.lock().usage = Some(usage); }
+}
+
+impl Drop for UsageRecorder {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            let latency = self.start.elapsed().as_secs_f64();
+            let outcome = ::core::mem::take(self.outcome.get_mut());
+            self.state.token_stats.lock().record(key, latency, outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_windows() {
+        let mut stats = TokenStats::default();
+        let hour = 3_600_000;
+        let start = 1_000 * hour;
+        stats.record(start, 1.0, Outcome::default());
+        stats.record(start + 30 * hour, 3.0, Outcome {
+            error: Some("boom".to_string()),
+            ..Outcome::default()
+        });
+        // 7 天后的同一分桶被新的小时覆盖
+        stats.record(start + BUCKET_COUNT as i64 * hour, 2.0, Outcome::default());
+
+        let view = stats.view(start + BUCKET_COUNT as i64 * hour);
+        assert_eq!(view.requests, 3);
+        assert_eq!(view.errors, 1);
+        assert_eq!(view.last_24h.requests, 1);
+        assert_eq!(view.last_7d.requests, 2);
+        assert_eq!(view.last_7d.errors, 1);
+        assert_eq!(view.avg_latency, Some(2.0));
+        assert_eq!(view.avg_ttft, None);
+        assert_eq!(view.last_error.as_deref(), Some("boom"));
+    }
+}
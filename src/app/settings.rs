@@ -0,0 +1,622 @@
+//! 配置文件与设置项校验
+//!
+//! 所有设置项最终都从环境变量读取，配置文件中的值仅在对应环境变量未设置（或为空）时写入，
+//! 优先级：命令行参数 > 环境变量（含 .env）> 配置文件 > 默认值。
+//!
+//! 配置文件中的键不区分大小写，`[table]` 下的键与表名以下划线连接，
+//! 如 `[tls]` 下的 `cert` 对应 `TLS_CERT`。
+
+mod file;
+mod reload;
+
+use crate::common::utils::{ParseFromEnv, parse_from_env};
+use parking_lot::Mutex;
+use reload::Reload;
+pub use reload::{ReloadReport, reload, spawn_reloader};
+use std::{env, process::exit, sync::OnceLock};
+use toml::Value;
+
+/// 指定配置文件路径的环境变量
+const ENV_CONFIG_FILE: &str = "CONFIG_FILE";
+/// 默认配置文件，不存在时忽略
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const MASK: &str = "\"******\"";
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Bool,
+    Uint,
+    Port,
+    Text,
+    /// 逗号分隔，配置文件中可以写作数组
+    List,
+    /// JSON 字符串数组，配置文件中可以写作数组
+    JsonList,
+    /// 可选值，不区分大小写
+    Choice(&'static [&'static str]),
+    /// 逗号分隔的可选值，配置文件中可以写作数组
+    ChoiceList(&'static [&'static str]),
+    Check(fn(&str) -> Result<(), String>),
+}
+
+struct Setting {
+    key: &'static str,
+    kind: Kind,
+    default: &'static str,
+    secret: bool,
+    reload: Reload,
+}
+
+const fn setting(key: &'static str, kind: Kind, default: &'static str) -> Setting {
+    Setting {
+        key,
+        kind,
+        default,
+        secret: false,
+        reload: Reload::Restart,
+    }
+}
+
+const fn secret(key: &'static str, kind: Kind) -> Setting {
+    Setting {
+        key,
+        kind,
+        default: "",
+        secret: true,
+        reload: Reload::Restart,
+    }
+}
+
+impl Setting {
+    /// 支持热重载
+    const fn live(self, reload: Reload) -> Self { Self { reload, ..self } }
+}
+
+fn check_ip(s: &str) -> Result<(), String> {
+    s.parse::<std::net::IpAddr>()
+        .map(|_| ())
+        .map_err(|_| "应为 IP 地址".to_string())
+}
+
+fn check_route_prefix(s: &str) -> Result<(), String> {
+    if !s.starts_with('/') {
+        return Err("应以 / 开头".to_string());
+    }
+    if s.contains(['{', '}', '*', '?', '#']) {
+        return Err("不能包含 {、}、*、? 或 #".to_string());
+    }
+    Ok(())
+}
+
+fn check_schedule(s: &str) -> Result<(), String> { super::jobs::Schedule::parse(s).map(|_| ()) }
+
+fn check_socket_mode(s: &str) -> Result<(), String> {
+    u32::from_str_radix(s, 8)
+        .ok()
+        .filter(|&mode| mode <= 0o777)
+        .map(|_| ())
+        .ok_or_else(|| "应为八进制权限，如 660".to_string())
+}
+
+fn check_cidrs(s: &str) -> Result<(), String> { super::model::access::IpList::parse(s).map(|_| ()) }
+
+fn check_cors_origins(s: &str) -> Result<(), String> {
+    super::model::access::cors_origins(s).map(|_| ())
+}
+
+fn check_cors_methods(s: &str) -> Result<(), String> {
+    super::model::access::cors_methods(s).map(|_| ())
+}
+
+fn check_cors_headers(s: &str) -> Result<(), String> {
+    super::model::access::cors_headers(s).map(|_| ())
+}
+
+fn check_timezone(s: &str) -> Result<(), String> {
+    s.parse::<chrono_tz::Tz>()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn check_gcpp_host(s: &str) -> Result<(), String> {
+    super::model::GcppHost::from_str(s)
+        .map(|_| ())
+        .ok_or_else(|| "应为 Asia、EU 或 US".to_string())
+}
+
+/// 全部设置项，顺序与 .env.example 一致
+static SETTINGS: &[Setting] = &[
+    setting("HOST", Kind::Check(check_ip), "0.0.0.0"),
+    setting("PORT", Kind::Port, "3000"),
+    setting("TLS_CERT", Kind::Text, ""),
+    setting("TLS_KEY", Kind::Text, ""),
+    setting("TLS_PORT", Kind::Port, ""),
+    setting("TLS_RELOAD_INTERVAL", Kind::Uint, "10"),
+    setting("ADMIN_PORT", Kind::Port, ""),
+    setting("ADMIN_HOST", Kind::Check(check_ip), "127.0.0.1"),
+    setting("ROUTE_PREFIX", Kind::Check(check_route_prefix), ""),
+    setting("UNIX_SOCKET", Kind::Text, ""),
+    setting("UNIX_SOCKET_MODE", Kind::Check(check_socket_mode), "660"),
+    setting("SHUTDOWN_TIMEOUT", Kind::Uint, "30"),
+    secret("AUTH_TOKEN", Kind::Text).live(Reload::Static),
+    secret("SHARED_TOKEN", Kind::Text).live(Reload::AppConfig),
+    setting("ENABLE_SLOW_POOL", Kind::Bool, "false").live(Reload::AppConfig),
+    setting("ENABLE_LONG_CONTEXT", Kind::Bool, "false").live(Reload::AppConfig),
+    setting(
+        "VISION_ABILITY",
+        Kind::Choice(&[
+            "none",
+            "disabled",
+            "base64",
+            "base64-only",
+            "all",
+            "base64-http",
+        ]),
+        "base64",
+    )
+    .live(Reload::AppConfig),
+    setting("USAGE_CHECK", Kind::Text, "default").live(Reload::AppConfig),
+    setting("DYNAMIC_KEY", Kind::Bool, "false").live(Reload::AppConfig),
+    setting("KEY_PREFIX", Kind::Text, "sk-"),
+    setting(
+        "DEFAULT_INSTRUCTIONS",
+        Kind::Text,
+        "Respond in Chinese by default\n<|END_USER|>\n\n<|BEGIN_ASSISTANT|>\n\n\nYour will\n<|END_ASSISTANT|>\n\n<|BEGIN_USER|>\n\n\nThe current date is {{currentDateTime}}",
+    ),
+    setting("PRI_REVERSE_PROXY_HOST", Kind::Text, "").live(Reload::Static),
+    setting("PUB_REVERSE_PROXY_HOST", Kind::Text, "").live(Reload::Static),
+    setting("REQUEST_BODY_LIMIT", Kind::Uint, "2000000"),
+    setting("ADMIN_ALLOW_CIDRS", Kind::Check(check_cidrs), "").live(Reload::Static),
+    setting("ADMIN_DENY_CIDRS", Kind::Check(check_cidrs), "").live(Reload::Static),
+    setting("API_ALLOW_CIDRS", Kind::Check(check_cidrs), "").live(Reload::Static),
+    setting("API_DENY_CIDRS", Kind::Check(check_cidrs), "").live(Reload::Static),
+    setting("TRUSTED_PROXIES", Kind::Check(check_cidrs), "").live(Reload::Static),
+    setting("CORS_ALLOW_ORIGINS", Kind::Check(check_cors_origins), "*"),
+    setting("CORS_ALLOW_METHODS", Kind::Check(check_cors_methods), "*"),
+    setting("CORS_ALLOW_HEADERS", Kind::Check(check_cors_headers), "*"),
+    setting("DEBUG", Kind::Bool, "true"),
+    setting("DEBUG_LOG_FILE", Kind::Text, "debug.log"),
+    setting("REQUEST_LOGS_LIMIT", Kind::Uint, "100"),
+    setting("LOG_MAX_AGE", Kind::Uint, "0").live(Reload::LogRetention),
+    setting("LOG_SUCCESS_MAX_AGE", Kind::Uint, "0").live(Reload::LogRetention),
+    setting("LOG_FAILURE_MAX_AGE", Kind::Uint, "0").live(Reload::LogRetention),
+    setting("LOG_CONTENT_MAX_AGE", Kind::Uint, "0").live(Reload::LogRetention),
+    setting("LOG_MAX_BYTES", Kind::Uint, "0").live(Reload::LogRetention),
+    setting("LOG_RETENTION_INTERVAL", Kind::Uint, "300"),
+    setting("REDACT_LOGS", Kind::Bool, "false").live(Reload::Redaction),
+    setting("REDACT_OUTBOUND", Kind::Bool, "false").live(Reload::Redaction),
+    setting(
+        "REDACT_DETECTORS",
+        Kind::ChoiceList(&["api_key", "email", "phone"]),
+        "api_key,email,phone",
+    )
+    .live(Reload::Redaction),
+    setting("REDACT_PATTERNS", Kind::JsonList, "").live(Reload::Redaction),
+    setting("CAPTURE_CONVERSATIONS", Kind::Bool, "false"),
+    setting("CAPTURE_MAX_SIZE", Kind::Uint, "1048576").live(Reload::Static),
+    setting("CAPTURE_MAX_BYTES", Kind::Uint, "67108864").live(Reload::Static),
+    setting("TCP_KEEPALIVE", Kind::Uint, "90").live(Reload::Clients),
+    setting("SERVICE_TIMEOUT", Kind::Uint, "30").live(Reload::Clients),
+    setting("PROXY_PROBE_INTERVAL", Kind::Uint, "60"),
+    setting("PROXY_PROBE_URL", Kind::Text, "https://api2.cursor.sh"),
+    setting("PROXY_FALLBACK", Kind::Text, "").live(Reload::Static),
+    setting("UPSTREAM_CA_CERTS", Kind::List, "").live(Reload::Clients),
+    setting("UPSTREAM_CLIENT_CERT", Kind::Text, "").live(Reload::Clients),
+    setting("UPSTREAM_CLIENT_KEY", Kind::Text, "").live(Reload::Clients),
+    setting(
+        "UPSTREAM_MIN_TLS_VERSION",
+        Kind::Choice(&["1.0", "1.1", "1.2", "1.3"]),
+        "",
+    )
+    .live(Reload::Clients),
+    setting("UPSTREAM_CERT_PINS", Kind::Text, "").live(Reload::Clients),
+    setting("STREAM_KEEPALIVE_INTERVAL", Kind::Uint, "15").live(Reload::Static),
+    setting("STREAM_EARLY_HEADERS", Kind::Bool, "false"),
+    setting("INCLUDE_WEB_REFERENCES", Kind::Bool, "false").live(Reload::AppConfig),
+    setting("DATA_DIR", Kind::Text, "data"),
+    setting("BACKUP_INTERVAL", Kind::Uint, "0"),
+    setting("BACKUP_RETENTION", Kind::Uint, "7"),
+    setting("JOB_AUTOSAVE_SCHEDULE", Kind::Check(check_schedule), "600"),
+    setting("JOB_AUTOSAVE_JITTER", Kind::Uint, "0"),
+    setting("JOB_AUTOSAVE_CONCURRENCY", Kind::Uint, "1"),
+    setting(
+        "JOB_PROFILE_REFRESH_SCHEDULE",
+        Kind::Check(check_schedule),
+        "0",
+    ),
+    setting("JOB_PROFILE_REFRESH_JITTER", Kind::Uint, "0"),
+    setting("JOB_PROFILE_REFRESH_CONCURRENCY", Kind::Uint, "1"),
+    setting("JOB_LOG_PRUNE_SCHEDULE", Kind::Check(check_schedule), ""),
+    setting("JOB_LOG_PRUNE_JITTER", Kind::Uint, "0"),
+    setting("JOB_LOG_PRUNE_CONCURRENCY", Kind::Uint, "1"),
+    setting("JOB_PROXY_PROBE_SCHEDULE", Kind::Check(check_schedule), ""),
+    setting("JOB_PROXY_PROBE_JITTER", Kind::Uint, "0"),
+    setting("JOB_PROXY_PROBE_CONCURRENCY", Kind::Uint, "1"),
+    setting("JOB_BACKUP_SCHEDULE", Kind::Check(check_schedule), ""),
+    setting("JOB_BACKUP_JITTER", Kind::Uint, "0"),
+    setting("JOB_BACKUP_CONCURRENCY", Kind::Uint, "1"),
+    secret("DATA_ENCRYPTION_KEY", Kind::Text),
+    setting("DATA_ENCRYPTION_KEY_FILE", Kind::Text, ""),
+    secret("DATA_ENCRYPTION_PREVIOUS_KEYS", Kind::List),
+    setting(
+        "GENERAL_TIMEZONE",
+        Kind::Check(check_timezone),
+        "Asia/Shanghai",
+    ),
+    setting("TZ", Kind::Check(check_timezone), ""),
+    setting("CURSOR_CLIENT_VERSION", Kind::Text, "1.0.0"),
+    setting("THINKING_TAG", Kind::Text, "think"),
+    setting("REAL_USAGE", Kind::Bool, "true").live(Reload::Static),
+    setting("SAFE_HASH", Kind::Bool, "true"),
+    setting(
+        "FETCH_RAW_MODELS",
+        Kind::Choice(&["truncate", "append:truncate", "append"]),
+        "truncate",
+    )
+    .live(Reload::AppConfig),
+    setting("GENERAL_GCPP_HOST", Kind::Check(check_gcpp_host), "Asia"),
+    setting(
+        "ALLOWED_PROVIDERS",
+        Kind::List,
+        "auth0,google-oauth2,github",
+    ),
+    setting("BYPASS_MODEL_VALIDATION", Kind::Bool, "false").live(Reload::Models),
+];
+
+/// 设置项的默认值，各设置项的默认值只在 [`SETTINGS`] 中定义
+pub fn default_of(key: &str) -> &'static str {
+    let setting = SETTINGS.iter().find(|setting| setting.key == key);
+    debug_assert!(setting.is_some(), "未知的设置项 {key}");
+    setting.map_or("", |setting| setting.default)
+}
+
+/// 读取设置项，未设置或无法解析时使用默认值
+#[inline]
+pub fn get<T: ParseFromEnv>(key: &str) -> T::Result {
+    parse_from_env(key, T::from_default(default_of(key)))
+}
+
+#[inline]
+fn split_list(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// 校验设置项的值，空值表示使用默认值
+fn check(kind: Kind, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(());
+    }
+    match kind {
+        Kind::Bool => match value.to_ascii_lowercase().as_str() {
+            "true" | "false" | "1" | "0" => Ok(()),
+            _ => Err("应为 true 或 false".to_string()),
+        },
+        Kind::Uint => value
+            .parse::<usize>()
+            .map(|_| ())
+            .map_err(|_| "应为非负整数".to_string()),
+        Kind::Port => value
+            .parse::<u16>()
+            .map(|_| ())
+            .map_err(|_| "应为 0-65535 之间的端口号".to_string()),
+        Kind::Text | Kind::List => Ok(()),
+        Kind::JsonList => serde_json::from_str::<Vec<String>>(value)
+            .map(|_| ())
+            .map_err(|e| format!("应为 JSON 字符串数组: {e}")),
+        Kind::Choice(choices) =>
+            if choices.contains(&value.to_ascii_lowercase().as_str()) {
+                Ok(())
+            } else {
+                Err(format!("可选值为 {}", choices.join("、")))
+            },
+        Kind::ChoiceList(choices) => match split_list(value).find(|s| !choices.contains(s)) {
+            Some(item) => Err(format!(
+                "未知的值 '{item}'，可选值为 {}",
+                choices.join("、")
+            )),
+            None => Ok(()),
+        },
+        Kind::Check(check) => check(value),
+    }
+}
+
+/// 将配置文件中的值转换为环境变量的值
+fn to_env_value(kind: Kind, value: Value) -> Result<String, String> {
+    let scalar = |value: Value| match value {
+        Value::String(s) => Ok(s),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        Value::Boolean(b) => Ok(b.to_string()),
+        Value::Datetime(_) => Err("不支持日期时间".to_string()),
+        Value::Array(_) => Err("不支持嵌套数组".to_string()),
+        Value::Table(_) => Err("不支持表".to_string()),
+    };
+    match (kind, value) {
+        (Kind::List | Kind::ChoiceList(_), Value::Array(items)) => Ok(items
+            .into_iter()
+            .map(scalar)
+            .collect::<Result<Vec<_>, _>>()?
+            .join(",")),
+        (Kind::JsonList, Value::Array(items)) => {
+            let items = items
+                .into_iter()
+                .map(scalar)
+                .collect::<Result<Vec<_>, _>>()?;
+            serde_json::to_string(&items).map_err(|e| e.to_string())
+        }
+        (_, Value::Array(_)) => Err("不支持数组".to_string()),
+        (_, value) => scalar(value),
+    }
+}
+
+/// 配置文件中的键对应的环境变量名
+fn env_name(key: &[String]) -> String { key.join("_").to_ascii_uppercase().replace('-', "_") }
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.bytes().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(current)
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// 与未知键最接近的设置项
+fn suggest(name: &str) -> Option<&'static str> {
+    SETTINGS
+        .iter()
+        .map(|setting| (edit_distance(name, setting.key), setting.key))
+        .filter(|&(distance, _)| distance <= 3)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, key)| key)
+}
+
+/// 解析并校验配置文件，返回全部错误
+fn parse_file(src: &str) -> Result<Vec<(&'static str, String)>, Vec<String>> {
+    let entries = file::parse(src).map_err(|e| vec![e.to_string()])?;
+    let mut values = Vec::with_capacity(entries.len());
+    let mut errors = Vec::new();
+
+    for entry in entries {
+        let key = entry.key.join(".");
+        let name = env_name(&entry.key);
+        let Some(setting) = SETTINGS.iter().find(|setting| setting.key == name) else {
+            errors.push(match suggest(&name) {
+                Some(similar) => format!(
+                    "第 {} 行: 未知的配置项 '{key}'，是否为 '{}'？",
+                    entry.line,
+                    similar.to_ascii_lowercase()
+                ),
+                None => format!("第 {} 行: 未知的配置项 '{key}'", entry.line),
+            });
+            continue;
+        };
+        if values.iter().any(|(k, _)| *k == setting.key) {
+            errors.push(format!("第 {} 行: 重复的配置项 '{key}'", entry.line));
+            continue;
+        }
+        match to_env_value(setting.kind, entry.value)
+            .and_then(|value| check(setting.kind, &value).map(|()| value))
+        {
+            Ok(value) => values.push((setting.key, value)),
+            Err(e) => errors.push(format!("第 {} 行: '{key}' 的值无效，{e}", entry.line)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
+
+/// 环境变量的值，空值视为未设置
+#[inline]
+fn env_value(key: &str) -> Option<String> { env::var(key).ok().filter(|v| !v.trim().is_empty()) }
+
+/// 读取并校验配置文件，可选的配置文件不存在时返回空
+fn read_file(path: &str, required: bool) -> Result<Vec<(&'static str, String)>, Vec<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(src) => parse_file(&src),
+        Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(vec![format!("无法读取配置文件 {path}: {e}")]),
+    }
+}
+
+/// 启动时的设置来源，重新加载时按相同的优先级合并
+struct Sources {
+    /// 进程环境变量与命令行参数设置的值，重新加载时保持不变
+    fixed: Vec<(&'static str, String)>,
+    /// 导入的 .env 文件，及是否覆盖已有变量
+    env_files: Vec<(String, bool)>,
+    /// 配置文件，及是否必须存在
+    config_file: Option<(String, bool)>,
+}
+
+static SOURCES: Mutex<Sources> = Mutex::new(Sources {
+    fixed: Vec::new(),
+    env_files: Vec::new(),
+    config_file: None,
+});
+
+/// 记录进程环境变量，须在导入 .env 文件之前调用
+pub fn capture_environment() {
+    SOURCES.lock().fixed = SETTINGS
+        .iter()
+        .filter_map(|setting| Some((setting.key, env::var(setting.key).ok()?)))
+        .collect();
+}
+
+/// 记录已导入的 .env 文件
+pub fn record_env_file(file: &str, override_existing: bool) {
+    SOURCES
+        .lock()
+        .env_files
+        .push((file.to_string(), override_existing));
+}
+
+/// 记录命令行参数设置的值
+pub fn pin(key: &str) {
+    let Some(setting) = SETTINGS.iter().find(|setting| setting.key == key) else {
+        return;
+    };
+    if let Ok(value) = env::var(key) {
+        let mut sources = SOURCES.lock();
+        sources.fixed.retain(|(k, _)| *k != key);
+        sources.fixed.push((setting.key, value));
+    }
+}
+
+/// 由配置文件写入的设置项
+static FILE_KEYS: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+/// 加载配置文件并校验全部设置项，须在读取任何设置项之前调用
+///
+/// 配置文件存在语法错误、未知键或无效值，或环境变量与命令行参数的值无效时，
+/// 打印全部错误并退出。
+pub fn init(file: Option<&str>) {
+    // 显式指定的配置文件必须存在
+    let (path, required) = match file {
+        Some(file) => (file.to_string(), true),
+        None => match env_value(ENV_CONFIG_FILE) {
+            Some(file) => (file, true),
+            None => (DEFAULT_CONFIG_FILE.to_string(), false),
+        },
+    };
+
+    let values = read_file(&path, required).unwrap_or_else(|errors| {
+        eprintln!("配置文件 {path} 无效:");
+        for error in errors {
+            eprintln!("  {error}");
+        }
+        exit(1);
+    });
+    let mut applied = Vec::new();
+    if !values.is_empty() {
+        for (key, value) in values {
+            if env_value(key).is_none() {
+                unsafe { env::set_var(key, value) };
+                applied.push(key);
+            }
+        }
+        println!("已加载配置文件 {path}（生效: {}）", applied.len());
+    }
+
+    let errors: Vec<_> = SETTINGS
+        .iter()
+        .filter(|setting| !applied.contains(&setting.key))
+        .filter_map(|setting| {
+            let value = env_value(setting.key)?;
+            check(setting.kind, &value)
+                .err()
+                .map(|e| format!("{} 的值无效，{e}", setting.key))
+        })
+        .collect();
+    if !errors.is_empty() {
+        eprintln!("环境变量无效:");
+        for error in errors {
+            eprintln!("  {error}");
+        }
+        exit(1);
+    }
+
+    SOURCES.lock().config_file = Some((path, required));
+    let _ = FILE_KEYS.set(applied);
+}
+
+/// 以 TOML 格式输出的值
+fn format_value(kind: Kind, value: &str) -> String {
+    let quote = |s: &str| serde_json::to_string(s).unwrap_or_default();
+    let array = |items: Vec<&str>| {
+        let items: Vec<_> = items.into_iter().map(quote).collect();
+        format!("[{}]", items.join(", "))
+    };
+    let valid = check(kind, value).is_ok();
+    match kind {
+        Kind::Bool if valid => match value.to_ascii_lowercase().as_str() {
+            "true" | "1" => "true".to_string(),
+            _ => "false".to_string(),
+        },
+        Kind::Uint | Kind::Port if valid => value.to_string(),
+        Kind::List | Kind::ChoiceList(_) => array(split_list(value).collect()),
+        Kind::JsonList if valid => match serde_json::from_str::<Vec<String>>(value) {
+            Ok(items) => array(items.iter().map(String::as_str).collect()),
+            Err(_) => quote(value),
+        },
+        _ => quote(value),
+    }
+}
+
+/// 打印合并后生效的配置，敏感值以掩码显示
+pub fn print_effective() {
+    let file_keys = FILE_KEYS.get().map(Vec::as_slice).unwrap_or_default();
+    println!("# 生效的配置（命令行参数 > 环境变量 > 配置文件 > 默认值）");
+    for setting in SETTINGS {
+        let name = setting.key.to_ascii_lowercase();
+        match env_value(setting.key) {
+            Some(value) => {
+                let source = if file_keys.contains(&setting.key) {
+                    "配置文件"
+                } else {
+                    "环境变量"
+                };
+                let invalid = if check(setting.kind, &value).is_err() {
+                    "，值无效"
+                } else {
+                    ""
+                };
+                let value = if setting.secret {
+                    MASK.to_string()
+                } else {
+                    format_value(setting.kind, value.trim())
+                };
+                println!("{name} = {value}  # {source}{invalid}");
+            }
+            None if setting.default.is_empty() => println!("# {name} =  # 未设置"),
+            None => println!(
+                "{name} = {}  # 默认值",
+                format_value(setting.kind, setting.default)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        for setting in SETTINGS {
+            assert!(
+                check(setting.kind, setting.default).is_ok(),
+                "{} 的默认值无效",
+                setting.key
+            );
+        }
+        // 仍以常量形式使用的默认值须与设置表一致
+        assert_eq!(
+            default_of("THINKING_TAG"),
+            crate::app::constant::DEFAULT_THINKING_TAG
+        );
+        assert_eq!(default_of("HOST"), crate::natural_args::DEFAULT_LISTEN_HOST);
+        assert_eq!(default_of("PORT"), crate::natural_args::DEFAULT_LISTEN_PORT);
+
+        assert_eq!(
+            <usize as ParseFromEnv>::from_default(default_of("SERVICE_TIMEOUT")),
+            30
+        );
+        assert!(<bool as ParseFromEnv>::from_default(default_of("DEBUG")));
+    }
+}
@@ -0,0 +1,207 @@
+//! 配置文件的解析
+//!
+//! 文件由 `toml` 解析，表与内联表按键展开，每个值保留所在的行号以便报告错误。
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use std::borrow::Cow;
+use toml::{Spanned, Value};
+
+pub struct Entry {
+    pub key: Vec<String>,
+    pub value: Value,
+    pub line: usize,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub line: usize,
+    pub message: Cow<'static, str>,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "第 {} 行: {}", self.line, self.message)
+    }
+}
+
+/// `toml` 以此为键表示日期时间
+const DATETIME_KEY: &str = "$__toml_private_datetime";
+
+/// 文件中的项，表中的值带有位置
+enum Item {
+    Table(Vec<(String, Spanned<Item>)>),
+    Value(Value),
+}
+
+impl<'de> Deserialize<'de> for Item {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ItemVisitor;
+
+        impl<'de> Visitor<'de> for ItemVisitor {
+            type Value = Item;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("TOML 值")
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<Item, E> {
+                Ok(Item::Value(Value::Boolean(v)))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Item, E> { Ok(Item::Value(Value::Integer(v))) }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Item, E> {
+                i64::try_from(v)
+                    .map(|v| Item::Value(Value::Integer(v)))
+                    .map_err(|_| E::custom("整数超出范围"))
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<Item, E> { Ok(Item::Value(Value::Float(v))) }
+
+            fn visit_str<E>(self, v: &str) -> Result<Item, E> {
+                Ok(Item::Value(Value::String(v.to_string())))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<Item, E> {
+                Ok(Item::Value(Value::String(v)))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Item, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(Item::Value(Value::Array(items)))
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Item, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut items = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == DATETIME_KEY {
+                        return Err(de::Error::custom("不支持日期时间"));
+                    }
+                    items.push((key, map.next_value()?));
+                }
+                Ok(Item::Table(items))
+            }
+        }
+
+        deserializer.deserialize_any(ItemVisitor)
+    }
+}
+
+#[inline]
+fn line_of(src: &str, offset: usize) -> usize {
+    src.as_bytes()[..offset.min(src.len())]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count()
+        + 1
+}
+
+fn flatten(
+    src: &str,
+    prefix: &[String],
+    items: Vec<(String, Spanned<Item>)>,
+    out: &mut Vec<Entry>,
+) {
+    for (name, item) in items {
+        let mut key = prefix.to_vec();
+        key.push(name);
+        let line = line_of(src, item.span().start);
+        match item.into_inner() {
+            Item::Table(items) => flatten(src, &key, items, out),
+            Item::Value(value) => out.push(Entry { key, value, line }),
+        }
+    }
+}
+
+/// 解析配置文件，按行号排序返回全部键值
+pub fn parse(src: &str) -> Result<Vec<Entry>, Error> {
+    let src = src.strip_prefix('\u{feff}').unwrap_or(src);
+    let items = match toml::from_str::<Item>(src) {
+        Ok(Item::Table(items)) => items,
+        Ok(Item::Value(_)) => Vec::new(),
+        Err(e) => {
+            return Err(Error {
+                line: e.span().map_or(1, |span| line_of(src, span.start)),
+                message: Cow::Owned(e.message().trim_end().to_string()),
+            });
+        }
+    };
+    let mut entries = Vec::with_capacity(items.len());
+    flatten(src, &[], items, &mut entries);
+    entries.sort_by_key(|entry| entry.line);
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let src = r#"
+# 注释
+port = 3_000
+debug = false  # 行尾注释
+default_instructions = """
+第一行\
+  续行
+第二行"""
+
+[tls]
+cert = 'C:\certs\server.crt'
+min.version = 1.2
+detectors = [
+    "api_key",
+    "email", # 注释
+]
+"#;
+        let entries = parse(src).unwrap();
+        let values: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.key.join("."), &entry.value))
+            .collect();
+        assert_eq!(values, [
+            ("port".to_string(), &Value::Integer(3000)),
+            ("debug".to_string(), &Value::Boolean(false)),
+            (
+                "default_instructions".to_string(),
+                &Value::String("第一行续行\n第二行".to_string())
+            ),
+            (
+                "tls.cert".to_string(),
+                &Value::String(r"C:\certs\server.crt".to_string())
+            ),
+            ("tls.min.version".to_string(), &Value::Float(1.2)),
+            (
+                "tls.detectors".to_string(),
+                &Value::Array(vec![
+                    Value::String("api_key".to_string()),
+                    Value::String("email".to_string())
+                ])
+            ),
+        ]);
+        assert_eq!(entries[3].line, 11);
+
+        for (src, line) in [
+            ("a = 1\nb = bare", 2),
+            ("a = \"x\n", 1),
+            ("a = 1\na = 2", 2),
+            ("a = 1 b", 1),
+            ("a = 1979-05-27", 1),
+        ] {
+            assert_eq!(parse(src).err().map(|e| e.line), Some(line), "{src}");
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! 配置热重载
+//!
+//! 重新读取启动时使用的 .env 文件与配置文件，按启动时的优先级合并后逐项比较，
+//! 支持热重载的设置项立即生效，其余设置项仅报告需要重启。
+
+use super::{SETTINGS, SOURCES, Setting, check, env_value, read_file};
+use crate::{
+    app::{
+        constant::EMPTY_STRING,
+        lazy::{
+            ADMIN_ACCESS, API_ACCESS, AUTH_TOKEN, CAPTURE_MAX_BYTES, CAPTURE_MAX_SIZE,
+            PRI_REVERSE_PROXY_HOST, PROXY_FALLBACK, PUB_REVERSE_PROXY_HOST, REAL_USAGE,
+            SERVICE_TIMEOUT, STREAM_KEEPALIVE_INTERVAL, TCP_KEEPALIVE, TRUSTED_PROXIES,
+        },
+        model::{AppConfig, FetchMode, UsageCheck, VisionAbility, proxy_pool::Proxies, redaction},
+    },
+    common::utils::parse_from_env,
+};
+use ahash::HashMap;
+use parking_lot::Mutex;
+use std::env;
+
+/// 设置项变化后的处理方式
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Reload {
+    /// 需要重启
+    Restart,
+    /// `lazy` 中的可热重载设置
+    Static,
+    /// `AppConfig` 中的设置
+    AppConfig,
+    LogRetention,
+    Redaction,
+    /// 重建上游客户端
+    Clients,
+    Models,
+}
+
+/// 重新加载的结果
+#[derive(::serde::Serialize, Default)]
+pub struct ReloadReport {
+    /// 已生效的设置项
+    pub applied: Vec<&'static str>,
+    /// 已变化但需要重启才能生效的设置项
+    pub restart_required: Vec<&'static str>,
+    /// 值无效或应用失败的设置项
+    pub warnings: Vec<String>,
+}
+
+/// 按启动时的优先级合并各来源：命令行参数与进程环境变量 > .env 文件 > 配置文件
+fn resolve(warnings: &mut Vec<String>) -> Result<HashMap<&'static str, String>, Vec<String>> {
+    let sources = SOURCES.lock();
+    let find = |key: &str| SETTINGS.iter().find(|setting| setting.key == key);
+
+    // 与 dotenvy 一致，不覆盖时跳过已存在的变量
+    let mut present: Vec<&'static str> = sources.fixed.iter().map(|(key, _)| *key).collect();
+    let mut overridden = Vec::new();
+    let mut values = HashMap::default();
+    for (file, override_existing) in &sources.env_files {
+        let iter = match dotenvy::from_filename_iter(file) {
+            Ok(iter) => iter,
+            Err(e) => {
+                warnings.push(format!("无法读取 {file}: {e}"));
+                continue;
+            }
+        };
+        for item in iter {
+            let (key, value) = match item {
+                Ok(item) => item,
+                Err(e) => {
+                    warnings.push(format!("解析 {file} 失败: {e}"));
+                    break;
+                }
+            };
+            let Some(setting) = find(&key) else { continue };
+            if *override_existing {
+                overridden.push(setting.key);
+            } else if present.contains(&setting.key) {
+                continue;
+            }
+            present.push(setting.key);
+            values.insert(setting.key, value);
+        }
+    }
+    for (key, value) in &sources.fixed {
+        if !overridden.contains(key) {
+            values.insert(*key, value.clone());
+        }
+    }
+
+    if let Some((path, required)) = &sources.config_file {
+        for (key, value) in read_file(path, *required)? {
+            values.entry(key).or_insert(value);
+        }
+    }
+    // 与 env_value 一致，空值视为未设置
+    values.retain(|_, value| !value.trim().is_empty());
+    Ok(values)
+}
+
+/// 使当前环境变量中的值生效
+fn apply(setting: &Setting) -> Result<(), String> {
+    let key = setting.key;
+    match setting.reload {
+        Reload::Restart => {}
+        Reload::Static => match key {
+            "AUTH_TOKEN" => AUTH_TOKEN.reload(),
+            "PRI_REVERSE_PROXY_HOST" => PRI_REVERSE_PROXY_HOST.reload(),
+            "PUB_REVERSE_PROXY_HOST" => PUB_REVERSE_PROXY_HOST.reload(),
+            "PROXY_FALLBACK" => PROXY_FALLBACK.reload(),
+            "CAPTURE_MAX_SIZE" => CAPTURE_MAX_SIZE.reload(),
+            "CAPTURE_MAX_BYTES" => CAPTURE_MAX_BYTES.reload(),
+            "STREAM_KEEPALIVE_INTERVAL" => STREAM_KEEPALIVE_INTERVAL.reload(),
+            "REAL_USAGE" => REAL_USAGE.reload(),
+            "ADMIN_ALLOW_CIDRS" | "ADMIN_DENY_CIDRS" => ADMIN_ACCESS.reload(),
+            "API_ALLOW_CIDRS" | "API_DENY_CIDRS" => API_ACCESS.reload(),
+            "TRUSTED_PROXIES" => TRUSTED_PROXIES.reload(),
+            _ => {}
+        },
+        Reload::AppConfig => match key {
+            "SHARED_TOKEN" => {
+                let token = parse_from_env(key, EMPTY_STRING);
+                if token.is_empty() {
+                    AppConfig::reset_share_token()
+                } else {
+                    AppConfig::update_share_token(token.into_owned())
+                }
+            }
+            "ENABLE_SLOW_POOL" => AppConfig::update_slow_pool(parse_from_env(key, false)),
+            "ENABLE_LONG_CONTEXT" => AppConfig::update_long_context(parse_from_env(key, false)),
+            "DYNAMIC_KEY" => AppConfig::update_dynamic_key(parse_from_env(key, false)),
+            "INCLUDE_WEB_REFERENCES" => AppConfig::update_web_refs(parse_from_env(key, false)),
+            "VISION_ABILITY" => AppConfig::update_vision_ability(VisionAbility::from_str(
+                &parse_from_env(key, EMPTY_STRING),
+            )),
+            "USAGE_CHECK" => AppConfig::update_usage_check(UsageCheck::from_str(&parse_from_env(
+                key,
+                EMPTY_STRING,
+            ))),
+            "FETCH_RAW_MODELS" => AppConfig::update_fetch_models(FetchMode::from_str(
+                &parse_from_env(key, EMPTY_STRING),
+            )),
+            _ => {}
+        },
+        Reload::LogRetention => AppConfig::reload_log_retention(),
+        Reload::Redaction =>
+            redaction::reload().map_err(|e| format!("脱敏规则无效，保持原设置: {e}"))?,
+        Reload::Clients => {
+            TCP_KEEPALIVE.reload();
+            SERVICE_TIMEOUT.reload();
+            Proxies::rebuild_clients().map_err(|e| format!("客户端设置无效，保持原设置: {e}"))?;
+        }
+        Reload::Models => crate::core::model::init_resolver(),
+    }
+    Ok(())
+}
+
+static RELOAD_LOCK: Mutex<()> = Mutex::new(());
+
+/// 重新加载配置
+///
+/// 配置文件无效或缺少 `AUTH_TOKEN` 时返回全部错误，不做任何修改
+pub fn reload() -> Result<ReloadReport, Vec<String>> {
+    let _guard = RELOAD_LOCK.lock();
+    let mut report = ReloadReport::default();
+    let values = resolve(&mut report.warnings)?;
+    if !values.contains_key("AUTH_TOKEN") {
+        return Err(vec!["AUTH_TOKEN 不能为空".to_string()]);
+    }
+
+    let mut changed: Vec<&Setting> = Vec::new();
+    for setting in SETTINGS {
+        let new = values.get(setting.key).map(|value| value.trim());
+        if new == env_value(setting.key).as_deref().map(str::trim) {
+            continue;
+        }
+        // 无效的值不生效，保持原值
+        if let Some(value) = new
+            && let Err(e) = check(setting.kind, value)
+        {
+            report
+                .warnings
+                .push(format!("{} 的值无效，保持原值: {e}", setting.key));
+            continue;
+        }
+        if setting.reload == Reload::Restart {
+            report.restart_required.push(setting.key);
+            continue;
+        }
+        // 运行期间仅在此处修改环境变量，且由 RELOAD_LOCK 串行化
+        unsafe {
+            match new {
+                Some(value) => env::set_var(setting.key, value),
+                None => env::remove_var(setting.key),
+            }
+        }
+        changed.push(setting);
+    }
+
+    // 同组设置项一起生效，每组仅应用一次
+    let mut applied_groups = Vec::new();
+    for setting in &changed {
+        let grouped = matches!(
+            setting.reload,
+            Reload::LogRetention | Reload::Redaction | Reload::Clients
+        );
+        let result = if grouped && applied_groups.contains(&setting.reload) {
+            Ok(())
+        } else {
+            if grouped {
+                applied_groups.push(setting.reload);
+            }
+            apply(setting)
+        };
+        match result {
+            Ok(()) => report.applied.push(setting.key),
+            Err(e) => report.warnings.push(format!("{}: {e}", setting.key)),
+        }
+    }
+    Ok(report)
+}
+
+/// 打印重新加载的结果
+fn print_report(result: &Result<ReloadReport, Vec<String>>) {
+    match result {
+        Ok(report) => {
+            println!(
+                "配置已重新加载，生效: {}，需要重启: {}",
+                if report.applied.is_empty() {
+                    "无".to_string()
+                } else {
+                    report.applied.join(", ")
+                },
+                if report.restart_required.is_empty() {
+                    "无".to_string()
+                } else {
+                    report.restart_required.join(", ")
+                }
+            );
+            for warning in &report.warnings {
+                eprintln!("  {warning}");
+            }
+        }
+        Err(errors) => {
+            eprintln!("重新加载配置失败，保持原配置:");
+            for error in errors {
+                eprintln!("  {error}");
+            }
+        }
+    }
+}
+
+/// 收到 SIGHUP 时重新加载配置
+pub fn spawn_reloader() {
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                eprintln!("无法监听 SIGHUP: {e}");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            print_report(&reload());
+        }
+    });
+}
@@ -1,7 +1,9 @@
 pub mod client;
 // pub(crate) mod impls;
 pub mod model;
+pub mod systemd;
 pub mod time;
+pub mod tls;
 pub mod utils;
 
 pub mod build {
@@ -20,9 +20,8 @@ use crate::{
             header_value_ua_cursor_latest,
         },
         lazy::{
-            PRI_REVERSE_PROXY_HOST, PUB_REVERSE_PROXY_HOST, USE_PRI_REVERSE_PROXY,
-            USE_PUB_REVERSE_PROXY, sessions_url, stripe_url, token_poll_url, token_refresh_url,
-            token_upgrade_url, usage_api_url, user_api_url,
+            PRI_REVERSE_PROXY_HOST, PUB_REVERSE_PROXY_HOST, sessions_url, stripe_url,
+            token_poll_url, token_refresh_url, token_upgrade_url, usage_api_url, user_api_url,
         },
         model::ExtToken,
     },
@@ -107,18 +106,18 @@ fn get_client_and_host<'a>(
     is_pri: bool,
     real_host: &'a str,
 ) -> (RequestBuilder, &'a str) {
-    if is_pri && *USE_PRI_REVERSE_PROXY {
-        (
-            client.request(method, url).header(PROXY_HOST, real_host),
-            &PRI_REVERSE_PROXY_HOST,
-        )
-    } else if !is_pri && *USE_PUB_REVERSE_PROXY {
+    let proxy_host = if is_pri {
+        PRI_REVERSE_PROXY_HOST.get()
+    } else {
+        PUB_REVERSE_PROXY_HOST.get()
+    };
+    if proxy_host.is_empty() {
+        (client.request(method, url), real_host)
+    } else {
         (
             client.request(method, url).header(PROXY_HOST, real_host),
-            &PUB_REVERSE_PROXY_HOST,
+            proxy_host,
         )
-    } else {
-        (client.request(method, url), real_host)
     }
 }
 
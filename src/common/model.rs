@@ -5,10 +5,38 @@ pub mod token;
 pub mod tri;
 pub mod userinfo;
 
-use std::borrow::Cow;
+use std::{borrow::Cow, net::SocketAddr};
 
+use axum::{extract::connect_info::Connected, serve::IncomingStream};
 use serde::Serialize;
 
+/// 连接的对端地址，同时适用于 HTTP 与 HTTPS 监听器
+#[derive(Clone, Copy)]
+pub struct PeerAddr(pub SocketAddr);
+
+impl Connected<IncomingStream<'_, tokio::net::TcpListener>> for PeerAddr {
+    #[inline]
+    fn connect_info(stream: IncomingStream<'_, tokio::net::TcpListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+impl Connected<IncomingStream<'_, super::tls::TlsListener>> for PeerAddr {
+    #[inline]
+    fn connect_info(stream: IncomingStream<'_, super::tls::TlsListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+/// Unix 域套接字没有对端 IP，视为本机连接
+#[cfg(unix)]
+impl Connected<IncomingStream<'_, tokio::net::UnixListener>> for PeerAddr {
+    #[inline]
+    fn connect_info(_stream: IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        Self(SocketAddr::from((std::net::Ipv4Addr::LOCALHOST, 0)))
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ApiStatus {
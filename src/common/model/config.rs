@@ -2,7 +2,9 @@ use std::borrow::Cow;
 
 use serde::{Deserialize, Serialize};
 
-use crate::app::model::{FetchMode, PageContent, UsageCheck, VisionAbility};
+use crate::app::model::{
+    FetchMode, LogRetention, PageContent, UsageCheck, VisionAbility, redaction::Redaction,
+};
 
 #[derive(Serialize)]
 pub struct ConfigData {
@@ -16,6 +18,8 @@ pub struct ConfigData {
     pub share_token: String,
     pub include_web_references: bool,
     pub fetch_raw_models: FetchMode,
+    pub log_retention: LogRetention,
+    pub redaction: Redaction,
 }
 
 #[derive(Deserialize, Default)]
@@ -32,6 +36,8 @@ pub struct ConfigUpdateRequest {
     pub share_token: Option<String>,
     pub include_web_references: Option<bool>,
     pub fetch_raw_models: Option<FetchMode>,
+    pub log_retention: Option<LogRetention>,
+    pub redaction: Option<Redaction>,
 }
 
 #[derive(Serialize)]
@@ -10,6 +10,8 @@ pub enum ChatError {
     NoTokens,
     RequestFailed(Cow<'static, str>),
     Unauthorized,
+    Forbidden,
+    AddressDenied,
     ProcessingFailed(Cow<'static, str>),
 }
 
@@ -22,6 +24,8 @@ impl ChatError {
             Self::NoTokens => "no_tokens",
             Self::RequestFailed(_) => "request_failed",
             Self::Unauthorized => "unauthorized",
+            Self::Forbidden => "forbidden",
+            Self::AddressDenied => "address_denied",
             Self::ProcessingFailed(_) => "processing_failed",
         }
     }
@@ -36,6 +40,8 @@ impl core::fmt::Display for ChatError {
             Self::NoTokens => write!(f, "No available tokens"),
             Self::RequestFailed(err) => write!(f, "Request failed: {err}"),
             Self::Unauthorized => write!(f, "Invalid authorization token"),
+            Self::Forbidden => write!(f, "Insufficient permissions"),
+            Self::AddressDenied => write!(f, "Client address not allowed"),
             Self::ProcessingFailed(err) => write!(f, "Processing failed: {err}"),
         }
     }
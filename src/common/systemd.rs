@@ -0,0 +1,107 @@
+//! systemd 集成：套接字激活、就绪通知与看门狗
+//!
+//! 未在 systemd 下运行或不是 Unix 平台时，所有函数均不做任何事。
+
+/// 继承的监听套接字
+pub enum Inherited {
+    Tcp(std::net::TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+/// 向 `NOTIFY_SOCKET` 发送状态，如 `READY=1`、`STOPPING=1`
+#[cfg(unix)]
+pub fn notify(state: &str) {
+    use std::os::unix::{ffi::OsStrExt as _, net::UnixDatagram};
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let result = UnixDatagram::unbound().and_then(|socket| {
+        match path.as_bytes().strip_prefix(b"@") {
+            // 抽象命名空间
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt as _;
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &addr)
+            }
+            _ => socket.send_to(state.as_bytes(), &path),
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("无法通知 systemd: {e}");
+    }
+}
+
+#[cfg(not(unix))]
+#[inline(always)]
+pub fn notify(_state: &str) {}
+
+/// 按 `WATCHDOG_USEC` 的一半间隔发送看门狗心跳
+pub fn spawn_watchdog() {
+    let Some(usec) = std::env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&usec| usec != 0)
+    else {
+        return;
+    };
+    // 设置了 WATCHDOG_PID 时只有对应的进程需要发送心跳
+    if let Ok(pid) = std::env::var("WATCHDOG_PID")
+        && pid.parse::<u32>().ok() != Some(std::process::id())
+    {
+        return;
+    }
+    let period = std::time::Duration::from_micros(usec / 2);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            notify("WATCHDOG=1");
+        }
+    });
+}
+
+/// 取得 systemd 传入的监听套接字及其名称（`FileDescriptorName`）
+#[cfg(unix)]
+pub fn listen_fds() -> Vec<(String, Inherited)> {
+    use std::os::{
+        fd::{FromRawFd as _, OwnedFd},
+        unix::net::UnixListener,
+    };
+
+    /// `SD_LISTEN_FDS_START`
+    const LISTEN_FDS_START: i32 = 3;
+
+    let env = |key| std::env::var(key).ok();
+    if env("LISTEN_PID").and_then(|pid| pid.parse::<u32>().ok()) != Some(std::process::id()) {
+        return Vec::new();
+    }
+    let count = env("LISTEN_FDS")
+        .and_then(|n| n.parse::<i32>().ok())
+        .unwrap_or(0);
+    let names = env("LISTEN_FDNAMES").unwrap_or_default();
+    let mut names = names.split(':');
+
+    (0..count)
+        .map(|i| {
+            // Safety: systemd 保证 LISTEN_FDS_START 起的 count 个描述符有效且归本进程所有
+            let fd = unsafe { OwnedFd::from_raw_fd(LISTEN_FDS_START + i) };
+            let name = names.next().unwrap_or_default().to_string();
+            // 无法取得 Unix 地址的视为 TCP 套接字
+            let unix = UnixListener::from(fd);
+            let listener = if unix.local_addr().is_ok() {
+                Inherited::Unix(unix)
+            } else {
+                Inherited::Tcp(std::net::TcpListener::from(OwnedFd::from(unix)))
+            };
+            (name, listener)
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+#[inline(always)]
+pub fn listen_fds() -> Vec<(String, Inherited)> { Vec::new() }
@@ -0,0 +1,238 @@
+//! 内置 HTTPS 监听，支持证书热重载
+
+use crate::app::lazy::TLS_RELOAD_INTERVAL;
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use rustls::{
+    ServerConfig,
+    crypto::ring::default_provider,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject as _},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use std::{
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, watch},
+};
+use tokio_rustls::{TlsAcceptor, server::TlsStream};
+
+/// TLS 握手超时
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 已完成握手但尚未被取走的连接数上限
+const ACCEPT_BACKLOG: usize = 128;
+
+/// 证书来源与当前生效的证书
+#[derive(Debug)]
+struct CertStore {
+    cert_path: String,
+    key_path: String,
+    current: ArcSwap<CertifiedKey>,
+    /// 最近一次加载时两个文件的修改时间
+    modified: Mutex<[Option<SystemTime>; 2]>,
+}
+
+impl CertStore {
+    fn open(cert_path: String, key_path: String) -> io::Result<Self> {
+        let modified = Self::modified_of(&cert_path, &key_path);
+        let key = load_certified_key(&cert_path, &key_path)?;
+        Ok(Self {
+            cert_path,
+            key_path,
+            current: ArcSwap::from_pointee(key),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    #[inline]
+    fn modified_of(cert_path: &str, key_path: &str) -> [Option<SystemTime>; 2] {
+        [cert_path, key_path].map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+    }
+
+    /// 重新读取证书，失败时保留当前证书
+    ///
+    /// 已建立的连接不受影响，新证书仅用于之后的握手
+    fn reload(&self) {
+        *self.modified.lock() = Self::modified_of(&self.cert_path, &self.key_path);
+        match load_certified_key(&self.cert_path, &self.key_path) {
+            Ok(key) => {
+                self.current.store(Arc::new(key));
+                println!("已重新加载证书 {}", self.cert_path);
+            }
+            Err(e) => eprintln!("重新加载证书失败，继续使用当前证书: {e}"),
+        }
+    }
+
+    /// 证书文件自上次加载后是否有变化
+    #[inline]
+    fn changed(&self) -> bool {
+        *self.modified.lock() != Self::modified_of(&self.cert_path, &self.key_path)
+    }
+}
+
+impl ResolvesServerCert for CertStore {
+    #[inline]
+    fn resolve(&self, _: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.load_full())
+    }
+}
+
+fn load_certified_key(cert_path: &str, key_path: &str) -> io::Result<CertifiedKey> {
+    let invalid = |path: &str, e: rustls::pki_types::pem::Error| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("解析 {path} 失败: {e}"))
+    };
+
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .map_err(|e| invalid(cert_path, e))?;
+    if certs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{cert_path} 中没有证书"),
+        ));
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path).map_err(|e| invalid(key_path, e))?;
+
+    CertifiedKey::from_der(certs, key, &default_provider())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// 在收到 SIGHUP 或证书文件变化时重新加载证书
+fn spawn_reloader(store: Arc<CertStore>) {
+    if let Some(interval) = *TLS_RELOAD_INTERVAL {
+        let store = store.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if store.changed() {
+                    store.reload();
+                }
+            }
+        });
+    }
+
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                eprintln!("无法监听 SIGHUP: {e}");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            store.reload();
+        }
+    });
+    #[cfg(not(unix))]
+    let _ = store;
+}
+
+/// 等待关闭信号
+async fn stopped(shutdown: &mut watch::Receiver<bool>) {
+    let _ = shutdown.wait_for(|&stop| stop).await;
+}
+
+/// HTTPS 监听器
+///
+/// 握手在独立任务中完成，避免慢速客户端阻塞其他连接的接入。
+/// 收到关闭信号后关闭监听套接字，并放弃尚未完成的握手
+pub struct TlsListener {
+    incoming: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    /// 绑定地址并加载证书，ALPN 同时协商 HTTP/2 与 HTTP/1.1
+    pub async fn bind(
+        addr: SocketAddr,
+        cert_path: String,
+        key_path: String,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> io::Result<Self> {
+        let store = Arc::new(CertStore::open(cert_path, key_path)?);
+
+        let mut config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_no_client_auth()
+            .with_cert_resolver(store.clone());
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+
+        spawn_reloader(store);
+
+        let (tx, incoming) = mpsc::channel(ACCEPT_BACKLOG);
+        tokio::spawn(async move {
+            loop {
+                let accepted = tokio::select! {
+                    accepted = listener.accept() => accepted,
+                    _ = stopped(&mut shutdown) => break,
+                };
+                let (stream, peer) = match accepted {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        // 与 axum 的处理一致，连接类错误直接忽略，其他错误稍后重试
+                        if !matches!(
+                            e.kind(),
+                            io::ErrorKind::ConnectionRefused
+                                | io::ErrorKind::ConnectionAborted
+                                | io::ErrorKind::ConnectionReset
+                        ) {
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                        }
+                        continue;
+                    }
+                };
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                let mut shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    let handshake =
+                        tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream));
+                    tokio::select! {
+                        result = handshake => {
+                            if let Ok(Ok(stream)) = result {
+                                let _ = tx.send((stream, peer)).await;
+                            }
+                        }
+                        _ = stopped(&mut shutdown) => {}
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            incoming,
+            local_addr,
+        })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(conn) => conn,
+            // 接入任务仅在关闭时退出，此时 axum 已不再接受连接
+            None => std::future::pending().await,
+        }
+    }
+
+    #[inline]
+    fn local_addr(&self) -> io::Result<Self::Addr> { Ok(self.local_addr) }
+}
@@ -4,6 +4,7 @@
 // mod token;
 pub mod base62;
 mod base64;
+pub mod crypto;
 pub mod duration_fmt;
 pub mod hex;
 pub mod string_builder;
@@ -50,9 +51,14 @@ mod sealed {
 pub trait ParseFromEnv: sealed::Sealed {
     type Result = Self;
     fn parse_from_env(key: &str, default: Self) -> Self::Result;
+    /// 由设置表中以字符串记录的默认值转换
+    fn from_default(default: &'static str) -> Self;
 }
 
 impl ParseFromEnv for bool {
+    #[inline]
+    fn from_default(default: &'static str) -> Self { matches!(default, "true" | "1") }
+
     #[inline]
     fn parse_from_env(key: &str, default: Self) -> Self::Result {
         ::std::env::var(key)
@@ -74,6 +80,9 @@ impl ParseFromEnv for bool {
 
 impl ParseFromEnv for &'static str {
     type Result = Cow<'static, str>;
+    #[inline]
+    fn from_default(default: &'static str) -> Self { default }
+
     #[inline]
     fn parse_from_env(key: &str, default: Self) -> Self::Result {
         match ::std::env::var(key) {
@@ -108,6 +117,9 @@ impl ParseFromEnv for &'static str {
 }
 
 impl ParseFromEnv for usize {
+    #[inline]
+    fn from_default(default: &'static str) -> Self { default.parse().unwrap_or(0) }
+
     #[inline]
     fn parse_from_env(key: &str, default: Self) -> Self::Result {
         ::std::env::var(key)
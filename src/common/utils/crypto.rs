@@ -0,0 +1,139 @@
+//! 基于口令或密钥的对称加密（AES-256-GCM，PBKDF2-HMAC-SHA256 派生密钥）
+
+use core::{fmt, num::NonZeroU32};
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, Nonce, UnboundKey},
+    digest, pbkdf2,
+    rand::{SecureRandom as _, SystemRandom},
+};
+
+pub use ring::aead::NONCE_LEN;
+
+/// 密钥长度
+pub const KEY_LEN: usize = 32;
+/// 盐长度
+pub const SALT_LEN: usize = 16;
+/// 默认的 PBKDF2 迭代次数
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+/// 允许的迭代次数范围，防止导入的文档指定过小或过大的值
+pub const ITERATIONS_RANGE: core::ops::RangeInclusive<u32> = 10_000..=10_000_000;
+
+#[derive(Debug)]
+pub enum CryptoError {
+    InvalidIterations,
+    InvalidNonce,
+    Random,
+    Seal,
+    Open,
+}
+
+impl std::error::Error for CryptoError {}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::InvalidIterations => "Invalid iteration count",
+            Self::InvalidNonce => "Invalid nonce",
+            Self::Random => "Failed to generate random bytes",
+            Self::Seal => "Encryption failed",
+            Self::Open => "Decryption failed, wrong key or corrupted data",
+        })
+    }
+}
+
+/// 生成随机字节
+pub fn random_bytes<const N: usize>() -> Result<[u8; N], CryptoError> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| CryptoError::Random)?;
+    Ok(bytes)
+}
+
+/// 从口令派生密钥
+pub fn derive_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    iterations: u32,
+) -> Result<[u8; KEY_LEN], CryptoError> {
+    if !ITERATIONS_RANGE.contains(&iterations) {
+        return Err(CryptoError::InvalidIterations);
+    }
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        unsafe { NonZeroU32::new_unchecked(iterations) },
+        salt,
+        passphrase,
+        &mut key,
+    );
+    Ok(key)
+}
+
+/// 密钥指纹，用于识别数据使用的密钥而不泄露密钥本身
+pub fn fingerprint(key: &[u8; KEY_LEN]) -> [u8; 8] {
+    let digest = digest::digest(&digest::SHA256, key);
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&digest.as_ref()[..8]);
+    id
+}
+
+#[inline]
+fn aead_key(key: &[u8; KEY_LEN]) -> LessSafeKey {
+    LessSafeKey::new(__unwrap!(UnboundKey::new(&AES_256_GCM, key)))
+}
+
+/// 加密数据，返回随机生成的 nonce 与附带认证标签的密文
+pub fn seal(
+    key: &[u8; KEY_LEN],
+    aad: &[u8],
+    mut data: Vec<u8>,
+) -> Result<([u8; NONCE_LEN], Vec<u8>), CryptoError> {
+    let nonce = random_bytes::<NONCE_LEN>()?;
+    aead_key(key)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad),
+            &mut data,
+        )
+        .map_err(|_| CryptoError::Seal)?;
+    Ok((nonce, data))
+}
+
+/// 解密并校验数据
+pub fn open(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8],
+    aad: &[u8],
+    mut data: Vec<u8>,
+) -> Result<Vec<u8>, CryptoError> {
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| CryptoError::InvalidNonce)?;
+    let len = aead_key(key)
+        .open_in_place(nonce, Aad::from(aad), &mut data)
+        .map_err(|_| CryptoError::Open)?
+        .len();
+    data.truncate(len);
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let salt = __unwrap!(random_bytes::<SALT_LEN>());
+        let key = __unwrap!(derive_key(b"passphrase", &salt, *ITERATIONS_RANGE.start()));
+        let (nonce, sealed) = __unwrap!(seal(&key, b"aad", b"hello".to_vec()));
+        assert_ne!(&sealed[..5], b"hello");
+        assert_eq!(
+            __unwrap!(open(&key, &nonce, b"aad", sealed.clone())),
+            b"hello"
+        );
+
+        let other = __unwrap!(derive_key(b"wrong", &salt, *ITERATIONS_RANGE.start()));
+        assert!(open(&other, &nonce, b"aad", sealed.clone()).is_err());
+        assert!(open(&key, &nonce, b"other", sealed).is_err());
+        assert!(derive_key(b"passphrase", &salt, 1).is_err());
+    }
+}
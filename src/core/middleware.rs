@@ -1,2 +1,8 @@
+mod access;
+pub use access::{Scope, access_middleware, client_ip_middleware, cors_layer};
+mod audit;
+pub use audit::audit_middleware;
 mod auth;
-pub use auth::{admin_auth_middleware, auth, cpp_auth_middleware, v1_auth_middleware};
+pub use auth::{admin_auth_middleware, auth, check_admin, cpp_auth_middleware, v1_auth_middleware};
+mod capture;
+pub use capture::capture_middleware;
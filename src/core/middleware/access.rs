@@ -0,0 +1,92 @@
+use crate::{
+    app::{
+        lazy::{ADMIN_ACCESS, API_ACCESS, TRUSTED_PROXIES},
+        model::access::{
+            ClientIp, CorsList, cors_headers, cors_methods, cors_origins, resolve_client_ip,
+        },
+    },
+    common::{
+        model::{PeerAddr, error::ChatError},
+        utils::parse_from_env,
+    },
+};
+use axum::{
+    Json,
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse as _, Response},
+};
+use tower_http::cors::{Any, CorsLayer};
+
+/// 地址访问控制的范围
+#[derive(Clone, Copy)]
+pub enum Scope {
+    /// 管理接口
+    Admin,
+    /// 对话、模型与日志等接口
+    Api,
+}
+
+// 解析客户端地址并写入请求扩展
+pub async fn client_ip_middleware(mut request: Request<Body>, next: Next) -> Response {
+    if let Some(&ConnectInfo(PeerAddr(peer))) = request.extensions().get::<ConnectInfo<PeerAddr>>()
+    {
+        let ip = resolve_client_ip(peer.ip(), request.headers(), &TRUSTED_PROXIES.load());
+        request.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(request).await
+}
+
+// 按客户端地址过滤请求，状态为路由所属的范围
+pub async fn access_middleware(
+    State(scope): State<Scope>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let access = match scope {
+        Scope::Admin => ADMIN_ACCESS.load(),
+        Scope::Api => API_ACCESS.load(),
+    };
+    // 无法确定客户端地址时，仅在未设置任何网段时放行
+    let permitted = match request.extensions().get::<ClientIp>() {
+        Some(&ClientIp(ip)) => access.permits(ip),
+        None => access.is_unrestricted(),
+    };
+    if !permitted {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ChatError::AddressDenied.to_generic()),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// 读取 CORS 设置，值无效时打印错误并不允许任何值
+fn cors_setting<T>(key: &str, parse: fn(&str) -> Result<CorsList<T>, String>) -> CorsList<T> {
+    parse(&parse_from_env(key, "*")).unwrap_or_else(|e| {
+        eprintln!("{key} 无效，已禁止跨域请求: {e}");
+        CorsList::List(Vec::new())
+    })
+}
+
+/// 按设置构建 CORS 层，全部为 `*` 时与 `CorsLayer::permissive` 相同
+///
+/// 仅在启动时构建一次，修改 CORS 设置后需要重启。
+pub fn cors_layer() -> CorsLayer {
+    let layer = CorsLayer::new().expose_headers(Any);
+    let layer = match cors_setting("CORS_ALLOW_ORIGINS", cors_origins) {
+        CorsList::Any => layer.allow_origin(Any),
+        CorsList::List(origins) => layer.allow_origin(origins),
+    };
+    let layer = match cors_setting("CORS_ALLOW_METHODS", cors_methods) {
+        CorsList::Any => layer.allow_methods(Any),
+        CorsList::List(methods) => layer.allow_methods(methods),
+    };
+    match cors_setting("CORS_ALLOW_HEADERS", cors_headers) {
+        CorsList::Any => layer.allow_headers(Any),
+        CorsList::List(headers) => layer.allow_headers(headers),
+    }
+}
@@ -7,7 +7,10 @@ use crate::{
     app::{
         constant::{API_KEY, AUTHORIZATION_BEARER_PREFIX},
         lazy::AUTH_TOKEN,
-        model::{AppConfig, AppState, TokenKey},
+        model::{
+            AppConfig, AppState, TokenKey,
+            admin::{self, Permission},
+        },
     },
     common::{model::error::ChatError, utils::tokeninfo_to_token},
     core::config::{KeyConfig, parse_dynamic_token},
@@ -36,23 +39,45 @@ pub fn auth(headers: &http::HeaderMap) -> Option<&str> {
     None
 }
 
-// 管理员认证中间件函数
-pub async fn admin_auth_middleware(request: Request<Body>, next: Next) -> Response {
-    if let Some(token) = request
+/// 校验管理员凭据是否具有指定权限，`AUTH_TOKEN` 具有全部权限
+///
+/// 凭据无效时返回 `UNAUTHORIZED`，权限不足时返回 `FORBIDDEN`
+pub fn check_admin(token: &str, permission: Permission) -> Result<(), StatusCode> {
+    match admin::identify(token) {
+        Some(identity) if identity.role.allows(permission) => Ok(()),
+        Some(_) => Err(StatusCode::FORBIDDEN),
+        None => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// 管理员认证失败时的响应
+fn admin_error(status: StatusCode) -> Response {
+    let error = if status == StatusCode::FORBIDDEN {
+        ChatError::Forbidden
+    } else {
+        ChatError::Unauthorized
+    };
+    (status, Json(error.to_generic())).into_response()
+}
+
+// 管理员认证中间件函数，状态为路由所需的权限
+pub async fn admin_auth_middleware(
+    State(permission): State<Permission>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let result = request
         .headers()
         .get(AUTHORIZATION)
         .and_then(|h| h.to_str().ok())
         .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        && token == *AUTH_TOKEN
-    {
-        return next.run(request).await;
-    };
+        .ok_or(StatusCode::UNAUTHORIZED)
+        .and_then(|token| check_admin(token, permission));
 
-    (
-        StatusCode::UNAUTHORIZED,
-        Json(ChatError::Unauthorized.to_generic()),
-    )
-        .into_response()
+    match result {
+        Ok(()) => next.run(request).await,
+        Err(status) => admin_error(status),
+    }
 }
 
 pub async fn v1_auth_middleware(
@@ -76,7 +101,7 @@ pub async fn v1_auth_middleware(
     // 获取token信息
     let v = {
         // 管理员Token
-        if let Some(part) = auth_token.strip_prefix(&**AUTH_TOKEN) {
+        if let Some(part) = auth_token.strip_prefix(AUTH_TOKEN.load().as_str()) {
             let token_manager = state.token_manager.read().await;
 
             let token_info = if part.is_empty() {
@@ -209,7 +234,7 @@ pub async fn cpp_auth_middleware(
     // 获取token信息
     let v = {
         // 管理员Token
-        if let Some(part) = auth_token.strip_prefix(&**AUTH_TOKEN) {
+        if let Some(part) = auth_token.strip_prefix(AUTH_TOKEN.load().as_str()) {
             let token_manager = state.token_manager.read().await;
 
             let token_info = if part.is_empty() {
@@ -0,0 +1,10 @@
+use crate::app::{lazy::CAPTURE_CONVERSATIONS, model::CaptureEnabled};
+use axum::{body::Body, extract::Request, middleware::Next, response::Response};
+
+// 对话捕获中间件，启用时标记请求，由处理函数在构建上游请求时记录规范化后的请求
+pub async fn capture_middleware(mut request: Request<Body>, next: Next) -> Response {
+    if *CAPTURE_CONVERSATIONS {
+        request.extensions_mut().insert(CaptureEnabled);
+    }
+    next.run(request).await
+}
@@ -13,7 +13,7 @@ use crate::app::constant::{ERROR, TYPE};
 
 use super::Role;
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct MessageCreateParams {
   pub model: String,
   pub messages: Vec<MessageParam>,
@@ -23,15 +23,42 @@ pub struct MessageCreateParams {
   // pub mcp_servers: Vec<McpServer>,
   #[serde(default)]
   pub stream: bool,
-  #[serde(default)]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   pub system: Option<SystemContent>,
-  #[serde(default)]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   pub thinking: Option<ThinkingConfig>,
-  #[serde(default)]
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub tools: Vec<Tool>,
 }
 
-#[derive(Deserialize)]
+impl MessageCreateParams {
+  /// 脱敏系统提示与消息文本
+  pub fn redact(&mut self, redact: fn(&mut String)) {
+    match &mut self.system {
+      Some(SystemContent::String(text)) => redact(text),
+      Some(SystemContent::Array(blocks)) => {
+        for block in blocks {
+          redact(&mut block.text);
+        }
+      }
+      None => {}
+    }
+    for message in &mut self.messages {
+      match &mut message.content {
+        MessageContent::String(text) => redact(text),
+        MessageContent::Array(blocks) => {
+          for block in blocks {
+            if let ContentBlockParam::Text { text } = block {
+              redact(text);
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct MessageParam {
   #[serde(deserialize_with = "deserialize_anthropic_role")]
   pub role: Role,
@@ -52,14 +79,14 @@ where
   }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum MessageContent {
   String(String),
   Array(Vec<ContentBlockParam>),
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ContentBlockParam {
   Text { text: String },
@@ -68,13 +95,14 @@ pub enum ContentBlockParam {
   RedactedThinking { data: String },
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ImageSource {
   Base64 { media_type: MediaType, data: String },
   Url { url: String },
 }
 
+#[derive(Clone, Copy)]
 #[repr(u8)]
 pub enum MediaType {
   ImageJpeg,
@@ -105,16 +133,26 @@ impl MediaType {
     }
   }
 
-  // pub fn as_mime(&self) -> &'static str {
-  //     match self {
-  //         Self::ImageJpeg => Self::IMAGE_JPEG,
-  //         Self::ImagePng => Self::IMAGE_PNG,
-  //         Self::ImageGif => Self::IMAGE_GIF,
-  //         Self::ImageWebp => Self::IMAGE_WEBP,
-  //         Self::ApplicationPdf => Self::APPLICATION_PDF,
-  //         Self::TextPlain => Self::TEXT_PLAIN,
-  //     }
-  // }
+  pub fn as_mime(&self) -> &'static str {
+    match self {
+      Self::ImageJpeg => Self::IMAGE_JPEG,
+      Self::ImagePng => Self::IMAGE_PNG,
+      Self::ImageGif => Self::IMAGE_GIF,
+      Self::ImageWebp => Self::IMAGE_WEBP,
+      // Self::ApplicationPdf => Self::APPLICATION_PDF,
+      // Self::TextPlain => Self::TEXT_PLAIN,
+    }
+  }
+}
+
+impl Serialize for MediaType {
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    serializer.serialize_str(self.as_mime())
+  }
 }
 
 impl<'de> Deserialize<'de> for MediaType {
@@ -153,17 +191,30 @@ impl<'de> Deserialize<'de> for MediaType {
 //     pub enabled: Option<bool>,
 // }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum SystemContent {
   String(String),
   Array(Vec<TextBlockParam>),
 }
 
+#[derive(Clone)]
 pub struct TextBlockParam {
   pub text: String,
 }
 
+impl Serialize for TextBlockParam {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    let mut state = serializer.serialize_struct("TextBlockParam", 2)?;
+    state.serialize_field(TYPE, "text")?;
+    state.serialize_field("text", &self.text)?;
+    state.end()
+  }
+}
+
 impl<'de> Deserialize<'de> for TextBlockParam {
   fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
   where
@@ -185,8 +236,9 @@ impl<'de> Deserialize<'de> for TextBlockParam {
         let mut type_ = None;
         let mut text = None;
 
-        while let Some(key) = map.next_key()? {
-          match key {
+        // 键可能无法借用，例如从 `serde_json::Value` 反序列化时
+        while let Some(key) = map.next_key::<Cow<'de, str>>()? {
+          match &*key {
             TYPE => {
               if type_.is_some() {
                 return Err(de::Error::duplicate_field(TYPE));
@@ -222,7 +274,7 @@ impl<'de> Deserialize<'de> for TextBlockParam {
   }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum ThinkingConfig {
   Enabled {
@@ -260,7 +312,7 @@ const _: () = assert!(
     )
 );
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Tool {
   pub input_schema: ToolInputSchema,
   pub name: String,
@@ -425,3 +477,28 @@ impl Serialize for AnthropicError {
     state.end()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_params_round_trip() {
+    let json = serde_json::json!({
+      "model": "claude-4-sonnet",
+      "max_tokens": 1024,
+      "stream": true,
+      "system": [{"type": "text", "text": "system"}],
+      "thinking": {"type": "enabled", "budget_tokens": 2048},
+      "messages": [
+        {"role": "user", "content": [
+          {"type": "text", "text": "hello"},
+          {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AA=="}}
+        ]},
+        {"role": "assistant", "content": "hi"}
+      ]
+    });
+    let params: MessageCreateParams = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&params).unwrap(), json);
+  }
+}
@@ -12,14 +12,14 @@ use crate::{
 
 use super::Role;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum MessageContent {
   String(String),
   Array(Vec<MessageContentObject>),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum MessageContentObject {
   Text { text: String },
@@ -36,12 +36,12 @@ impl MessageContentObject {
   }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ImageUrl {
   pub url: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Message {
   pub role: Role,
   pub content: MessageContent,
@@ -190,17 +190,35 @@ pub struct Usage {
 }
 
 // 聊天请求
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ChatRequest {
   pub model: String,
   pub messages: Vec<Message>,
   #[serde(default)]
   pub stream: bool,
-  #[serde(default)]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   pub stream_options: Option<StreamOptions>,
 }
 
-#[derive(Deserialize)]
+impl ChatRequest {
+  /// 脱敏消息文本
+  pub fn redact(&mut self, redact: fn(&mut String)) {
+    for message in &mut self.messages {
+      match &mut message.content {
+        MessageContent::String(text) => redact(text),
+        MessageContent::Array(objects) => {
+          for object in objects {
+            if let MessageContentObject::Text { text } = object {
+              redact(text);
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct StreamOptions {
   pub include_usage: bool,
 }
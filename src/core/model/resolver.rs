@@ -3,14 +3,16 @@ use crate::{
     app::model::{AppConfig, UsageCheck},
     core::constant::{FREE_MODELS, get_static_id},
 };
+use std::sync::atomic::{AtomicBool, Ordering};
 
-static mut BYPASS_MODEL_VALIDATION: bool = false;
+static BYPASS_MODEL_VALIDATION: AtomicBool = AtomicBool::new(false);
 
+/// 从环境变量读取是否绕过模型验证，重新加载配置时再次调用
 pub fn init_resolver() {
-    unsafe {
-        BYPASS_MODEL_VALIDATION =
-            crate::common::utils::parse_from_env("BYPASS_MODEL_VALIDATION", false)
-    }
+    BYPASS_MODEL_VALIDATION.store(
+        crate::app::settings::get::<bool>("BYPASS_MODEL_VALIDATION"),
+        Ordering::Relaxed,
+    )
 }
 
 #[derive(Clone, Copy)]
@@ -64,7 +66,7 @@ impl ExtModel {
         }
 
         // 正常验证都失败后，检查是否绕过验证
-        if unsafe { BYPASS_MODEL_VALIDATION } && !model_str.is_empty() {
+        if BYPASS_MODEL_VALIDATION.load(Ordering::Relaxed) && !model_str.is_empty() {
             let id = get_static_id(model_str);
             return Some(Self {
                 id,
@@ -1,12 +1,16 @@
 mod logs;
-pub use logs::{handle_get_logs, handle_get_logs_tokens, handle_logs};
+pub use logs::{
+    handle_export_logs, handle_get_logs, handle_get_logs_analytics, handle_get_logs_tokens,
+    handle_logs, handle_replay_log, handle_tail_logs,
+};
 mod health;
 pub use health::{handle_health, handle_root};
 mod token;
 pub use token::{handle_build_key, handle_get_config_version};
 mod tokens;
 pub use tokens::{
-    handle_add_tokens, handle_delete_tokens, handle_get_tokens, handle_refresh_tokens,
+    handle_add_tokens, handle_assign_tokens_proxy, handle_bulk_tokens, handle_delete_tokens,
+    handle_export_tokens, handle_get_tokens, handle_import_tokens, handle_refresh_tokens,
     handle_set_tokens, handle_set_tokens_alias, handle_set_tokens_proxy, handle_set_tokens_status,
     handle_set_tokens_timezone, handle_update_tokens_config_version, handle_update_tokens_profile,
 };
@@ -18,9 +22,17 @@ pub use r#gen::{
 // pub use profile::{handle_token_upgrade, handle_user_info};
 mod proxies;
 pub use proxies::{
-    handle_add_proxy, handle_delete_proxies, handle_get_proxies, handle_set_general_proxy,
-    handle_set_proxies,
+    handle_add_proxy, handle_delete_proxies, handle_delete_proxy_groups, handle_get_proxies,
+    handle_get_proxy_groups, handle_set_general_proxy, handle_set_proxies, handle_set_proxy_groups,
 };
+mod admins;
+pub use admins::{handle_add_admin, handle_delete_admins, handle_get_admins, handle_set_admin};
+mod audit;
+pub use audit::handle_get_audit;
+mod jobs;
+pub use jobs::{handle_get_jobs, handle_run_job};
+mod backup;
+pub use backup::{handle_backup, handle_create_backup, handle_list_backups, handle_restore_backup};
 mod page;
 pub use page::{
     handle_about, handle_api_page, handle_build_key_page, handle_config_page, handle_env_example,
@@ -0,0 +1,194 @@
+use crate::{
+    app::model::{
+        CommonResponse,
+        admin::{self, Admin, AdminInfo, Admins, Role},
+    },
+    common::{
+        model::{ApiStatus, GenericError},
+        utils::string_builder::StringBuilder,
+    },
+};
+use axum::{Json, http::StatusCode};
+use std::borrow::Cow;
+
+crate::define_typed_constants! {
+    &'static str => {
+        ERROR_SAVE_ADMINS = "Failed to save admins: ",
+        MESSAGE_SAVE_ADMINS_FAILED = "无法保存管理员凭据",
+        ERROR_INVALID_ADMIN = "Invalid admin",
+        ERROR_ADMIN_NOT_FOUND = "Admin not found",
+        MESSAGE_INVALID_NAME = "名称只能包含字母、数字与 -_.，不超过 64 个字符且不能为 AUTH_TOKEN",
+        MESSAGE_NAME_EXISTS = "管理员已存在: ",
+        MESSAGE_NAME_NOT_FOUND = "管理员不存在: ",
+        MESSAGE_ROLE_SET = "角色已更新",
+        MESSAGE_DELETED_PREFIX = "已删除 ",
+        MESSAGE_DELETED_SUFFIX = " 个管理员",
+    }
+}
+
+type AdminResult<T> = Result<T, (StatusCode, Json<GenericError>)>;
+
+#[inline]
+fn admin_error(
+    status: StatusCode,
+    error: &'static str,
+    message: String,
+) -> (StatusCode, Json<GenericError>) {
+    (
+        status,
+        Json(GenericError {
+            status: ApiStatus::Error,
+            code: None,
+            error: Some(Cow::Borrowed(error)),
+            message: Some(Cow::Owned(message)),
+        }),
+    )
+}
+
+#[derive(::serde::Serialize)]
+pub struct AdminsResponse {
+    status: ApiStatus,
+    admins: Vec<AdminInfo>,
+}
+
+// 获取所有管理员，不含密钥
+pub async fn handle_get_admins() -> Json<AdminsResponse> {
+    Json(AdminsResponse {
+        status: ApiStatus::Success,
+        admins: admin::list(),
+    })
+}
+
+#[derive(::serde::Deserialize)]
+pub struct AdminAddRequest {
+    name: String,
+    role: Role,
+}
+
+#[derive(::serde::Serialize)]
+pub struct AdminAddResponse {
+    status: ApiStatus,
+    name: String,
+    /// 仅在创建时返回
+    key: String,
+}
+
+// 创建管理员并返回其密钥
+pub async fn handle_add_admin(
+    Json(request): Json<AdminAddRequest>,
+) -> AdminResult<Json<AdminAddResponse>> {
+    if !admin::is_valid_name(&request.name) {
+        return Err(admin_error(
+            StatusCode::BAD_REQUEST,
+            ERROR_INVALID_ADMIN,
+            MESSAGE_INVALID_NAME.to_string(),
+        ));
+    }
+
+    let mut admins = admin::snapshot();
+    if admins.contains_key(&request.name) {
+        return Err(admin_error(
+            StatusCode::CONFLICT,
+            ERROR_INVALID_ADMIN,
+            StringBuilder::with_capacity(2)
+                .append(MESSAGE_NAME_EXISTS)
+                .append(&request.name)
+                .build(),
+        ));
+    }
+
+    let (admin, key) = Admin::generate(request.role);
+    admins.insert(request.name.clone(), admin);
+    admin::store(admins);
+    save_admins().await?;
+
+    Ok(Json(AdminAddResponse {
+        status: ApiStatus::Success,
+        name: request.name,
+        key,
+    }))
+}
+
+#[derive(::serde::Deserialize)]
+pub struct AdminSetRequest {
+    name: String,
+    role: Role,
+}
+
+// 修改管理员的角色，密钥保持不变
+pub async fn handle_set_admin(
+    Json(request): Json<AdminSetRequest>,
+) -> AdminResult<Json<CommonResponse>> {
+    let mut admins = admin::snapshot();
+    let Some(admin) = admins.get_mut(&request.name) else {
+        return Err(admin_error(
+            StatusCode::NOT_FOUND,
+            ERROR_ADMIN_NOT_FOUND,
+            StringBuilder::with_capacity(2)
+                .append(MESSAGE_NAME_NOT_FOUND)
+                .append(&request.name)
+                .build(),
+        ));
+    };
+
+    if admin.role != request.role {
+        admin.role = request.role;
+        admin::store(admins);
+        save_admins().await?;
+    }
+
+    Ok(Json(CommonResponse {
+        status: ApiStatus::Success,
+        message: Cow::Borrowed(MESSAGE_ROLE_SET),
+    }))
+}
+
+#[derive(::serde::Deserialize)]
+pub struct AdminsDeleteRequest {
+    names: Vec<String>,
+}
+
+// 删除指定的管理员，其密钥立即失效
+pub async fn handle_delete_admins(
+    Json(request): Json<AdminsDeleteRequest>,
+) -> AdminResult<Json<CommonResponse>> {
+    let mut admins = admin::snapshot();
+    let before = admins.len();
+    admins.retain(|name, _| !request.names.contains(name));
+    let deleted = before - admins.len();
+
+    if deleted > 0 {
+        admin::store(admins);
+        save_admins().await?;
+    }
+
+    Ok(Json(CommonResponse {
+        status: ApiStatus::Success,
+        message: Cow::Owned(
+            StringBuilder::with_capacity(3)
+                .append(MESSAGE_DELETED_PREFIX)
+                .append(deleted.to_string())
+                .append(MESSAGE_DELETED_SUFFIX)
+                .build(),
+        ),
+    }))
+}
+
+async fn save_admins() -> AdminResult<()> {
+    Admins::save().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Owned(
+                    StringBuilder::with_capacity(2)
+                        .append(ERROR_SAVE_ADMINS)
+                        .append(e.to_string())
+                        .build(),
+                )),
+                message: Some(Cow::Borrowed(MESSAGE_SAVE_ADMINS_FAILED)),
+            }),
+        )
+    })
+}
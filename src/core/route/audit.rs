@@ -0,0 +1,57 @@
+use crate::{
+    app::model::audit::{self, AuditEntry, AuditQuery},
+    common::{
+        model::{ApiStatus, GenericError},
+        utils::string_builder::StringBuilder,
+    },
+};
+use axum::{Json, http::StatusCode};
+use std::borrow::Cow;
+
+crate::define_typed_constants! {
+    &'static str => {
+        ERROR_READ_AUDIT = "Failed to read audit log: ",
+        MESSAGE_READ_AUDIT_FAILED = "无法读取审计记录",
+    }
+}
+
+#[derive(::serde::Deserialize)]
+pub struct AuditRequest {
+    #[serde(default)]
+    query: AuditQuery,
+}
+
+#[derive(::serde::Serialize)]
+pub struct AuditResponse {
+    status: ApiStatus,
+    total: usize,
+    entries: Vec<AuditEntry>,
+}
+
+// 按条件查询审计记录
+pub async fn handle_get_audit(
+    Json(request): Json<AuditRequest>,
+) -> Result<Json<AuditResponse>, (StatusCode, Json<GenericError>)> {
+    let (total, entries) = audit::query(&request.query).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Owned(
+                    StringBuilder::with_capacity(2)
+                        .append(ERROR_READ_AUDIT)
+                        .append(e.to_string())
+                        .build(),
+                )),
+                message: Some(Cow::Borrowed(MESSAGE_READ_AUDIT_FAILED)),
+            }),
+        )
+    })?;
+
+    Ok(Json(AuditResponse {
+        status: ApiStatus::Success,
+        total,
+        entries,
+    }))
+}
@@ -0,0 +1,128 @@
+use crate::{
+    app::model::{
+        AppState,
+        backup::{self, BackupArchive, BackupEntry, BackupError, RestoreSummary},
+    },
+    common::model::{ApiStatus, GenericError},
+};
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderValue, StatusCode, header::CONTENT_DISPOSITION},
+    response::{IntoResponse as _, Response},
+};
+use std::{borrow::Cow, sync::Arc};
+
+crate::define_typed_constants! {
+    &'static str => {
+        ERROR_INVALID_BACKUP = "Invalid backup",
+        ERROR_BACKUP_NOT_FOUND = "Backup not found",
+        ERROR_BACKUP_FAILED = "Backup failed",
+        ERROR_INVALID_REQUEST = "Invalid request",
+        MESSAGE_INVALID_REQUEST = "archive 与 name 必须且只能提供其中之一",
+    }
+}
+
+type BackupResult<T> = Result<T, (StatusCode, Json<GenericError>)>;
+
+fn backup_error(e: BackupError) -> (StatusCode, Json<GenericError>) {
+    let (status, error) = match &e {
+        BackupError::Invalid(_) => (StatusCode::BAD_REQUEST, ERROR_INVALID_BACKUP),
+        BackupError::NotFound => (StatusCode::NOT_FOUND, ERROR_BACKUP_NOT_FOUND),
+        BackupError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, ERROR_BACKUP_FAILED),
+    };
+    (
+        status,
+        Json(GenericError {
+            status: ApiStatus::Error,
+            code: None,
+            error: Some(Cow::Borrowed(error)),
+            message: Some(Cow::Owned(e.to_string())),
+        }),
+    )
+}
+
+/// 下载当前全部状态的备份
+pub async fn handle_backup(State(state): State<Arc<AppState>>) -> BackupResult<Response> {
+    let archive = state.backup().await.map_err(backup_error)?;
+    let mut response = Json(archive).into_response();
+    response.headers_mut().insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_static("attachment; filename=\"cursor-api-backup.json\""),
+    );
+    Ok(response)
+}
+
+#[derive(::serde::Serialize)]
+pub struct BackupCreateResponse {
+    status: ApiStatus,
+    name: String,
+}
+
+/// 立即在数据目录中创建备份
+pub async fn handle_create_backup(
+    State(state): State<Arc<AppState>>,
+) -> BackupResult<Json<BackupCreateResponse>> {
+    let name = state.backup_to_disk().await.map_err(backup_error)?;
+    Ok(Json(BackupCreateResponse {
+        status: ApiStatus::Success,
+        name,
+    }))
+}
+
+#[derive(::serde::Serialize)]
+pub struct BackupListResponse {
+    status: ApiStatus,
+    backups: Vec<BackupEntry>,
+}
+
+/// 列出数据目录中的备份
+pub async fn handle_list_backups() -> BackupResult<Json<BackupListResponse>> {
+    Ok(Json(BackupListResponse {
+        status: ApiStatus::Success,
+        backups: backup::list_backups().await.map_err(backup_error)?,
+    }))
+}
+
+#[derive(::serde::Deserialize)]
+pub struct BackupRestoreRequest {
+    /// 下载的备份
+    #[serde(default)]
+    pub archive: Option<BackupArchive>,
+    /// 数据目录中的备份
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(::serde::Serialize)]
+pub struct BackupRestoreResponse {
+    status: ApiStatus,
+    #[serde(flatten)]
+    summary: RestoreSummary,
+}
+
+pub async fn handle_restore_backup(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BackupRestoreRequest>,
+) -> BackupResult<Json<BackupRestoreResponse>> {
+    let archive = match (request.archive, request.name) {
+        (Some(archive), None) => archive,
+        (None, Some(name)) => backup::read_backup(&name).await.map_err(backup_error)?,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(GenericError {
+                    status: ApiStatus::Error,
+                    code: None,
+                    error: Some(Cow::Borrowed(ERROR_INVALID_REQUEST)),
+                    message: Some(Cow::Borrowed(MESSAGE_INVALID_REQUEST)),
+                }),
+            ));
+        }
+    };
+    let summary = state.restore(archive).await.map_err(backup_error)?;
+    Ok(Json(BackupRestoreResponse {
+        status: ApiStatus::Success,
+        summary,
+    }))
+}
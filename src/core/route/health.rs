@@ -15,7 +15,7 @@ use crate::{
             ROUTE_TOKENS_PROXY_SET_PATH, ROUTE_TOKENS_REFRESH_PATH, ROUTE_TOKENS_SET_PATH,
             ROUTE_TOKENS_STATUS_SET_PATH, ROUTE_TOKENS_TIMEZONE_SET_PATH,
         },
-        lazy::get_start_time,
+        lazy::{ROUTE_PREFIX, get_start_time},
         model::{AppConfig, AppState, DateTime},
     },
     common::model::{
@@ -43,16 +43,16 @@ pub async fn handle_root() -> Response {
         .into_response(|| {
             Response::builder()
                 .status(StatusCode::TEMPORARY_REDIRECT)
-                .header(LOCATION, ROUTE_HEALTH_PATH)
+                .header(LOCATION, format!("{}{ROUTE_HEALTH_PATH}", *ROUTE_PREFIX))
                 .body(Body::empty())
         })
 }
 
 const ENDPOINTS: &'static [&'static str] = &[
-    "{}/v1/chat/completions",
-    "{}/v1/messages",
-    "{}/v1/models",
-    "{}/raw/models",
+    "/v1/chat/completions",
+    "/v1/messages",
+    "/v1/models",
+    "/raw/models",
     ROUTE_TOKENS_PATH,
     ROUTE_TOKENS_GET_PATH,
     ROUTE_TOKENS_SET_PATH,
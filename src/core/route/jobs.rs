@@ -0,0 +1,67 @@
+use crate::{
+    app::{
+        jobs::{self, JobStatus, TriggerError},
+        model::CommonResponse,
+    },
+    common::model::{ApiStatus, GenericError},
+};
+use axum::{Json, http::StatusCode};
+use std::borrow::Cow;
+
+crate::define_typed_constants! {
+    &'static str => {
+        ERROR_JOB_NOT_FOUND = "Job not found",
+        ERROR_JOB_BUSY = "Job is running",
+        MESSAGE_JOB_NOT_FOUND = "任务不存在: ",
+        MESSAGE_JOB_BUSY = "任务已达到并发上限: ",
+        MESSAGE_JOB_STARTED = "任务已开始执行: ",
+    }
+}
+
+#[derive(::serde::Serialize)]
+pub struct JobsResponse {
+    status: ApiStatus,
+    jobs: Vec<JobStatus>,
+}
+
+// 获取所有任务的执行计划与最近一次执行结果
+pub async fn handle_get_jobs() -> Json<JobsResponse> {
+    Json(JobsResponse {
+        status: ApiStatus::Success,
+        jobs: jobs::snapshot(),
+    })
+}
+
+#[derive(::serde::Deserialize)]
+pub struct JobRunRequest {
+    name: String,
+}
+
+// 立即在后台执行一次任务，结果通过 /jobs/get 查看
+pub async fn handle_run_job(
+    Json(request): Json<JobRunRequest>,
+) -> Result<Json<CommonResponse>, (StatusCode, Json<GenericError>)> {
+    let (status, error, message) = match jobs::trigger(&request.name) {
+        Ok(()) => {
+            return Ok(Json(CommonResponse {
+                status: ApiStatus::Success,
+                message: Cow::Owned(format!("{MESSAGE_JOB_STARTED}{}", request.name)),
+            }));
+        }
+        Err(TriggerError::NotFound) => (
+            StatusCode::NOT_FOUND,
+            ERROR_JOB_NOT_FOUND,
+            MESSAGE_JOB_NOT_FOUND,
+        ),
+        Err(TriggerError::Busy) => (StatusCode::CONFLICT, ERROR_JOB_BUSY, MESSAGE_JOB_BUSY),
+    };
+    Err((
+        status,
+        Json(GenericError {
+            status: ApiStatus::Error,
+            code: None,
+            error: Some(Cow::Borrowed(error)),
+            message: Some(Cow::Owned(format!("{message}{}", request.name))),
+        }),
+    ))
+}
@@ -4,11 +4,13 @@ use crate::{
             AUTHORIZATION_BEARER_PREFIX, ERR_LOG_TOKEN_NOT_FOUND, HEADER_VALUE_TEXT_HTML_UTF8,
             ROUTE_LOGS_PATH,
         },
-        lazy::AUTH_TOKEN,
-        model::{AppConfig, AppState, DateTime, ExtToken, LogStatus, RequestLog, TokenKey},
+        model::{
+            AppConfig, AppState, DateTime, ExtToken, LogManager, LogStatus, RequestLog, TokenKey,
+            UserId, admin::Permission,
+        },
     },
     common::model::{ApiStatus, userinfo::MembershipType},
-    core::config::parse_dynamic_token,
+    core::{config::parse_dynamic_token, middleware::check_admin},
 };
 use ahash::{HashMap, HashSet};
 use axum::{
@@ -23,6 +25,13 @@ use axum::{
 };
 use std::sync::{Arc, atomic::Ordering};
 
+mod export;
+mod replay;
+mod tail;
+pub use export::handle_export_logs;
+pub use replay::handle_replay_log;
+pub use tail::handle_tail_logs;
+
 // 日志处理
 pub async fn handle_logs() -> Response {
     AppConfig::get_page_content(ROUTE_LOGS_PATH)
@@ -61,8 +70,9 @@ pub struct LogsQueryParams {
     pub has_chain: Option<bool>, // 是否包含对话链
 
     // 错误相关过滤
-    pub has_error: Option<bool>, // 是否包含错误
-    pub error: Option<String>,   // 按错误过滤（部分匹配）
+    pub has_error: Option<bool>,   // 是否包含错误
+    pub error: Option<String>,     // 按错误过滤（部分匹配）
+    pub proxy_error: Option<bool>, // 是否为代理错误
 
     // 性能指标过滤
     pub min_total_time: Option<f64>, // 最小总耗时（秒）
@@ -77,11 +87,8 @@ pub struct LogsRequest {
     pub query: LogsQueryParams,
 }
 
-pub async fn handle_get_logs(
-    State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
-    Json(request): Json<LogsRequest>,
-) -> Result<Json<LogsResponse>, StatusCode> {
+/// 解析调用方身份，具有日志权限的管理员返回 `None`，其他调用方返回其令牌
+fn caller_token(headers: &HeaderMap) -> Result<Option<TokenKey>, StatusCode> {
     // 获取认证头
     let auth_token = headers
         .get(AUTHORIZATION)
@@ -89,191 +96,278 @@ pub async fn handle_get_logs(
         .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    let user_token = if auth_token != *AUTH_TOKEN {
-        Some(if let Some(token_key) = TokenKey::from_string(auth_token) {
-            token_key
-        } else {
-            parse_dynamic_token(auth_token)
-                .and_then(|key_config| key_config.token_info)
-                .and_then(|info| info.token)
-                .and_then(|t| t.into_raw())
-                .ok_or(StatusCode::UNAUTHORIZED)?
-                .key()
-        })
+    if check_admin(auth_token, Permission::ReadLogs).is_err() {
+        Ok(Some(
+            if let Some(token_key) = TokenKey::from_string(auth_token) {
+                token_key
+            } else {
+                parse_dynamic_token(auth_token)
+                    .and_then(|key_config| key_config.token_info)
+                    .and_then(|info| info.token)
+                    .and_then(|t| t.into_raw())
+                    .ok_or(StatusCode::UNAUTHORIZED)?
+                    .key()
+            },
+        ))
     } else {
-        None
-    };
-
-    // 如果状态存在但无效，直接返回空结果
-    if let Some(status) = &request.query.status
-        && LogStatus::from_str_name(status).is_none()
-    {
-        return Ok(Json(LogsResponse {
-            status: ApiStatus::Success,
-            total: 0,
-            active: None,
-            error: None,
-            logs: Vec::new(),
-            timestamp: DateTime::now(),
-        }));
+        Ok(None)
     }
+}
 
-    // 如果会员类型存在但无效，直接返回空结果
-    let membership_enum = if let Some(membership_type) = &request.query.membership_type {
-        match MembershipType::from_str(membership_type) {
-            Some(m) => Some(m),
-            None => {
-                return Ok(Json(LogsResponse {
-                    status: ApiStatus::Success,
-                    total: 0,
-                    active: None,
-                    error: None,
-                    logs: Vec::new(),
-                    timestamp: DateTime::now(),
-                }));
-            }
+type LogIter<'a> = Box<dyn Iterator<Item = &'a RequestLog> + 'a>;
+
+/// 由查询参数解析出的日志过滤条件
+struct LogFilter<'q> {
+    query: &'q LogsQueryParams,
+    /// 非管理员只能看到自己令牌的日志
+    user_token: Option<TokenKey>,
+    membership_type: Option<MembershipType>,
+    user_id: Option<UserId>,
+}
+
+impl<'q> LogFilter<'q> {
+    /// 参数有效但不可能匹配任何日志时返回 `Ok(None)`
+    fn new(
+        user_token: Option<TokenKey>,
+        query: &'q LogsQueryParams,
+    ) -> Result<Option<Self>, StatusCode> {
+        // 如果状态存在但无效，直接返回空结果
+        if let Some(status) = &query.status
+            && LogStatus::from_str_name(status).is_none()
+        {
+            return Ok(None);
         }
-    } else {
-        None
-    };
 
-    // 如果user_id存在但无效，直接返回空结果
-    let parsed_user_id = if let Some(user_id) = &request.query.user_id {
-        match user_id.parse() {
-            Ok(id) => Some(id),
-            Err(_) => {
-                return Err(StatusCode::BAD_REQUEST);
+        // 如果会员类型存在但无效，直接返回空结果
+        let membership_type = if let Some(membership_type) = &query.membership_type {
+            match MembershipType::from_str(membership_type) {
+                Some(m) => Some(m),
+                None => return Ok(None),
             }
-        }
-    } else {
-        None
-    };
+        } else {
+            None
+        };
+
+        // 如果user_id存在但无效，返回错误
+        let user_id = if let Some(user_id) = &query.user_id {
+            match user_id.parse() {
+                Ok(id) => Some(id),
+                Err(_) => {
+                    return Err(StatusCode::BAD_REQUEST);
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(Some(Self {
+            query,
+            user_token,
+            membership_type,
+            user_id,
+        }))
+    }
 
-    // 准备日志数据
-    let log_manager = state.log_manager_lock().await;
-    let tokens = log_manager.tokens();
-    let mut iterator = Box::new(log_manager.logs().iter()) as Box<dyn Iterator<Item = &RequestLog>>;
+    /// 是否需要日志对应的令牌才能判断
+    #[inline]
+    fn needs_token(&self) -> bool { self.user_id.is_some() || self.query.email.is_some() }
 
-    let (active, error) = if let Some(token_key) = user_token {
-        iterator = Box::new(iterator.filter(move |log| log.token_info.key == token_key));
-        (None, None)
-    } else {
-        (
-            Some(state.active_requests.load(Ordering::Relaxed)),
-            Some(state.error_requests.load(Ordering::Relaxed)),
-        )
-    };
+    /// 判断日志是否匹配，`token` 仅在 [`Self::needs_token`] 为真时调用
+    fn matches<'t>(&self, log: &RequestLog, token: impl FnOnce() -> Option<&'t ExtToken>) -> bool {
+        let query = self.query;
 
-    // 时间范围过滤
-    if let Some(from_date) = request.query.from_date {
-        iterator = Box::new(iterator.filter(move |log| log.timestamp >= from_date));
-    }
+        if let Some(token_key) = self.user_token
+            && log.token_info.key != token_key
+        {
+            return false;
+        }
 
-    if let Some(to_date) = request.query.to_date {
-        iterator = Box::new(iterator.filter(move |log| log.timestamp <= to_date));
-    }
+        // 时间范围过滤
+        if let Some(from_date) = query.from_date
+            && log.timestamp < from_date
+        {
+            return false;
+        }
 
-    // 用户标识过滤
-    if let Some(user_id) = parsed_user_id {
-        iterator = Box::new(iterator.filter(move |log| {
-            tokens
-                .get(&log.token_info.key)
-                .expect(ERR_LOG_TOKEN_NOT_FOUND)
-                .primary_token
-                .raw()
-                .subject
-                .id
-                == user_id
-        }));
-    }
+        if let Some(to_date) = query.to_date
+            && log.timestamp > to_date
+        {
+            return false;
+        }
 
-    if let Some(email) = &request.query.email {
-        iterator = Box::new(iterator.filter(move |log| {
-            tokens
-                .get(&log.token_info.key)
-                .expect(ERR_LOG_TOKEN_NOT_FOUND)
-                .user
-                .as_ref()
-                .map(|user| user.email.contains(email))
-                .unwrap_or(false)
-        }));
-    }
+        // 用户标识过滤
+        if self.needs_token() {
+            let token = token().expect(ERR_LOG_TOKEN_NOT_FOUND);
 
-    if let Some(membership_type) = membership_enum {
-        iterator = Box::new(iterator.filter(move |log| {
-            log.token_info
+            if let Some(user_id) = self.user_id
+                && token.primary_token.raw().subject.id != user_id
+            {
+                return false;
+            }
+
+            if let Some(email) = &query.email
+                && !token
+                    .user
+                    .as_ref()
+                    .map(|user| user.email.contains(email))
+                    .unwrap_or(false)
+            {
+                return false;
+            }
+        }
+
+        if let Some(membership_type) = self.membership_type
+            && !log
+                .token_info
                 .stripe
                 .as_ref()
                 .map(|p| p.membership_type == membership_type)
                 .unwrap_or(false)
-        }));
-    }
+        {
+            return false;
+        }
 
-    // 核心业务过滤
-    if let Some(status) = &request.query.status {
-        iterator = Box::new(iterator.filter(move |log| log.status.as_str_name() == status));
-    }
+        // 核心业务过滤
+        if let Some(status) = &query.status
+            && log.status.as_str_name() != status
+        {
+            return false;
+        }
 
-    if let Some(model) = &request.query.model {
-        iterator = Box::new(iterator.filter(move |log| log.model.contains(model)));
-    }
+        if let Some(model) = &query.model
+            && !log.model.contains(model)
+        {
+            return false;
+        }
 
-    if let Some(include_models) = &request.query.include_models {
-        iterator =
-            Box::new(iterator.filter(move |log| include_models.iter().any(|m| log.model == *m)));
-    }
+        if let Some(include_models) = &query.include_models
+            && !include_models.iter().any(|m| log.model == *m)
+        {
+            return false;
+        }
 
-    if let Some(exclude_models) = &request.query.exclude_models {
-        iterator =
-            Box::new(iterator.filter(move |log| !exclude_models.iter().any(|m| log.model == *m)));
-    }
+        if let Some(exclude_models) = &query.exclude_models
+            && exclude_models.iter().any(|m| log.model == *m)
+        {
+            return false;
+        }
 
-    // 请求特征过滤
-    if let Some(stream) = request.query.stream {
-        iterator = Box::new(iterator.filter(move |log| log.stream == stream));
-    }
+        // 请求特征过滤
+        if let Some(stream) = query.stream
+            && log.stream != stream
+        {
+            return false;
+        }
 
-    if let Some(has_chain) = request.query.has_chain {
-        iterator = Box::new(iterator.filter(move |log| log.chain.is_some() == has_chain));
-    }
+        if let Some(has_chain) = query.has_chain
+            && log.chain.is_some() != has_chain
+        {
+            return false;
+        }
 
-    // 错误相关过滤
-    if let Some(has_error) = request.query.has_error {
-        iterator = Box::new(iterator.filter(move |log| log.error.is_some() == has_error));
-    }
+        // 错误相关过滤
+        if let Some(has_error) = query.has_error
+            && log.error.is_some() != has_error
+        {
+            return false;
+        }
 
-    if let Some(error) = &request.query.error {
-        iterator = Box::new(iterator.filter(move |log| log.error.contains(error)));
-    }
+        if let Some(error) = &query.error
+            && !log.error.contains(error)
+        {
+            return false;
+        }
 
-    // 性能指标过滤
-    if let Some(min_time) = request.query.min_total_time {
-        iterator = Box::new(iterator.filter(move |log| log.timing.total >= min_time));
-    }
+        if let Some(proxy_error) = query.proxy_error
+            && log.error.is_proxy() != proxy_error
+        {
+            return false;
+        }
 
-    if let Some(max_time) = request.query.max_total_time {
-        iterator = Box::new(iterator.filter(move |log| log.timing.total <= max_time));
-    }
+        // 性能指标过滤
+        if let Some(min_time) = query.min_total_time
+            && log.timing.total < min_time
+        {
+            return false;
+        }
 
-    if let Some(min_tokens) = request.query.min_tokens {
-        iterator = Box::new(iterator.filter(move |log| {
-            log.chain
-                .as_ref()
-                .and_then(|c| c.usage)
-                .map(|u| (u.input + u.output) >= min_tokens)
-                .unwrap_or(false)
-        }));
+        if let Some(max_time) = query.max_total_time
+            && log.timing.total > max_time
+        {
+            return false;
+        }
+
+        let tokens = log
+            .chain
+            .as_ref()
+            .and_then(|c| c.usage)
+            .map(|u| u.input + u.output);
+
+        if let Some(min_tokens) = query.min_tokens
+            && !tokens.is_some_and(|t| t >= min_tokens)
+        {
+            return false;
+        }
+
+        if let Some(max_tokens) = query.max_tokens
+            && !tokens.is_some_and(|t| t <= max_tokens)
+        {
+            return false;
+        }
+
+        true
     }
+}
 
-    if let Some(max_tokens) = request.query.max_tokens {
-        iterator = Box::new(iterator.filter(move |log| {
-            log.chain
-                .as_ref()
-                .and_then(|c| c.usage)
-                .map(|u| (u.input + u.output) <= max_tokens)
-                .unwrap_or(false)
+/// 按查询参数过滤日志，非管理员只能看到自己令牌的日志
+///
+/// `after` 不为空时只返回ID大于该值的日志；参数有效但不可能匹配任何日志时返回 `Ok(None)`
+fn filter_logs<'a>(
+    log_manager: &'a LogManager,
+    user_token: Option<TokenKey>,
+    after: Option<u64>,
+    query: &'a LogsQueryParams,
+) -> Result<Option<LogIter<'a>>, StatusCode> {
+    let Some(filter) = LogFilter::new(user_token, query)? else {
+        return Ok(None);
+    };
+
+    // 日志按ID递增排列
+    let logs = log_manager.logs();
+    let start = after.map_or(0, |id| logs.partition_point(|log| log.id <= id));
+    Ok(Some(Box::new(logs.range(start..).filter(move |log| {
+        filter.matches(log, || log_manager.get_token(&log.token_info.key))
+    }))))
+}
+
+pub async fn handle_get_logs(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<LogsRequest>,
+) -> Result<Json<LogsResponse>, StatusCode> {
+    let user_token = caller_token(&headers)?;
+
+    // 准备日志数据
+    let log_manager = state.log_manager_lock().await;
+    let Some(iterator) = filter_logs(&log_manager, user_token, None, &request.query)? else {
+        return Ok(Json(LogsResponse {
+            status: ApiStatus::Success,
+            total: 0,
+            active: None,
+            error: None,
+            logs: Vec::new(),
+            timestamp: DateTime::now(),
         }));
-    }
+    };
+
+    let (active, error) = if user_token.is_some() {
+        (None, None)
+    } else {
+        (
+            Some(state.active_requests.load(Ordering::Relaxed)),
+            Some(state.error_requests.load(Ordering::Relaxed)),
+        )
+    };
 
     // 收集过滤后的日志引用
     let filtered_logs: Vec<_> = iterator.cloned().collect();
@@ -324,27 +418,7 @@ pub async fn handle_get_logs_tokens(
     headers: HeaderMap,
     Json(keys): Json<HashSet<String>>,
 ) -> Result<Json<LogsTokensResponse>, StatusCode> {
-    // 获取认证头
-    let auth_token = headers
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
-    let user_token = if auth_token != *AUTH_TOKEN {
-        Some(if let Some(token_key) = TokenKey::from_string(auth_token) {
-            token_key
-        } else {
-            parse_dynamic_token(auth_token)
-                .and_then(|key_config| key_config.token_info)
-                .and_then(|info| info.token)
-                .and_then(|t| t.into_raw())
-                .ok_or(StatusCode::UNAUTHORIZED)?
-                .key()
-        })
-    } else {
-        None
-    };
+    let user_token = caller_token(&headers)?;
 
     if let Some(token_key) = user_token {
         let mut iter = keys.into_iter();
@@ -400,3 +474,276 @@ pub struct LogsTokensResponse {
     pub total: u64,
     pub timestamp: DateTime,
 }
+
+/// 统计分组维度
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum LogsGroupBy {
+    /// 模型名称
+    Model,
+    /// 令牌别名，令牌不在令牌列表中时使用令牌标识
+    Token,
+    /// 令牌标识，动态密钥与令牌列表中的令牌均有
+    Key,
+    /// 请求状态
+    Status,
+    /// 错误标题
+    Error,
+}
+
+/// 统计时间粒度，按服务器时区对齐
+#[derive(::serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum LogsTimeBucket {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl LogsTimeBucket {
+    fn start_of(self, time: &DateTime) -> chrono::NaiveDateTime {
+        use chrono::{Datelike as _, Timelike as _};
+        let local = time.naive_local();
+        let date = local.date();
+        let (date, hour, minute) = match self {
+            Self::Minute => (date, local.hour(), local.minute()),
+            Self::Hour => (date, local.hour(), 0),
+            Self::Day => (date, 0, 0),
+            Self::Week => (
+                date - chrono::Days::new(date.weekday().num_days_from_monday() as u64),
+                0,
+                0,
+            ),
+            Self::Month => (__unwrap!(date.with_day(1)), 0, 0),
+        };
+        __unwrap!(date.and_hms_opt(hour, minute, 0))
+    }
+}
+
+#[derive(::serde::Deserialize)]
+pub struct LogsAnalyticsRequest {
+    #[serde(default)]
+    pub query: LogsQueryParams,
+    /// 分组维度，可组合多个
+    #[serde(default)]
+    pub group_by: Vec<LogsGroupBy>,
+    /// 时间粒度，为空则不按时间分组
+    pub bucket: Option<LogsTimeBucket>,
+}
+
+/// 分布统计，单位为秒
+#[derive(::serde::Serialize)]
+pub struct Distribution {
+    pub avg: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    fn from_samples(mut samples: Vec<f64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable_by(f64::total_cmp);
+        // 最近秩法
+        let percentile = |p: usize| {
+            let rank = (samples.len() * p).div_ceil(100).max(1);
+            samples[rank - 1]
+        };
+        Some(Self {
+            avg: samples.iter().sum::<f64>() / samples.len() as f64,
+            p50: percentile(50),
+            p90: percentile(90),
+            p95: percentile(95),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+        })
+    }
+}
+
+#[derive(::serde::Serialize, Default)]
+pub struct UsageTotals {
+    pub input: i64,
+    pub output: i64,
+    pub cache_write: i64,
+    pub cache_read: i64,
+    pub cents: f64,
+}
+
+#[derive(::serde::Serialize)]
+pub struct LogsAnalyticsGroup {
+    /// 时间桶起点（服务器时区）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<chrono::NaiveDateTime>,
+    /// 各分组维度的取值
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub key: HashMap<LogsGroupBy, Option<String>>,
+    pub count: u64,
+    pub success: u64,
+    pub failure: u64,
+    pub pending: u64,
+    /// 已结束请求中失败的比例
+    pub error_rate: f64,
+    /// 已结束请求的总耗时分布
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<Distribution>,
+    /// 首个内容的到达时间分布
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttft: Option<Distribution>,
+    pub usage: UsageTotals,
+}
+
+#[derive(Default)]
+struct GroupAccumulator {
+    success: u64,
+    failure: u64,
+    pending: u64,
+    latencies: Vec<f64>,
+    ttfts: Vec<f64>,
+    usage: UsageTotals,
+}
+
+impl GroupAccumulator {
+    fn push(&mut self, log: &RequestLog) {
+        match log.status {
+            LogStatus::Pending => self.pending += 1,
+            LogStatus::Success => self.success += 1,
+            LogStatus::Failure => self.failure += 1,
+        }
+        if log.status != LogStatus::Pending {
+            self.latencies.push(log.timing.total);
+        }
+        if let Some(chain) = &log.chain {
+            if let Some((_, delays)) = &chain.delays
+                && let Some(&(_, first)) = delays.first()
+            {
+                self.ttfts.push(first as f64);
+            }
+            if let Some(usage) = chain.usage {
+                self.usage.input += usage.input as i64;
+                self.usage.output += usage.output as i64;
+                self.usage.cache_write += usage.cache_write as i64;
+                self.usage.cache_read += usage.cache_read as i64;
+                self.usage.cents += usage.cents as f64;
+            }
+        }
+    }
+
+    fn finish(
+        self,
+        bucket: Option<chrono::NaiveDateTime>,
+        key: HashMap<LogsGroupBy, Option<String>>,
+    ) -> LogsAnalyticsGroup {
+        let finished = self.success + self.failure;
+        LogsAnalyticsGroup {
+            bucket,
+            key,
+            count: finished + self.pending,
+            success: self.success,
+            failure: self.failure,
+            pending: self.pending,
+            error_rate: if finished == 0 {
+                0.0
+            } else {
+                self.failure as f64 / finished as f64
+            },
+            latency: Distribution::from_samples(self.latencies),
+            ttft: Distribution::from_samples(self.ttfts),
+            usage: self.usage,
+        }
+    }
+}
+
+#[inline]
+fn error_title(error: &crate::app::model::ErrorInfo) -> Option<&'static str> {
+    use crate::app::model::ErrorInfo;
+    match *error {
+        ErrorInfo::None => None,
+        ErrorInfo::Error(error)
+        | ErrorInfo::Details { error, .. }
+        | ErrorInfo::Proxy { error, .. } => Some(error),
+    }
+}
+
+pub async fn handle_get_logs_analytics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<LogsAnalyticsRequest>,
+) -> Result<Json<LogsAnalyticsResponse>, StatusCode> {
+    let user_token = caller_token(&headers)?;
+
+    // 按别名分组时需要令牌列表，先于日志加锁
+    let token_manager = if request.group_by.contains(&LogsGroupBy::Token) {
+        Some(state.token_manager_read().await)
+    } else {
+        None
+    };
+    let alias_of = |key: &TokenKey| -> String {
+        token_manager
+            .as_ref()
+            .and_then(|manager| {
+                let id = *manager.id_map().get(key)?;
+                manager.id_to_alias().get(id)?.as_ref()
+            })
+            .map_or_else(|| key.to_string(), ToString::to_string)
+    };
+
+    let log_manager = state.log_manager_lock().await;
+    let mut total = 0;
+    let mut groups: HashMap<
+        (Option<chrono::NaiveDateTime>, Vec<Option<String>>),
+        GroupAccumulator,
+    > = HashMap::default();
+
+    if let Some(iterator) = filter_logs(&log_manager, user_token, None, &request.query)? {
+        for log in iterator {
+            total += 1;
+            let bucket = request.bucket.map(|bucket| bucket.start_of(&log.timestamp));
+            let key = request
+                .group_by
+                .iter()
+                .map(|group_by| match group_by {
+                    LogsGroupBy::Model => Some(log.model.to_string()),
+                    LogsGroupBy::Token => Some(alias_of(&log.token_info.key)),
+                    LogsGroupBy::Key => Some(log.token_info.key.to_string()),
+                    LogsGroupBy::Status => Some(log.status.as_str_name().to_string()),
+                    LogsGroupBy::Error => error_title(&log.error).map(ToString::to_string),
+                })
+                .collect();
+            groups.entry((bucket, key)).or_default().push(log);
+        }
+    }
+
+    drop(log_manager);
+    drop(token_manager);
+
+    let mut groups: Vec<LogsAnalyticsGroup> = groups
+        .into_iter()
+        .map(|((bucket, values), acc)| {
+            let key = request.group_by.iter().copied().zip(values).collect();
+            acc.finish(bucket, key)
+        })
+        .collect();
+    // 按时间先后，同一时间内按请求数从多到少
+    groups.sort_unstable_by(|a, b| a.bucket.cmp(&b.bucket).then(b.count.cmp(&a.count)));
+
+    Ok(Json(LogsAnalyticsResponse {
+        status: ApiStatus::Success,
+        total,
+        groups,
+        timestamp: DateTime::now(),
+    }))
+}
+
+#[derive(::serde::Serialize)]
+pub struct LogsAnalyticsResponse {
+    pub status: ApiStatus,
+    pub total: u64,
+    pub groups: Vec<LogsAnalyticsGroup>,
+    pub timestamp: DateTime,
+}
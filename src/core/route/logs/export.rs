@@ -0,0 +1,313 @@
+use super::{LogsQueryParams, caller_token, filter_logs};
+use crate::app::{
+    constant::{HEADER_VALUE_APPLICATION_X_NDJSON, HEADER_VALUE_TEXT_CSV_UTF8},
+    model::{
+        AppState, ChainUsage, DateTime, ErrorInfo, LogStatus, LogTokenInfo, Prompt, RequestLog,
+        TimingInfo, TokenKey,
+    },
+};
+use axum::{
+    Json,
+    body::Body,
+    extract::State,
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    },
+    response::Response,
+};
+use bytes::Bytes;
+use std::{convert::Infallible, fmt::Write as _, net::IpAddr, sync::Arc};
+
+/// 每批导出的日志数，每批之间释放日志锁
+const EXPORT_BATCH_SIZE: usize = 256;
+
+#[derive(::serde::Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogsExportFormat {
+    #[default]
+    Ndjson,
+    Csv,
+}
+
+#[derive(::serde::Deserialize)]
+pub struct LogsExportRequest {
+    /// 过滤条件，`limit` 限制导出总数，`offset` 与 `reverse` 不生效
+    #[serde(default)]
+    pub query: LogsQueryParams,
+    #[serde(default)]
+    pub format: LogsExportFormat,
+    /// 从该日志ID之后开始导出，用于断点续传
+    pub cursor: Option<u64>,
+    #[serde(default)]
+    pub include_prompt: bool,
+    #[serde(default)]
+    pub include_think: bool,
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+/// 单条导出记录
+#[derive(::serde::Serialize)]
+struct ExportRecord<'a> {
+    id: u64,
+    timestamp: DateTime,
+    model: &'static str,
+    token_info: &'a LogTokenInfo,
+    timing: TimingInfo,
+    stream: bool,
+    status: LogStatus,
+    error: ErrorInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_ip: Option<IpAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<ChainUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt: Option<&'a Prompt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<&'a str>,
+}
+
+impl<'a> ExportRecord<'a> {
+    fn new(log: &'a RequestLog, request: &LogsExportRequest) -> Self {
+        let chain = log.chain.as_ref();
+        Self {
+            id: log.id,
+            timestamp: log.timestamp,
+            model: log.model,
+            token_info: &log.token_info,
+            timing: log.timing,
+            stream: log.stream,
+            status: log.status,
+            error: log.error,
+            client_ip: log.client_ip,
+            usage: chain
+                .and_then(|c| c.usage)
+                .filter(|_| request.include_usage),
+            prompt: chain
+                .map(|c| &c.prompt)
+                .filter(|p| request.include_prompt && p.is_some()),
+            think: chain
+                .and_then(|c| c.think.as_deref())
+                .filter(|_| request.include_think),
+        }
+    }
+
+    fn write_ndjson(&self, buf: &mut Vec<u8>) {
+        if serde_json::to_writer(&mut *buf, self).is_ok() {
+            buf.push(b'\n');
+        }
+    }
+
+    fn write_csv(&self, request: &LogsExportRequest, buf: &mut String) {
+        let _ = write!(
+            buf,
+            "{},{},",
+            self.id,
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        );
+        write_csv_field(buf, self.model);
+        buf.push(',');
+        buf.push_str(&self.token_info.key.to_string());
+        let _ = write!(
+            buf,
+            ",{},{},{},",
+            self.stream,
+            self.status.as_str_name(),
+            self.timing.total
+        );
+        write_csv_field(buf, &error_text(&self.error));
+        buf.push(',');
+        if let Some(ip) = self.client_ip {
+            let _ = write!(buf, "{ip}");
+        }
+        if request.include_usage {
+            match self.usage {
+                Some(u) => {
+                    let _ = write!(
+                        buf,
+                        ",{},{},{},{},{}",
+                        u.input, u.output, u.cache_write, u.cache_read, u.cents
+                    );
+                }
+                None => buf.push_str(",,,,,"),
+            }
+        }
+        if request.include_prompt {
+            buf.push(',');
+            if let Some(prompt) = self.prompt {
+                let prompt = match prompt {
+                    Prompt::Origin(s) => s.clone(),
+                    _ => serde_json::to_string(prompt).unwrap_or_default(),
+                };
+                write_csv_field(buf, &prompt);
+            }
+        }
+        if request.include_think {
+            buf.push(',');
+            if let Some(think) = self.think {
+                write_csv_field(buf, think);
+            }
+        }
+        buf.push_str("\r\n");
+    }
+}
+
+fn csv_header(request: &LogsExportRequest) -> String {
+    let mut header =
+        String::from("id,timestamp,model,token,stream,status,total_time,error,client_ip");
+    if request.include_usage {
+        header.push_str(",input,output,cache_write,cache_read,cents");
+    }
+    if request.include_prompt {
+        header.push_str(",prompt");
+    }
+    if request.include_think {
+        header.push_str(",think");
+    }
+    header.push_str("\r\n");
+    header
+}
+
+#[inline]
+fn write_csv_field(buf: &mut String, field: &str) {
+    if field.contains([',', '"', '\r', '\n']) {
+        buf.push('"');
+        buf.push_str(&field.replace('"', "\"\""));
+        buf.push('"');
+    } else {
+        buf.push_str(field);
+    }
+}
+
+fn error_text(error: &ErrorInfo) -> String {
+    match *error {
+        ErrorInfo::None => String::new(),
+        ErrorInfo::Error(error) => error.to_string(),
+        ErrorInfo::Details { error, details } =>
+            if error.is_empty() {
+                details.to_string()
+            } else {
+                format!("{error}: {details}")
+            },
+        ErrorInfo::Proxy {
+            proxy,
+            error,
+            details: None,
+        } => format!("[{proxy}] {error}"),
+        ErrorInfo::Proxy {
+            proxy,
+            error,
+            details: Some(details),
+        } => format!("[{proxy}] {error}: {details}"),
+    }
+}
+
+/// 导出进度
+struct ExportCursor {
+    /// 已导出的最后一条日志ID
+    last_id: Option<u64>,
+    /// 剩余可导出数量
+    remaining: usize,
+    /// 尚未输出CSV表头
+    header: Option<String>,
+    done: bool,
+}
+
+/// 读取下一批日志并编码，仅在读取期间持有日志锁
+async fn next_batch(
+    state: &AppState,
+    request: &LogsExportRequest,
+    user_token: Option<TokenKey>,
+    end_id: u64,
+    cursor: &mut ExportCursor,
+) -> Option<Bytes> {
+    let mut ndjson = Vec::new();
+    let mut csv = cursor.header.take().unwrap_or_default();
+
+    let log_manager = state.log_manager_lock().await;
+    let iterator = filter_logs(&log_manager, user_token, cursor.last_id, &request.query)
+        .ok()
+        .flatten()?;
+    let limit = cursor.remaining.min(EXPORT_BATCH_SIZE);
+    let mut count = 0;
+    for log in iterator.take_while(|log| log.id <= end_id).take(limit) {
+        let record = ExportRecord::new(log, request);
+        match request.format {
+            LogsExportFormat::Ndjson => record.write_ndjson(&mut ndjson),
+            LogsExportFormat::Csv => record.write_csv(request, &mut csv),
+        }
+        cursor.last_id = Some(log.id);
+        count += 1;
+    }
+    drop(log_manager);
+
+    cursor.remaining -= count;
+    if count < limit || cursor.remaining == 0 {
+        cursor.done = true;
+    }
+
+    let bytes = match request.format {
+        LogsExportFormat::Ndjson => Bytes::from(ndjson),
+        LogsExportFormat::Csv => Bytes::from(csv),
+    };
+    if bytes.is_empty() { None } else { Some(bytes) }
+}
+
+pub async fn handle_export_logs(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<LogsExportRequest>,
+) -> Result<Response, StatusCode> {
+    let user_token = caller_token(&headers)?;
+
+    // 校验参数并固定导出上界，导出过程中新增的日志不会被包含
+    let end_id = {
+        let log_manager = state.log_manager_lock().await;
+        filter_logs(&log_manager, user_token, None, &request.query)?
+            .and_then(|_| log_manager.logs().back().map(|log| log.id))
+    };
+
+    let (content_type, disposition) = match request.format {
+        LogsExportFormat::Ndjson => (
+            HEADER_VALUE_APPLICATION_X_NDJSON,
+            HeaderValue::from_static("attachment; filename=\"logs.ndjson\""),
+        ),
+        LogsExportFormat::Csv => (
+            HEADER_VALUE_TEXT_CSV_UTF8,
+            HeaderValue::from_static("attachment; filename=\"logs.csv\""),
+        ),
+    };
+    let cursor = ExportCursor {
+        last_id: request.cursor,
+        remaining: request.query.limit.unwrap_or(usize::MAX),
+        header: (request.format == LogsExportFormat::Csv).then(|| csv_header(&request)),
+        done: end_id.is_none(),
+    };
+    let end_id = end_id.unwrap_or_default();
+    let request = Arc::new(request);
+
+    let stream = futures::stream::unfold(cursor, move |mut cursor| {
+        let state = state.clone();
+        let request = request.clone();
+        async move {
+            // 无匹配日志时仍需输出CSV表头
+            if cursor.done {
+                return cursor
+                    .header
+                    .take()
+                    .map(|header| (Ok::<_, Infallible>(Bytes::from(header)), cursor));
+            }
+            let bytes = next_batch(&state, &request, user_token, end_id, &mut cursor).await?;
+            Some((Ok(bytes), cursor))
+        }
+    });
+
+    Ok(__unwrap!(
+        Response::builder()
+            .header(CONTENT_TYPE, content_type)
+            .header(CONTENT_DISPOSITION, disposition)
+            .body(Body::from_stream(stream))
+    ))
+}
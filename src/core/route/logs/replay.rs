@@ -0,0 +1,340 @@
+use crate::{
+    app::model::{
+        AppState, Capture, CaptureKind, CapturedResponse, ChainUsage, ErrorInfo, LogStatus,
+    },
+    common::{
+        model::{ApiStatus, GenericError},
+        utils::format_time_ms,
+    },
+    core::{
+        config::KeyConfig,
+        service::{handle_chat_completions, handle_messages},
+    },
+};
+use axum::{
+    Json,
+    extract::State,
+    http::{Extensions, StatusCode},
+    response::IntoResponse as _,
+};
+use serde_json::Value;
+use std::{borrow::Cow, sync::Arc, time::Instant};
+
+const ERROR_CAPTURE_NOT_FOUND: &str = "capture_not_found";
+const ERROR_TOKEN_NOT_FOUND: &str = "token_not_found";
+const ERROR_INVALID_CAPTURE: &str = "invalid_capture";
+
+#[derive(::serde::Deserialize)]
+pub struct LogsReplayRequest {
+    /// 日志ID
+    pub id: u64,
+    /// 回放使用的模型，默认与原请求相同
+    pub model: Option<String>,
+    /// 回放使用的令牌别名，默认使用原请求的令牌
+    pub token: Option<String>,
+    /// 是否在响应中包含原始请求
+    #[serde(default)]
+    pub include_request: bool,
+}
+
+/// 对比中的用量，两侧统一为 token 数
+#[derive(::serde::Serialize, Default)]
+struct ReplayUsage {
+    input: i64,
+    output: i64,
+    cache_write: i64,
+    cache_read: i64,
+}
+
+impl From<ChainUsage> for ReplayUsage {
+    fn from(usage: ChainUsage) -> Self {
+        Self {
+            input: usage.input as i64,
+            output: usage.output as i64,
+            cache_write: usage.cache_write as i64,
+            cache_read: usage.cache_read as i64,
+        }
+    }
+}
+
+/// 对比的一侧
+#[derive(::serde::Serialize)]
+struct ReplaySide {
+    model: String,
+    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<ReplayUsage>,
+    total_time: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Value>,
+}
+
+#[derive(::serde::Serialize)]
+pub struct LogsReplayResponse {
+    status: ApiStatus,
+    id: u64,
+    kind: CaptureKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request: Option<Value>,
+    original: ReplaySide,
+    replay: ReplaySide,
+    same_content: bool,
+}
+
+type ReplayError = (StatusCode, Json<GenericError>);
+
+#[inline]
+fn replay_error(status: StatusCode, error: &'static str, message: String) -> ReplayError {
+    (
+        status,
+        Json(GenericError {
+            status: ApiStatus::Error,
+            code: None,
+            error: Some(Cow::Borrowed(error)),
+            message: Some(Cow::Owned(message)),
+        }),
+    )
+}
+
+/// 原请求一侧，响应缺失时退回到日志中的信息
+async fn original_side(state: &AppState, capture: &Capture, request: &Value) -> ReplaySide {
+    let log_manager = state.log_manager_lock().await;
+    let log = log_manager
+        .find_log_with_token(capture.id)
+        .map(|(log, _)| log);
+    let model = request
+        .get("model")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| log.map(|log| log.model.to_string()))
+        .unwrap_or_default();
+    let response = capture
+        .response
+        .clone()
+        .or_else(|| log.map(CapturedResponse::from_log));
+    let error = log
+        .filter(|log| log.status == LogStatus::Failure)
+        .and_then(|log| match log.error {
+            ErrorInfo::None => None,
+            error => Some(serde_json::to_value(error).unwrap_or_default()),
+        });
+    match response {
+        Some(response) => ReplaySide {
+            model,
+            content: response.content,
+            think: response.think,
+            usage: response.usage.map(ReplayUsage::from),
+            total_time: response.total_time,
+            error,
+        },
+        None => ReplaySide {
+            model,
+            content: String::new(),
+            think: None,
+            usage: None,
+            total_time: 0.0,
+            error,
+        },
+    }
+}
+
+/// 从非流式响应中提取文本、思考与用量
+fn parse_response(
+    kind: CaptureKind,
+    body: &Value,
+) -> (String, Option<String>, Option<ReplayUsage>) {
+    let int = |v: &Value, key: &str| v.get(key).and_then(Value::as_i64).unwrap_or_default();
+    match kind {
+        CaptureKind::ChatCompletions => {
+            let content = body
+                .pointer("/choices/0/message/content")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let usage = body.get("usage").map(|u| ReplayUsage {
+                input: int(u, "prompt_tokens"),
+                output: int(u, "completion_tokens"),
+                cache_write: 0,
+                cache_read: u
+                    .pointer("/prompt_tokens_details/cached_tokens")
+                    .and_then(Value::as_i64)
+                    .unwrap_or_default(),
+            });
+            (content, None, usage)
+        }
+        CaptureKind::Messages => {
+            let mut content = String::new();
+            let mut think = None::<String>;
+            for block in body
+                .get("content")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                match block.get("type").and_then(Value::as_str) {
+                    Some("text") => content.push_str(
+                        block
+                            .get("text")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default(),
+                    ),
+                    Some("thinking") => think.get_or_insert_default().push_str(
+                        block
+                            .get("thinking")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default(),
+                    ),
+                    _ => {}
+                }
+            }
+            let usage = body.get("usage").map(|u| ReplayUsage {
+                input: int(u, "input_tokens"),
+                output: int(u, "output_tokens"),
+                cache_write: int(u, "cache_creation_input_tokens"),
+                cache_read: int(u, "cache_read_input_tokens"),
+            });
+            (content, think, usage)
+        }
+    }
+}
+
+pub async fn handle_replay_log(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<LogsReplayRequest>,
+) -> Result<Json<LogsReplayResponse>, ReplayError> {
+    let capture = state.get_capture(request.id).ok_or_else(|| {
+        replay_error(
+            StatusCode::NOT_FOUND,
+            ERROR_CAPTURE_NOT_FOUND,
+            format!("日志 {} 没有捕获记录", request.id),
+        )
+    })?;
+    let original_request: Value = serde_json::from_slice(&capture.request).map_err(|e| {
+        replay_error(
+            StatusCode::BAD_REQUEST,
+            ERROR_INVALID_CAPTURE,
+            e.to_string(),
+        )
+    })?;
+
+    // 选择回放令牌，令牌池中的令牌按管理员请求处理
+    let token = if let Some(alias) = &request.token {
+        state
+            .token_manager_read()
+            .await
+            .get_by_alias(alias)
+            .map(|info| (info.bundle.clone_without_user(), true))
+    } else {
+        let token = state
+            .log_manager_lock()
+            .await
+            .find_log_with_token(capture.id)
+            .map(|(_, token)| token.clone_without_user());
+        match token {
+            Some(token) => {
+                let is_pri = state
+                    .token_manager_read()
+                    .await
+                    .id_map()
+                    .contains_key(&token.primary_token.key());
+                Some((token, is_pri))
+            }
+            None => None,
+        }
+    };
+    let Some(token) = token else {
+        return Err(replay_error(
+            StatusCode::NOT_FOUND,
+            ERROR_TOKEN_NOT_FOUND,
+            match request.token {
+                Some(alias) => format!("令牌 {alias} 不存在"),
+                None => "原请求的令牌已不可用，请指定令牌".to_string(),
+            },
+        ));
+    };
+
+    // 以非流式方式重新发送
+    let mut replay_request = original_request.clone();
+    if let Some(object) = replay_request.as_object_mut() {
+        if let Some(model) = &request.model {
+            object.insert("model".to_string(), Value::String(model.clone()));
+        }
+        object.insert("stream".to_string(), Value::Bool(false));
+        object.remove("stream_options");
+    }
+    let replay_model = replay_request
+        .get("model")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    let mut extensions = Extensions::new();
+    extensions.insert(token);
+    extensions.insert(KeyConfig::new_with_global());
+    let invalid = |e: serde_json::Error| {
+        replay_error(
+            StatusCode::BAD_REQUEST,
+            ERROR_INVALID_CAPTURE,
+            e.to_string(),
+        )
+    };
+
+    let start = Instant::now();
+    let response = match capture.kind {
+        CaptureKind::ChatCompletions => handle_chat_completions(
+            State(state.clone()),
+            extensions,
+            Json(serde_json::from_value(replay_request).map_err(invalid)?),
+        )
+        .await
+        .into_response(),
+        CaptureKind::Messages => handle_messages(
+            State(state.clone()),
+            extensions,
+            Json(serde_json::from_value(replay_request).map_err(invalid)?),
+        )
+        .await
+        .into_response(),
+    };
+    let success = response.status().is_success();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())
+        .unwrap_or_default();
+    let total_time = format_time_ms(start.elapsed().as_secs_f64());
+
+    let replay = if success {
+        let (content, think, usage) = parse_response(capture.kind, &body);
+        ReplaySide {
+            model: replay_model,
+            content,
+            think,
+            usage,
+            total_time,
+            error: None,
+        }
+    } else {
+        ReplaySide {
+            model: replay_model,
+            content: String::new(),
+            think: None,
+            usage: None,
+            total_time,
+            error: Some(body),
+        }
+    };
+    let original = original_side(&state, &capture, &original_request).await;
+
+    Ok(Json(LogsReplayResponse {
+        status: ApiStatus::Success,
+        id: capture.id,
+        kind: capture.kind,
+        request: request.include_request.then_some(original_request),
+        same_content: original.content == replay.content,
+        original,
+        replay,
+    }))
+}
@@ -0,0 +1,106 @@
+use super::{LogFilter, LogsRequest, caller_token};
+use crate::{
+    app::{
+        constant::{EVENT_STREAM, NO_CACHE_REVALIDATE},
+        lazy::STREAM_KEEPALIVE_INTERVAL,
+        model::{AppState, LogEvent, RequestLog, TokenKey},
+    },
+    core::stream::keepalive::KeepAliveStream,
+};
+use axum::{
+    Json,
+    body::Body,
+    extract::State,
+    http::{
+        HeaderMap, StatusCode,
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+    },
+    response::Response,
+};
+use bytes::Bytes;
+use std::{convert::Infallible, sync::Arc};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+
+/// 实时日志订阅的状态
+struct Tail {
+    state: Arc<AppState>,
+    events: Receiver<LogEvent>,
+    request: LogsRequest,
+    user_token: Option<TokenKey>,
+}
+
+impl Tail {
+    /// 判断日志是否符合订阅条件，需要令牌信息时短暂持有日志锁
+    async fn matches(&self, log: &RequestLog) -> bool {
+        let Ok(Some(filter)) = LogFilter::new(self.user_token, &self.request.query) else {
+            return false;
+        };
+        if filter.needs_token() {
+            let log_manager = self.state.log_manager_lock().await;
+            // 日志可能已被淘汰，此时无法判断
+            match log_manager.get_token(&log.token_info.key) {
+                Some(token) => filter.matches(log, || Some(token)),
+                None => false,
+            }
+        } else {
+            filter.matches(log, || None)
+        }
+    }
+
+    /// 等待下一条符合条件的事件并编码为 SSE
+    async fn next(&mut self) -> Option<Bytes> {
+        loop {
+            let (event, log) = match self.events.recv().await {
+                Ok(LogEvent::Created(log)) => ("created", log),
+                Ok(LogEvent::Updated(log)) => ("updated", log),
+                Err(RecvError::Lagged(skipped)) => {
+                    return Some(Bytes::from(format!(
+                        "event: lagged\ndata: {{\"skipped\":{skipped}}}\n\n"
+                    )));
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            if !self.matches(&log).await {
+                continue;
+            }
+            let Ok(data) = serde_json::to_string(&log) else {
+                continue;
+            };
+            return Some(Bytes::from(format!(
+                "event: {event}\nid: {}\ndata: {data}\n\n",
+                log.id
+            )));
+        }
+    }
+}
+
+pub async fn handle_tail_logs(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<LogsRequest>,
+) -> Result<Response, StatusCode> {
+    let user_token = caller_token(&headers)?;
+    // 提前校验参数，无效的 user_id 直接返回错误
+    LogFilter::new(user_token, &request.query)?;
+
+    let tail = Tail {
+        events: state.subscribe_logs(),
+        state,
+        request,
+        user_token,
+    };
+    let stream = futures::stream::unfold(tail, |mut tail| async move {
+        let bytes = tail.next().await?;
+        Some((Ok::<_, Infallible>(bytes), tail))
+    });
+    let stream = KeepAliveStream::new(stream, STREAM_KEEPALIVE_INTERVAL.get(), || {
+        Bytes::from_static(b": keep-alive\n\n")
+    });
+
+    Ok(__unwrap!(
+        Response::builder()
+            .header(CACHE_CONTROL, NO_CACHE_REVALIDATE)
+            .header(CONTENT_TYPE, EVENT_STREAM)
+            .body(Body::from_stream(stream))
+    ))
+}
@@ -5,7 +5,7 @@ use crate::app::{
         ROUTE_CONFIG_PATH, ROUTE_PROXIES_PATH, ROUTE_README_PATH, ROUTE_SHARED_JS_PATH,
         ROUTE_SHARED_STYLES_PATH, ROUTE_TOKENS_PATH, get_content_type_by_extension,
     },
-    lazy::STATIC_DIR,
+    lazy::{ROUTE_PREFIX, STATIC_DIR},
     model::AppConfig,
 };
 use axum::{
@@ -106,7 +106,7 @@ pub async fn handle_about() -> Response {
         .into_response(|| {
             Response::builder()
                 .status(StatusCode::TEMPORARY_REDIRECT)
-                .header(LOCATION, ROUTE_README_PATH)
+                .header(LOCATION, format!("{}{ROUTE_README_PATH}", *ROUTE_PREFIX))
                 .body(Body::empty())
         })
 }
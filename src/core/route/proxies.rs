@@ -1,16 +1,20 @@
 use crate::{
     app::model::{
-        CommonResponse, ProxiesDeleteRequest, ProxiesDeleteResponse, ProxyAddRequest,
-        ProxyInfoResponse, ProxyUpdateRequest, SetGeneralProxyRequest,
-        proxy_pool::{self, Proxies},
+        AppState, CommonResponse, ProxiesDeleteRequest, ProxiesDeleteResponse, ProxyAddRequest,
+        ProxyGroupsDeleteRequest, ProxyGroupsResponse, ProxyGroupsSetRequest, ProxyInfoResponse,
+        ProxyUpdateRequest, SetGeneralProxyRequest,
+        proxy_pool::{
+            self, Proxies,
+            group::{self, ProxyGroups},
+        },
     },
     common::{
         model::{ApiStatus, GenericError},
         utils::string_builder::StringBuilder,
     },
 };
-use ahash::HashMap;
-use axum::{Json, http::StatusCode};
+use ahash::{HashMap, HashSet};
+use axum::{Json, extract::State, http::StatusCode};
 use std::{borrow::Cow, sync::Arc};
 
 crate::define_typed_constants! {
@@ -24,6 +28,21 @@ crate::define_typed_constants! {
         MESSAGE_NO_NEW_PROXY_ADDED = "没有添加新代理",
         MESSAGE_ADDED_PREFIX = "已添加 ",
         MESSAGE_ADDED_SUFFIX = " 个新代理",
+        ERROR_INVALID_PROXY = "Invalid proxy",
+        MESSAGE_PROXY_NAME_CONFLICT = "代理名称与代理组名称冲突: ",
+        ERROR_PROXY_GROUPS_NOT_INITIALIZED = "Proxy groups not initialized",
+        ERROR_SAVE_TOKEN_PROXIES = "Failed to save token proxies: ",
+        MESSAGE_SAVE_TOKEN_PROXIES_FAILED = "无法保存令牌代理数据",
+        ERROR_SAVE_PROXY_GROUPS = "Failed to save proxy groups: ",
+        MESSAGE_SAVE_PROXY_GROUPS_FAILED = "无法保存代理组",
+        ERROR_INVALID_PROXY_GROUP = "Invalid proxy group",
+        MESSAGE_GROUP_NAME_CONFLICT = "代理组名称与代理名称冲突: ",
+        MESSAGE_GROUP_EMPTY = "代理组没有成员: ",
+        MESSAGE_GROUP_MEMBER_NOT_FOUND = "代理组成员不存在: ",
+        MESSAGE_GROUPS_SET_PREFIX = "已设置 ",
+        MESSAGE_GROUPS_SET_SUFFIX = " 个代理组",
+        MESSAGE_GROUPS_DELETED_PREFIX = "已删除 ",
+        MESSAGE_GROUPS_DELETED_SUFFIX = " 个代理组",
     }
 }
 
@@ -35,20 +54,32 @@ pub async fn handle_get_proxies() -> Json<ProxyInfoResponse> {
     let proxies_count = proxies.len();
     let general_proxy = proxy_pool::general_name().load_full();
 
+    // 按名称展开健康状态
+    let health = proxy_pool::health::snapshot();
+    let health = proxies
+        .iter()
+        .filter_map(|(name, proxy)| Some((name.clone(), health.get(proxy)?.clone())))
+        .collect();
+
     Json(ProxyInfoResponse {
         status: ApiStatus::Success,
         proxies: Some(proxies),
         proxies_count,
         general_proxy: Some(general_proxy),
+        health: Some(health),
         message: None,
     })
 }
 
 // 更新代理配置
 pub async fn handle_set_proxies(
+    State(state): State<Arc<AppState>>,
     Json(proxies): Json<ProxyUpdateRequest>,
 ) -> Result<Json<ProxyInfoResponse>, (StatusCode, Json<GenericError>)> {
+    check_group_conflicts(proxies.names())?;
+
     // 更新全局代理池并保存配置
+    let previous = proxy_pool::proxies().load_full();
     proxies.update_global();
     if let Err(e) = Proxies::update_and_save().await {
         return Err((
@@ -66,6 +97,7 @@ pub async fn handle_set_proxies(
             }),
         ));
     }
+    remove_references(&state, removed_names(&previous)).await?;
 
     // 获取通用代理信息（在更新应用状态前）
     let proxies_count = proxy_pool::proxies().load().len();
@@ -75,6 +107,7 @@ pub async fn handle_set_proxies(
         proxies: None,
         proxies_count,
         general_proxy: None,
+        health: None,
         message: Some(Cow::Borrowed(MESSAGE_PROXY_CONFIG_UPDATED)),
     }))
 }
@@ -83,6 +116,8 @@ pub async fn handle_set_proxies(
 pub async fn handle_add_proxy(
     Json(request): Json<ProxyAddRequest>,
 ) -> Result<Json<ProxyInfoResponse>, (StatusCode, Json<GenericError>)> {
+    check_group_conflicts(request.proxies.keys())?;
+
     // 获取当前的代理配置
     let current = proxy_pool::proxies().load_full();
     let proxies = request
@@ -100,6 +135,7 @@ pub async fn handle_add_proxy(
             proxies: Some(current),
             proxies_count,
             general_proxy: None,
+            health: None,
             message: Some(Cow::Borrowed(MESSAGE_NO_NEW_PROXY_ADDED)),
         }));
     }
@@ -142,6 +178,7 @@ pub async fn handle_add_proxy(
         proxies: None,
         proxies_count,
         general_proxy: None,
+        health: None,
         message: Some(Cow::Owned(
             StringBuilder::with_capacity(3)
                 .append(MESSAGE_ADDED_PREFIX)
@@ -154,6 +191,7 @@ pub async fn handle_add_proxy(
 
 // 删除指定的代理
 pub async fn handle_delete_proxies(
+    State(state): State<Arc<AppState>>,
     Json(request): Json<ProxiesDeleteRequest>,
 ) -> Result<Json<ProxiesDeleteResponse>, (StatusCode, Json<GenericError>)> {
     let names = request.names;
@@ -208,6 +246,7 @@ pub async fn handle_delete_proxies(
             }),
         ));
     }
+    remove_references(&state, removed_names(&current)).await?;
 
     // 根据expectation返回不同的结果
     let updated_proxies = if request.expectation.needs_updated_tokens() {
@@ -270,3 +309,227 @@ pub async fn handle_set_general_proxy(
         message: Cow::Borrowed(MESSAGE_GENERAL_PROXY_SET),
     }))
 }
+
+// 获取所有代理组
+pub async fn handle_get_proxy_groups()
+-> Result<Json<ProxyGroupsResponse>, (StatusCode, Json<GenericError>)> {
+    Ok(Json(ProxyGroupsResponse {
+        status: ApiStatus::Success,
+        groups: group::snapshot().map_err(groups_error)?,
+    }))
+}
+
+// 设置代理组
+pub async fn handle_set_proxy_groups(
+    Json(request): Json<ProxyGroupsSetRequest>,
+) -> Result<Json<CommonResponse>, (StatusCode, Json<GenericError>)> {
+    // 校验组名与成员，成员只能是已存在的代理
+    let proxies = proxy_pool::proxies().load();
+    for (name, group) in &request.groups {
+        let invalid = if proxies.contains_key(name) {
+            Some((MESSAGE_GROUP_NAME_CONFLICT, name))
+        } else if group.members.is_empty() {
+            Some((MESSAGE_GROUP_EMPTY, name))
+        } else {
+            group
+                .members
+                .iter()
+                .find(|member| !proxies.contains_key(*member))
+                .map(|member| (MESSAGE_GROUP_MEMBER_NOT_FOUND, member))
+        };
+
+        if let Some((message, name)) = invalid {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(GenericError {
+                    status: ApiStatus::Error,
+                    code: None,
+                    error: Some(Cow::Borrowed(ERROR_INVALID_PROXY_GROUP)),
+                    message: Some(Cow::Owned(
+                        StringBuilder::with_capacity(2)
+                            .append(message)
+                            .append(name.as_str())
+                            .build(),
+                    )),
+                }),
+            ));
+        }
+    }
+
+    let count = request.groups.len();
+    let mut groups = group::snapshot().map_err(groups_error)?;
+    groups.extend(request.groups);
+    group::store(groups).map_err(groups_error)?;
+
+    save_proxy_groups().await?;
+
+    Ok(Json(CommonResponse {
+        status: ApiStatus::Success,
+        message: Cow::Owned(
+            StringBuilder::with_capacity(3)
+                .append(MESSAGE_GROUPS_SET_PREFIX)
+                .append(count.to_string())
+                .append(MESSAGE_GROUPS_SET_SUFFIX)
+                .build(),
+        ),
+    }))
+}
+
+// 删除指定的代理组
+pub async fn handle_delete_proxy_groups(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ProxyGroupsDeleteRequest>,
+) -> Result<Json<CommonResponse>, (StatusCode, Json<GenericError>)> {
+    let mut groups = group::snapshot().map_err(groups_error)?;
+    let mut removed = HashSet::default();
+    groups.retain(|name, _| {
+        let keep = !request.names.contains(name);
+        if !keep {
+            removed.insert(name.clone());
+        }
+        keep
+    });
+    let deleted = removed.len();
+
+    if deleted > 0 {
+        group::store(groups).map_err(groups_error)?;
+        save_proxy_groups().await?;
+        remove_references(&state, removed).await?;
+    }
+
+    Ok(Json(CommonResponse {
+        status: ApiStatus::Success,
+        message: Cow::Owned(
+            StringBuilder::with_capacity(3)
+                .append(MESSAGE_GROUPS_DELETED_PREFIX)
+                .append(deleted.to_string())
+                .append(MESSAGE_GROUPS_DELETED_SUFFIX)
+                .build(),
+        ),
+    }))
+}
+
+async fn save_proxy_groups() -> Result<(), (StatusCode, Json<GenericError>)> {
+    ProxyGroups::save().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Owned(
+                    StringBuilder::with_capacity(2)
+                        .append(ERROR_SAVE_PROXY_GROUPS)
+                        .append(e.to_string())
+                        .build(),
+                )),
+                message: Some(Cow::Borrowed(MESSAGE_SAVE_PROXY_GROUPS_FAILED)),
+            }),
+        )
+    })
+}
+
+/// 代理名称不能与代理组名称相同，否则令牌引用该名称时无法区分
+fn check_group_conflicts<'a>(
+    names: impl IntoIterator<Item = &'a String>,
+) -> Result<(), (StatusCode, Json<GenericError>)> {
+    match names.into_iter().find(|name| group::contains(name)) {
+        Some(name) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Borrowed(ERROR_INVALID_PROXY)),
+                message: Some(Cow::Owned(
+                    StringBuilder::with_capacity(2)
+                        .append(MESSAGE_PROXY_NAME_CONFLICT)
+                        .append(name.as_str())
+                        .build(),
+                )),
+            }),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// 更新前存在而当前代理池中已不存在的代理名称
+fn removed_names(previous: &HashMap<String, proxy_pool::SingleProxy>) -> HashSet<String> {
+    let current = proxy_pool::proxies().load();
+    previous
+        .keys()
+        .filter(|name| !current.contains_key(*name))
+        .cloned()
+        .collect()
+}
+
+/// 清理代理组成员与令牌中对已删除代理或代理组的引用，成员被清空的代理组一并删除
+async fn remove_references(
+    state: &AppState,
+    mut removed: HashSet<String>,
+) -> Result<(), (StatusCode, Json<GenericError>)> {
+    if removed.is_empty() {
+        return Ok(());
+    }
+
+    let mut groups = group::snapshot().map_err(groups_error)?;
+    let mut groups_changed = false;
+    let mut emptied = Vec::new();
+    groups.retain(|name, group| {
+        let before = group.members.len();
+        group.members.retain(|member| !removed.contains(member));
+        groups_changed |= group.members.len() != before;
+        if group.members.is_empty() {
+            emptied.push(name.clone());
+            return false;
+        }
+        true
+    });
+    if groups_changed {
+        group::store(groups).map_err(groups_error)?;
+        save_proxy_groups().await?;
+        removed.extend(emptied);
+    }
+
+    let mut token_manager = state.token_manager_write().await;
+    let mut tokens_changed = false;
+    for info in token_manager.tokens_mut().iter_mut().flatten() {
+        if info
+            .bundle
+            .proxy
+            .as_ref()
+            .is_some_and(|name| removed.contains(name))
+        {
+            info.bundle.proxy = None;
+            tokens_changed = true;
+        }
+    }
+    if tokens_changed && let Err(e) = token_manager.save().await {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Owned(
+                    StringBuilder::with_capacity(2)
+                        .append(ERROR_SAVE_TOKEN_PROXIES)
+                        .append(e.to_string())
+                        .build(),
+                )),
+                message: Some(Cow::Borrowed(MESSAGE_SAVE_TOKEN_PROXIES_FAILED)),
+            }),
+        ));
+    }
+
+    Ok(())
+}
+
+fn groups_error(e: group::NotInitialized) -> (StatusCode, Json<GenericError>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(GenericError {
+            status: ApiStatus::Error,
+            code: None,
+            error: Some(Cow::Borrowed(ERROR_PROXY_GROUPS_NOT_INITIALIZED)),
+            message: Some(Cow::Owned(e.to_string())),
+        }),
+    )
+}
@@ -7,16 +7,17 @@ use ::prost::Message as _;
 use crate::{
     app::{
         constant::AUTHORIZATION_BEARER_PREFIX,
-        lazy::{AUTH_TOKEN, KEY_PREFIX},
+        lazy::KEY_PREFIX,
         model::{
             AppConfig, BuildKeyRequest, BuildKeyResponse, ExtToken, GetConfigVersionRequest,
-            GetConfigVersionResponse, Token, UsageCheckModelType,
+            GetConfigVersionResponse, Token, UsageCheckModelType, admin::Permission,
         },
     },
     common::utils::{to_base64, token_to_tokeninfo},
     core::{
         config::{KeyConfig, key_config},
         constant::ERR_NODATA,
+        middleware::check_admin,
     },
 };
 
@@ -49,7 +50,7 @@ const ERROR_UNAUTHORIZED: &str = "Unauthorized";
 //         .get(AUTHORIZATION)
 //         .and_then(|h| h.to_str().ok())
 //         .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX))
-//         .is_none_or(|h| !AppConfig::calibrate_token_eq(h) && h != *AUTH_TOKEN)
+//         .is_none_or(|h| !AppConfig::calibrate_token_eq(h) && h != AUTH_TOKEN.load().as_str())
 //     {
 //         return Json(BasicCalibrationResponse {
 //             status: ApiStatus::Error,
@@ -119,7 +120,9 @@ pub async fn handle_build_key(
             .and_then(|h| h.to_str().ok())
             .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX));
 
-        if auth_header.is_none_or(|h| !AppConfig::share_token_eq(h) && h != *AUTH_TOKEN) {
+        if auth_header.is_none_or(|h| {
+            !AppConfig::share_token_eq(h) && check_admin(h, Permission::BuildKey).is_err()
+        }) {
             return (
                 StatusCode::UNAUTHORIZED,
                 Json(BuildKeyResponse::Error(ERROR_UNAUTHORIZED)),
@@ -208,7 +211,9 @@ pub async fn handle_get_config_version(
             .and_then(|h| h.to_str().ok())
             .and_then(|h| h.strip_prefix(AUTHORIZATION_BEARER_PREFIX));
 
-        if auth_header.is_none_or(|h| !AppConfig::share_token_eq(h) && h != *AUTH_TOKEN) {
+        if auth_header.is_none_or(|h| {
+            !AppConfig::share_token_eq(h) && check_admin(h, Permission::BuildKey).is_err()
+        }) {
             return (
                 StatusCode::UNAUTHORIZED,
                 Json(GetConfigVersionResponse::Error(ERROR_UNAUTHORIZED)),
@@ -1,12 +1,9 @@
 use crate::{
-    app::{
-        constant::UNNAMED,
-        model::{
-            Alias, AppState, Checksum, CommonResponse, ExtToken, GcppHost, Hash, RawToken, Token,
-            TokenError, TokenInfo, TokenManager, TokenUpdateRequest, TokensAddRequest,
-            TokensAliasSetRequest, TokensDeleteRequest, TokensDeleteResponse, TokensInfoResponse,
-            TokensProxySetRequest, TokensStatusSetRequest, TokensTimezoneSetRequest,
-        },
+    app::model::{
+        Alias, AppState, CommonResponse, TokenError, TokenManager, TokenUpdateRequest,
+        TokensAddRequest, TokensAliasSetRequest, TokensDeleteRequest, TokensDeleteResponse,
+        TokensInfoResponse, TokensProxyAssignRequest, TokensProxySetRequest,
+        TokensStatusSetRequest, TokensTimezoneSetRequest, proxy_pool,
     },
     common::{
         model::{ApiStatus, GenericError},
@@ -14,8 +11,23 @@ use crate::{
     },
 };
 use ahash::HashSet;
-use axum::{Json, extract::State, http::StatusCode};
-use std::{borrow::Cow, str::FromStr as _, sync::Arc};
+use axum::{
+    Json,
+    extract::{Query, State, rejection::QueryRejection},
+    http::StatusCode,
+};
+use bytes::Bytes;
+use std::{borrow::Cow, sync::Arc};
+
+mod bulk;
+mod filter;
+mod import;
+mod query;
+mod transfer;
+pub use bulk::handle_bulk_tokens;
+use import::TokensAddResponse;
+use query::{TokensGetResponse, TokensQueryParams};
+pub use transfer::{handle_export_tokens, handle_import_tokens};
 
 crate::define_typed_constants! {
     &'static str => {
@@ -38,19 +50,47 @@ crate::define_typed_constants! {
         MESSAGE_SAVE_TOKEN_STATUS_FAILED = "无法保存令牌状态数据",
         MESSAGE_SAVE_TOKEN_PROXY_FAILED = "无法保存令牌代理数据",
         MESSAGE_SAVE_TOKEN_TIMEZONE_FAILED = "无法保存令牌时区数据",
+        ERROR_NO_PROXIES_PROVIDED = "No proxies provided",
+        MESSAGE_NO_PROXIES_PROVIDED = "未提供任何代理",
+        ERROR_PROXY_NOT_FOUND = "Proxy not found",
+        MESSAGE_PROXY_NOT_FOUND = "代理或代理组不存在: ",
+        ERROR_INVALID_QUERY = "Invalid query",
     }
 }
 
-pub async fn handle_get_tokens(State(state): State<Arc<AppState>>) -> Json<TokensInfoResponse> {
-    let tokens: Vec<_> = state.token_manager_read().await.list();
-    let tokens_count = tokens.len();
+#[inline]
+fn invalid_query(message: String) -> (StatusCode, Json<GenericError>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(GenericError {
+            status: ApiStatus::Error,
+            code: None,
+            error: Some(Cow::Borrowed(ERROR_INVALID_QUERY)),
+            message: Some(Cow::Owned(message)),
+        }),
+    )
+}
 
-    Json(TokensInfoResponse {
-        status: ApiStatus::Success,
-        tokens: Some(tokens),
-        tokens_count,
-        message: None,
-    })
+pub async fn handle_get_tokens(
+    State(state): State<Arc<AppState>>,
+    query: Result<Query<TokensQueryParams>, QueryRejection>,
+    body: Bytes,
+) -> Result<Json<TokensGetResponse>, (StatusCode, Json<GenericError>)> {
+    // 参数可通过查询字符串或 JSON 请求体传入，两者都省略时返回全部令牌
+    let params: TokensQueryParams = if body.is_empty() {
+        query
+            .map(|Query(params)| params)
+            .map_err(|e| invalid_query(e.body_text()))?
+    } else {
+        serde_json::from_slice(&body).map_err(|e| invalid_query(e.to_string()))?
+    };
+
+    let token_manager = state.token_manager_read().await;
+    let token_stats = state.token_stats.lock();
+    params
+        .select(&token_manager, &token_stats)
+        .map(Json)
+        .map_err(invalid_query)
 }
 
 pub async fn handle_set_tokens(
@@ -82,103 +122,60 @@ pub async fn handle_set_tokens(
 pub async fn handle_add_tokens(
     State(state): State<Arc<AppState>>,
     Json(request): Json<TokensAddRequest>,
-) -> Result<Json<TokensInfoResponse>, (StatusCode, Json<GenericError>)> {
+) -> Result<Json<TokensAddResponse>, (StatusCode, Json<GenericError>)> {
+    let dry_run = request.dry_run;
+
     // 获取token manager的写锁
     let mut token_manager = state.token_manager_write().await;
 
-    // 创建现有token的集合
-    let existing_tokens: HashSet<_> = token_manager
-        .tokens()
-        .iter()
-        .flatten()
-        .map(|info| info.bundle.primary_token.as_str())
-        .collect();
-
-    // 处理新的tokens
-    let mut new_tokens = Vec::with_capacity(request.tokens.len());
-    for token_info in request.tokens {
-        if !existing_tokens.contains(token_info.token.as_str())
-            && let Ok(raw) = <RawToken as ::core::str::FromStr>::from_str(&token_info.token)
-        {
-            new_tokens.push((
-                TokenInfo {
-                    bundle: ExtToken {
-                        primary_token: Token::new(raw, Some(token_info.token)),
-                        secondary_token: None,
-                        checksum: token_info
-                            .checksum
-                            .as_deref()
-                            .map(Checksum::repair)
-                            .unwrap_or_default(),
-                        client_key: token_info
-                            .client_key
-                            .and_then(|s| Hash::from_str(&s).ok())
-                            .unwrap_or_else(Hash::random),
-                        session_id: token_info
-                            .session_id
-                            .and_then(|s| uuid::Uuid::parse_str(&s).ok())
-                            .unwrap_or_else(uuid::Uuid::new_v4),
-                        config_version: token_info
-                            .config_version
-                            .and_then(|s| uuid::Uuid::parse_str(&s).ok()),
-                        proxy: token_info.proxy,
-                        timezone: token_info
-                            .timezone
-                            .and_then(|s| chrono_tz::Tz::from_str(&s).ok()),
-                        gcpp_host: token_info.gcpp_host.and_then(|s| GcppHost::from_str(&s)),
-                        user: None,
-                    },
-                    status: request.status,
-                    stripe: None,
-                    sessions: vec![],
-                },
-                token_info
-                    .alias
-                    .filter(|s| s.split_whitespace().next().is_some())
-                    .map(Cow::Owned)
-                    .unwrap_or(Cow::Borrowed(UNNAMED)),
-            ));
-        }
-    }
+    // 逐行校验，无效的令牌不会被导入
+    let (new_tokens, mut diagnostics) = import::diagnose(&token_manager, request).await;
 
-    // 如果有新tokens才进行后续操作
-    if !new_tokens.is_empty() {
+    let mut accepted = 0;
+    if !dry_run && !new_tokens.is_empty() {
         // 添加新tokens
-        for (token_info, alias) in new_tokens {
-            let _ = token_manager.add(token_info, alias);
+        for token in new_tokens {
+            match token_manager.add(token.info, token.alias) {
+                Ok(_) => accepted += 1,
+                Err(_) => diagnostics[token.index].reject(),
+            }
         }
-        let tokens_count = token_manager.tokens().len();
 
         // 保存到文件
-        token_manager.save().await.map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(GenericError {
-                    status: ApiStatus::Error,
-                    code: None,
-                    error: Some(Cow::Borrowed(ERROR_SAVE_TOKEN_DATA)),
-                    message: Some(Cow::Borrowed(MESSAGE_SAVE_TOKEN_DATA_FAILED)),
-                }),
-            )
-        })?;
-
-        Ok(Json(TokensInfoResponse {
-            status: ApiStatus::Success,
-            tokens: None,
-            tokens_count,
-            message: Some(Cow::Borrowed("New tokens have been added and reloaded")),
-        }))
+        if accepted > 0 {
+            token_manager.save().await.map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(GenericError {
+                        status: ApiStatus::Error,
+                        code: None,
+                        error: Some(Cow::Borrowed(ERROR_SAVE_TOKEN_DATA)),
+                        message: Some(Cow::Borrowed(MESSAGE_SAVE_TOKEN_DATA_FAILED)),
+                    }),
+                )
+            })?;
+        }
     } else {
-        // 如果没有新tokens，返回当前状态
-        let tokens_count = token_manager.tokens().len();
-
-        Ok(Json(TokensInfoResponse {
-            status: ApiStatus::Success,
-            tokens: None,
-            tokens_count,
-            message: Some(Cow::Borrowed("No new tokens were added")),
-        }))
+        accepted = new_tokens.len();
     }
+
+    let message = if dry_run {
+        "Tokens have been validated, nothing was added"
+    } else if accepted > 0 {
+        "New tokens have been added and reloaded"
+    } else {
+        "No new tokens were added"
+    };
+
+    Ok(Json(TokensAddResponse {
+        status: ApiStatus::Success,
+        dry_run,
+        tokens_count: token_manager.tokens().len(),
+        accepted,
+        rejected: diagnostics.len() - accepted,
+        message: Cow::Borrowed(message),
+        diagnostics,
+    }))
 }
 
 pub async fn handle_delete_tokens(
@@ -717,6 +714,113 @@ pub async fn handle_set_tokens_proxy(
     }))
 }
 
+pub async fn handle_assign_tokens_proxy(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TokensProxyAssignRequest>,
+) -> Result<Json<CommonResponse>, (StatusCode, Json<GenericError>)> {
+    // 验证请求
+    if request.aliases.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Borrowed(ERROR_NO_TOKENS_PROVIDED)),
+                message: Some(Cow::Borrowed(MESSAGE_NO_TOKENS_PROVIDED)),
+            }),
+        ));
+    }
+    if request.proxies.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Borrowed(ERROR_NO_PROXIES_PROVIDED)),
+                message: Some(Cow::Borrowed(MESSAGE_NO_PROXIES_PROVIDED)),
+            }),
+        ));
+    }
+
+    // 代理名称需存在于代理或代理组中
+    {
+        let proxies = proxy_pool::proxies().load();
+        if let Some(name) = request
+            .proxies
+            .iter()
+            .find(|name| !proxies.contains_key(*name) && !proxy_pool::group::contains(name))
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(GenericError {
+                    status: ApiStatus::Error,
+                    code: None,
+                    error: Some(Cow::Borrowed(ERROR_PROXY_NOT_FOUND)),
+                    message: Some(Cow::Owned(
+                        StringBuilder::with_capacity(2)
+                            .append(MESSAGE_PROXY_NOT_FOUND)
+                            .append(name)
+                            .build(),
+                    )),
+                }),
+            ));
+        }
+    }
+
+    // 获取当前的 token_manager
+    let mut token_manager = state.token_manager_write().await;
+
+    // 按顺序轮流分配代理
+    let mut updated_count: u32 = 0;
+    let mut failed_count: u32 = 0;
+    let mut proxies = request.proxies.iter().cycle();
+
+    for alias in request.aliases {
+        if let Some(info) = token_manager
+            .alias_map()
+            .get(alias.as_str())
+            .copied()
+            .and_then(|id| {
+                token_manager
+                    .tokens_mut()
+                    .get_mut(id)
+                    .and_then(|t| t.as_mut())
+            })
+        {
+            info.bundle.proxy = proxies.next().cloned();
+            updated_count += 1;
+        } else {
+            failed_count += 1;
+        }
+    }
+
+    // 保存更改
+    if updated_count > 0 && token_manager.save().await.is_err() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Borrowed(ERROR_SAVE_TOKEN_PROXIES)),
+                message: Some(Cow::Borrowed(MESSAGE_SAVE_TOKEN_PROXY_FAILED)),
+            }),
+        ));
+    }
+
+    Ok(Json(CommonResponse {
+        status: ApiStatus::Success,
+        message: Cow::Owned(
+            StringBuilder::with_capacity(5)
+                .append(SET_SUCCESS)
+                .append(updated_count.to_string())
+                .append("个令牌代理, ")
+                .append(failed_count.to_string())
+                .append(SET_FAILURE_COUNT)
+                .build(),
+        ),
+    }))
+}
+
 pub async fn handle_set_tokens_timezone(
     State(state): State<Arc<AppState>>,
     Json(request): Json<TokensTimezoneSetRequest>,
@@ -0,0 +1,238 @@
+use super::{
+    ERROR_PROXY_NOT_FOUND, ERROR_SAVE_TOKEN_DATA, MESSAGE_PROXY_NOT_FOUND,
+    MESSAGE_SAVE_TOKEN_DATA_FAILED, filter::TokensFilter, invalid_query,
+};
+use crate::{
+    app::model::{Alias, AppState, TokenInfo, TokenStatus, proxy_pool},
+    common::model::{ApiStatus, GenericError},
+};
+use axum::{Json, extract::State, http::StatusCode};
+use std::{borrow::Cow, sync::Arc};
+
+const ERROR_EMPTY_FILTER: &str = "Empty filter";
+const MESSAGE_EMPTY_FILTER: &str = "过滤条件为空，如需作用于全部令牌请设置 all 为 true";
+const ERROR_TOKEN_NOT_FOUND: &str = "令牌不存在";
+const ERROR_PROFILE_UNAVAILABLE: &str = "获取令牌信息失败";
+
+/// 批量操作
+#[derive(::serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkAction {
+    Enable,
+    Disable,
+    Delete,
+    SetProxy {
+        #[serde(default)]
+        proxy: Option<String>,
+    },
+    SetTimezone {
+        #[serde(default)]
+        timezone: Option<chrono_tz::Tz>,
+    },
+    RefreshProfile,
+}
+
+impl BulkAction {
+    /// 操作是否会修改令牌，无法预知结果的操作视为会修改
+    fn changes(&self, info: &TokenInfo) -> bool {
+        match self {
+            Self::Enable => info.status != TokenStatus::Enabled,
+            Self::Disable => info.status != TokenStatus::Disabled,
+            Self::SetProxy { proxy } => info.bundle.proxy != *proxy,
+            Self::SetTimezone { timezone } => info.bundle.timezone != *timezone,
+            Self::Delete | Self::RefreshProfile => true,
+        }
+    }
+
+    /// 修改令牌，删除由调用方处理
+    async fn apply(&self, info: &mut TokenInfo) -> Result<(), &'static str> {
+        match self {
+            Self::Enable => info.status = TokenStatus::Enabled,
+            Self::Disable => info.status = TokenStatus::Disabled,
+            Self::SetProxy { proxy } => info.bundle.proxy = proxy.clone(),
+            Self::SetTimezone { timezone } => info.bundle.timezone = *timezone,
+            Self::RefreshProfile => {
+                let (user, stripe, sessions) = crate::common::utils::get_token_profile(
+                    info.bundle.get_client(),
+                    &info.bundle.primary_token,
+                    info.bundle.secondary_token.as_ref(),
+                    true,
+                    true,
+                    true,
+                )
+                .await;
+                // 未获取到任何信息时保持原样
+                if user.is_none() && stripe.is_none() {
+                    return Err(ERROR_PROFILE_UNAVAILABLE);
+                }
+                info.bundle.user = user;
+                info.stripe = stripe;
+                if let Some(sessions) = sessions {
+                    info.sessions = sessions;
+                }
+            }
+            Self::Delete => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(::serde::Deserialize)]
+pub struct TokensBulkRequest {
+    #[serde(default)]
+    pub filter: TokensFilter,
+    pub action: BulkAction,
+    /// 仅返回匹配结果，不执行操作
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 允许空过滤条件作用于全部令牌
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(::serde::Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum BulkOutcome {
+    /// 预演模式下将被修改
+    Matched,
+    Updated,
+    Deleted,
+    Unchanged,
+    Failed,
+}
+
+#[derive(::serde::Serialize)]
+struct BulkResult {
+    alias: String,
+    outcome: BulkOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'static str>,
+}
+
+#[derive(::serde::Serialize)]
+pub struct TokensBulkResponse {
+    status: ApiStatus,
+    dry_run: bool,
+    /// 匹配的令牌数
+    matched: usize,
+    /// 实际修改或删除的令牌数
+    changed: usize,
+    failed: usize,
+    results: Vec<BulkResult>,
+}
+
+pub async fn handle_bulk_tokens(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TokensBulkRequest>,
+) -> Result<Json<TokensBulkResponse>, (StatusCode, Json<GenericError>)> {
+    if request.filter.is_empty() && !request.all {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Borrowed(ERROR_EMPTY_FILTER)),
+                message: Some(Cow::Borrowed(MESSAGE_EMPTY_FILTER)),
+            }),
+        ));
+    }
+    if let BulkAction::SetProxy { proxy: Some(name) } = &request.action
+        && !proxy_pool::proxies().load().contains_key(name)
+        && !proxy_pool::group::contains(name)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Borrowed(ERROR_PROXY_NOT_FOUND)),
+                message: Some(Cow::Owned(format!("{MESSAGE_PROXY_NOT_FOUND}{name}"))),
+            }),
+        ));
+    }
+
+    let mut token_manager = state.token_manager_write().await;
+
+    // 先收集匹配的令牌，统计锁不跨越后续的网络请求
+    let targets: Vec<(usize, String, bool)> = {
+        let token_stats = state.token_stats.lock();
+        request
+            .filter
+            .candidates(&token_manager, &token_stats, false)
+            .map_err(invalid_query)?
+            .into_iter()
+            .map(|c| (c.id, c.alias.to_string(), request.action.changes(c.info)))
+            .collect()
+    };
+
+    let mut results = Vec::with_capacity(targets.len());
+    for (id, alias, changes) in targets {
+        let (outcome, error) = if !changes {
+            (BulkOutcome::Unchanged, None)
+        } else if request.dry_run {
+            (BulkOutcome::Matched, None)
+        } else if let BulkAction::Delete = request.action {
+            let _ = token_manager.remove(id);
+            (BulkOutcome::Deleted, None)
+        } else {
+            match token_manager
+                .tokens_mut()
+                .get_mut(id)
+                .and_then(|t| t.as_mut())
+            {
+                Some(info) => match request.action.apply(info).await {
+                    Ok(()) => {
+                        // 与 /tokens/profile/update 一致，未命名的令牌改用邮箱作为别名
+                        if let BulkAction::RefreshProfile = request.action
+                            && let Some(email) = info.bundle.user.as_ref().map(|u| u.email.clone())
+                            && token_manager.id_to_alias()[id]
+                                .as_ref()
+                                .is_some_and(Alias::is_unnamed)
+                        {
+                            let _ = token_manager.set_alias(id, email);
+                        }
+                        (BulkOutcome::Updated, None)
+                    }
+                    Err(e) => (BulkOutcome::Failed, Some(e)),
+                },
+                None => (BulkOutcome::Failed, Some(ERROR_TOKEN_NOT_FOUND)),
+            }
+        };
+        results.push(BulkResult {
+            alias,
+            outcome,
+            error,
+        });
+    }
+
+    let changed = results
+        .iter()
+        .filter(|r| matches!(r.outcome, BulkOutcome::Updated | BulkOutcome::Deleted))
+        .count();
+    let failed = results
+        .iter()
+        .filter(|r| r.outcome == BulkOutcome::Failed)
+        .count();
+
+    // 保存更改
+    if changed > 0 && token_manager.save().await.is_err() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(GenericError {
+                status: ApiStatus::Error,
+                code: None,
+                error: Some(Cow::Borrowed(ERROR_SAVE_TOKEN_DATA)),
+                message: Some(Cow::Borrowed(MESSAGE_SAVE_TOKEN_DATA_FAILED)),
+            }),
+        ));
+    }
+
+    Ok(Json(TokensBulkResponse {
+        status: ApiStatus::Success,
+        dry_run: request.dry_run,
+        matched: results.len(),
+        changed,
+        failed,
+        results,
+    }))
+}
@@ -0,0 +1,178 @@
+use crate::{
+    app::model::{
+        Alias, TokenInfo, TokenManager, TokenStatsStore, TokenStatsView, TokenStatus, proxy_pool,
+    },
+    common::model::userinfo::MembershipType,
+};
+use serde::{Deserialize, Deserializer, de};
+use std::{borrow::Borrow, fmt::Display, str::FromStr};
+
+/// 数值参数，同时接受 JSON 数字与查询字符串中的文本
+fn number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value<T> {
+        Typed(T),
+        Text(String),
+    }
+
+    match Option::<Value<T>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Value::Typed(value)) => Ok(Some(value)),
+        Some(Value::Text(text)) => text.trim().parse().map(Some).map_err(de::Error::custom),
+    }
+}
+
+/// 令牌过滤条件，所有条件同时满足时匹配
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct TokensFilter {
+    // 令牌信息
+    pub search: Option<String>, // 按别名、邮箱、会员类型或代理模糊搜索
+    pub status: Option<TokenStatus>, // 按状态过滤
+    pub membership_type: Option<MembershipType>, // 按会员类型过滤
+    pub proxy: Option<String>,  // 按代理名精确匹配
+    pub proxy_group: Option<String>, // 使用该代理组或其成员代理
+    #[serde(deserialize_with = "number")]
+    pub expires_within: Option<u64>, // 指定秒数内过期，包括已过期的令牌
+
+    // 使用统计
+    #[serde(deserialize_with = "number")]
+    pub min_requests: Option<u64>, // 累计请求数下限
+    #[serde(deserialize_with = "number")]
+    pub min_errors: Option<u64>, // 累计错误数下限
+    #[serde(deserialize_with = "number")]
+    pub min_error_rate: Option<f64>, // 累计错误率下限
+    #[serde(deserialize_with = "number")]
+    pub unused_for: Option<u64>, // 超过指定秒数未使用，包括从未使用的令牌
+    #[serde(deserialize_with = "number")]
+    pub used_within: Option<u64>, // 指定秒数内使用过
+}
+
+/// 过滤后的令牌引用
+pub struct Candidate<'a> {
+    pub id: usize,
+    pub alias: &'a Alias,
+    pub info: &'a TokenInfo,
+    pub stats: Option<TokenStatsView>,
+}
+
+impl TokensFilter {
+    /// 是否未设置任何条件
+    pub fn is_empty(&self) -> bool {
+        self.search.as_deref().is_none_or(|s| s.trim().is_empty())
+            && self.status.is_none()
+            && self.membership_type.is_none()
+            && self.proxy.is_none()
+            && self.proxy_group.is_none()
+            && self.expires_within.is_none()
+            && !self.needs_stats()
+    }
+
+    /// 过滤是否依赖使用统计
+    #[inline]
+    pub fn needs_stats(&self) -> bool {
+        self.min_requests.is_some()
+            || self.min_errors.is_some()
+            || self.min_error_rate.is_some()
+            || self.unused_for.is_some()
+            || self.used_within.is_some()
+    }
+
+    fn matches_info(
+        &self,
+        alias: &Alias,
+        info: &TokenInfo,
+        search: Option<&str>,
+        group: Option<(&str, &[String])>,
+        deadline: Option<i64>,
+    ) -> bool {
+        let membership_type = info.stripe.map(|s| s.membership_type);
+        let proxy = info.bundle.proxy.as_deref();
+        self.status.is_none_or(|status| info.status == status)
+            && self
+                .membership_type
+                .is_none_or(|m| membership_type == Some(m))
+            && self.proxy.as_deref().is_none_or(|p| proxy == Some(p))
+            && group.is_none_or(|(name, members)| {
+                proxy.is_some_and(|p| p == name || members.iter().any(|m| m == p))
+            })
+            && deadline.is_none_or(|t| info.bundle.primary_token.raw().duration.end <= t)
+            && search.is_none_or(|search| {
+                let contains = |s: &str| s.to_lowercase().contains(search);
+                contains(alias.borrow())
+                    || info
+                        .bundle
+                        .user
+                        .as_ref()
+                        .is_some_and(|user| contains(&user.email))
+                    || membership_type.is_some_and(|m| m.as_str().contains(search))
+                    || proxy.is_some_and(contains)
+            })
+    }
+
+    fn matches_stats(&self, stats: &TokenStatsView, now: i64) -> bool {
+        let last_used = stats.last_used.map(|t| t.timestamp_millis());
+        let since = |secs: u64| now.saturating_sub(secs.saturating_mul(1000) as i64);
+        self.min_requests.is_none_or(|n| stats.requests >= n)
+            && self.min_errors.is_none_or(|n| stats.errors >= n)
+            && self.min_error_rate.is_none_or(|r| stats.error_rate() >= r)
+            && self
+                .unused_for
+                .is_none_or(|secs| last_used.is_none_or(|t| t < since(secs)))
+            && self
+                .used_within
+                .is_none_or(|secs| last_used.is_some_and(|t| t >= since(secs)))
+    }
+
+    /// 按ID顺序返回匹配的令牌，`with_stats` 为真或过滤依赖统计时附带统计
+    pub fn candidates<'a>(
+        &self,
+        token_manager: &'a TokenManager,
+        token_stats: &TokenStatsStore,
+        with_stats: bool,
+    ) -> Result<Vec<Candidate<'a>>, String> {
+        let search = self
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_lowercase);
+        let members = match &self.proxy_group {
+            Some(name) => Some(
+                proxy_pool::group::snapshot()
+                    .map_err(|e| e.to_string())?
+                    .remove(name)
+                    .map(|group| group.members)
+                    .ok_or_else(|| format!("代理组不存在: {name}"))?,
+            ),
+            None => None,
+        };
+        let group = self.proxy_group.as_deref().zip(members.as_deref());
+        let now = TokenStatsStore::now();
+        // 令牌过期时间以秒为单位
+        let deadline = self
+            .expires_within
+            .map(|secs| (now / 1000).saturating_add(secs.min(i64::MAX as u64) as i64));
+        let with_stats = with_stats || self.needs_stats();
+
+        Ok(token_manager
+            .iter()
+            .filter(|(_, alias, info)| {
+                self.matches_info(alias, info, search.as_deref(), group, deadline)
+            })
+            .map(|(id, alias, info)| Candidate {
+                id,
+                alias,
+                info,
+                stats: with_stats.then(|| token_stats.view(&info.bundle.primary_token.key(), now)),
+            })
+            .filter(|c| c.stats.as_ref().is_none_or(|s| self.matches_stats(s, now)))
+            .collect())
+    }
+}
@@ -0,0 +1,362 @@
+use crate::{
+    app::{
+        constant::{ISSUER, UNNAMED},
+        model::{
+            Checksum, DateTime, ExtToken, GcppHost, Hash, RawToken, Subject, SubjectError, Token,
+            TokenInfo, TokenKey, TokenManager, TokensAddRequest, TokensAddRequestTokenInfo, UserId,
+            providers, proxy_pool,
+        },
+    },
+    common::model::{ApiStatus, userinfo::MembershipType},
+};
+use ahash::{HashMap, HashSet};
+use std::{borrow::Cow, str::FromStr as _};
+
+crate::define_typed_constants! {
+    &'static str => {
+        // 阻止导入的问题
+        INVALID_TOKEN = "invalid_token",
+        UNSUPPORTED_PROVIDER = "unsupported_provider",
+        EXPIRED = "expired",
+        DUPLICATE_EXISTING = "duplicate_existing",
+        DUPLICATE_IN_REQUEST = "duplicate_in_request",
+        ALIAS_EXISTS = "alias_exists",
+        // 不影响导入的问题
+        SAME_USER_EXISTING = "same_user_existing",
+        SAME_USER_IN_REQUEST = "same_user_in_request",
+        INVALID_CHECKSUM = "invalid_checksum",
+        INVALID_CLIENT_KEY = "invalid_client_key",
+        INVALID_SESSION_ID = "invalid_session_id",
+        INVALID_CONFIG_VERSION = "invalid_config_version",
+        INVALID_TIMEZONE = "invalid_timezone",
+        INVALID_GCPP_HOST = "invalid_gcpp_host",
+        UNKNOWN_PROXY = "unknown_proxy",
+        PROFILE_UNAVAILABLE = "profile_unavailable",
+    }
+}
+
+#[derive(::serde::Serialize)]
+pub struct Issue {
+    code: &'static str,
+    message: Cow<'static, str>,
+}
+
+#[inline]
+fn issue(code: &'static str, message: impl Into<Cow<'static, str>>) -> Issue {
+    Issue {
+        code,
+        message: message.into(),
+    }
+}
+
+/// 令牌解码结果
+#[derive(::serde::Serialize)]
+pub struct TokenDetails {
+    issuer: &'static str,
+    subject: Subject,
+    is_session: bool,
+    issued_at: Option<DateTime>,
+    expires_at: Option<DateTime>,
+    expired: bool,
+}
+
+/// 探测到的用户信息
+#[derive(::serde::Serialize)]
+pub struct ProfileProbe {
+    email: Option<String>,
+    membership_type: Option<MembershipType>,
+}
+
+/// 单行令牌的诊断信息
+#[derive(::serde::Serialize)]
+pub struct TokenDiagnostic {
+    /// 在请求中的序号，从0开始
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alias: Option<String>,
+    /// 是否可以导入
+    accepted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<TokenDetails>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<ProfileProbe>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<Issue>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<Issue>,
+}
+
+#[derive(::serde::Serialize)]
+pub struct TokensAddResponse {
+    pub status: ApiStatus,
+    pub dry_run: bool,
+    pub tokens_count: usize,
+    /// 已导入（预演时为可导入）的令牌数
+    pub accepted: usize,
+    pub rejected: usize,
+    pub message: Cow<'static, str>,
+    pub diagnostics: Vec<TokenDiagnostic>,
+}
+
+impl TokenDiagnostic {
+    /// 导入时才发现的冲突
+    #[inline]
+    pub fn reject(&mut self) {
+        self.accepted = false;
+        self.errors.push(issue(ALIAS_EXISTS, "别名已存在"));
+    }
+}
+
+#[inline]
+fn datetime_of(secs: i64) -> Option<DateTime> {
+    chrono::DateTime::from_timestamp(secs, 0).map(|dt| DateTime::from_naive(&dt.naive_utc()))
+}
+
+/// 通过校验的令牌
+pub struct Accepted {
+    pub index: usize,
+    pub info: TokenInfo,
+    pub alias: Cow<'static, str>,
+}
+
+/// 校验导入请求，返回可导入的令牌与每行的诊断
+pub async fn diagnose(
+    token_manager: &TokenManager,
+    request: TokensAddRequest,
+) -> (Vec<Accepted>, Vec<TokenDiagnostic>) {
+    let now = DateTime::utc_now().timestamp();
+
+    // 池中已有的用户
+    let existing_users: HashMap<UserId, String> = token_manager
+        .iter()
+        .map(|(_, alias, info)| {
+            (
+                info.bundle.primary_token.raw().subject.id,
+                alias.to_string(),
+            )
+        })
+        .collect();
+    let mut seen_keys: HashMap<TokenKey, usize> = HashMap::default();
+    let mut seen_users: HashMap<UserId, usize> = HashMap::default();
+    let mut seen_aliases: HashSet<String> = HashSet::default();
+
+    let mut accepted = Vec::new();
+    let mut diagnostics = Vec::with_capacity(request.tokens.len());
+
+    for (index, token_info) in request.tokens.into_iter().enumerate() {
+        let TokensAddRequestTokenInfo {
+            alias,
+            token,
+            checksum,
+            client_key,
+            session_id,
+            config_version,
+            proxy,
+            timezone,
+            gcpp_host,
+        } = token_info;
+        let alias = alias.filter(|s| s.split_whitespace().next().is_some());
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        let raw = match RawToken::from_str(&token) {
+            Ok(raw) => Some(raw),
+            Err(e) => {
+                let message = e.to_string();
+                // 提供者错误在 JSON 解析中被转换为文本
+                if message.contains(&SubjectError::UnsupportedProvider.to_string()) {
+                    errors.push(issue(
+                        UNSUPPORTED_PROVIDER,
+                        format!(
+                            "提供者不在 ALLOWED_PROVIDERS 中: {}",
+                            providers().join(", ")
+                        ),
+                    ));
+                } else {
+                    errors.push(issue(INVALID_TOKEN, message));
+                }
+                None
+            }
+        };
+
+        let details = raw.map(|raw| {
+            let key = raw.key();
+            let user_id = raw.subject.id;
+            if raw.duration.end <= now {
+                errors.push(issue(EXPIRED, "令牌已过期"));
+            }
+            if let Some(&id) = token_manager.id_map().get(&key) {
+                let existing = token_manager.id_to_alias()[id]
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                errors.push(issue(
+                    DUPLICATE_EXISTING,
+                    format!("与已有令牌 {existing} 相同"),
+                ));
+            } else if let Some(&other) = seen_keys.get(&key) {
+                errors.push(issue(
+                    DUPLICATE_IN_REQUEST,
+                    format!("与序号 {other} 的令牌相同"),
+                ));
+            } else if let Some(existing) = existing_users.get(&user_id) {
+                warnings.push(issue(
+                    SAME_USER_EXISTING,
+                    format!("与已有令牌 {existing} 属于同一用户"),
+                ));
+            } else if let Some(&other) = seen_users.get(&user_id) {
+                warnings.push(issue(
+                    SAME_USER_IN_REQUEST,
+                    format!("与序号 {other} 的令牌属于同一用户"),
+                ));
+            }
+            seen_keys.entry(key).or_insert(index);
+            seen_users.entry(user_id).or_insert(index);
+            TokenDetails {
+                issuer: ISSUER,
+                subject: raw.subject,
+                is_session: raw.is_session,
+                issued_at: datetime_of(raw.duration.start),
+                expires_at: datetime_of(raw.duration.end),
+                expired: raw.duration.end <= now,
+            }
+        });
+
+        if let Some(alias) = &alias
+            && (token_manager.alias_map().contains_key(alias.as_str())
+                || !seen_aliases.insert(alias.clone()))
+        {
+            errors.push(issue(ALIAS_EXISTS, format!("别名 {alias} 已存在")));
+        }
+
+        // 以下字段无效时与导入时一样使用默认值
+        let checksum = checksum.as_deref().map(|s| {
+            if Checksum::from_str(s).is_err() {
+                warnings.push(issue(
+                    INVALID_CHECKSUM,
+                    "checksum 格式无效，将部分或全部随机生成",
+                ));
+            }
+            Checksum::repair(s)
+        });
+        let client_key = client_key.and_then(|s| {
+            let hash = Hash::from_str(&s).ok();
+            if hash.is_none() {
+                warnings.push(issue(INVALID_CLIENT_KEY, "client_key 格式无效，将随机生成"));
+            }
+            hash
+        });
+        let session_id = session_id.and_then(|s| {
+            let id = uuid::Uuid::parse_str(&s).ok();
+            if id.is_none() {
+                warnings.push(issue(INVALID_SESSION_ID, "session_id 格式无效，将随机生成"));
+            }
+            id
+        });
+        let config_version = config_version.and_then(|s| {
+            let version = uuid::Uuid::parse_str(&s).ok();
+            if version.is_none() {
+                warnings.push(issue(
+                    INVALID_CONFIG_VERSION,
+                    "config_version 格式无效，已忽略",
+                ));
+            }
+            version
+        });
+        let timezone = timezone.and_then(|s| {
+            let tz = chrono_tz::Tz::from_str(&s).ok();
+            if tz.is_none() {
+                warnings.push(issue(INVALID_TIMEZONE, format!("未知时区 {s}，已忽略")));
+            }
+            tz
+        });
+        let gcpp_host = gcpp_host.and_then(|s| {
+            let host = GcppHost::from_str(&s);
+            if host.is_none() {
+                warnings.push(issue(
+                    INVALID_GCPP_HOST,
+                    format!("未知 gcpp_host {s}，已忽略"),
+                ));
+            }
+            host
+        });
+        if let Some(name) = &proxy
+            && !proxy_pool::proxies().load().contains_key(name)
+            && !proxy_pool::group::contains(name)
+        {
+            warnings.push(issue(
+                UNKNOWN_PROXY,
+                format!("代理或代理组不存在: {name}，将使用通用代理"),
+            ));
+        }
+
+        let mut profile = None;
+        let ok = errors.is_empty();
+        if let Some(raw) = raw
+            && ok
+        {
+            let mut info = TokenInfo {
+                bundle: ExtToken {
+                    primary_token: Token::new(raw, Some(token)),
+                    secondary_token: None,
+                    checksum: checksum.unwrap_or_default(),
+                    client_key: client_key.unwrap_or_else(Hash::random),
+                    session_id: session_id.unwrap_or_else(uuid::Uuid::new_v4),
+                    config_version,
+                    proxy,
+                    timezone,
+                    gcpp_host,
+                    user: None,
+                },
+                status: request.status,
+                stripe: None,
+                sessions: vec![],
+                stats: None,
+            };
+            if request.probe {
+                let (user, stripe, sessions) = crate::common::utils::get_token_profile(
+                    info.bundle.get_client(),
+                    &info.bundle.primary_token,
+                    None,
+                    true,
+                    true,
+                    true,
+                )
+                .await;
+                if user.is_none() && stripe.is_none() {
+                    warnings.push(issue(PROFILE_UNAVAILABLE, "无法获取用户信息"));
+                }
+                profile = Some(ProfileProbe {
+                    email: user.as_ref().map(|u| u.email.clone()),
+                    membership_type: stripe.map(|s| s.membership_type),
+                });
+                // 探测结果随令牌一同保存
+                info.bundle.user = user;
+                info.stripe = stripe;
+                if let Some(sessions) = sessions {
+                    info.sessions = sessions;
+                }
+            }
+            accepted.push(Accepted {
+                index,
+                info,
+                alias: alias
+                    .clone()
+                    .map(Cow::Owned)
+                    .unwrap_or(Cow::Borrowed(UNNAMED)),
+            });
+        }
+
+        diagnostics.push(TokenDiagnostic {
+            index,
+            alias,
+            accepted: ok,
+            token: details,
+            profile,
+            errors,
+            warnings,
+        });
+    }
+
+    (accepted, diagnostics)
+}
@@ -0,0 +1,169 @@
+use super::filter::{Candidate, TokensFilter};
+use crate::{
+    app::model::{Alias, TokenInfo, TokenManager, TokenStatsSort, TokenStatsStore, TokenStatus},
+    common::model::{ApiStatus, userinfo::MembershipType},
+};
+use serde_json::{Map, Value};
+use std::{borrow::Borrow, cmp::Ordering};
+
+/// 可投影的 `TokenInfo` 字段
+const PROJECTABLE_FIELDS: [&str; 5] = ["bundle", "status", "stripe", "sessions", "stats"];
+
+/// 排序字段
+#[derive(::serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TokensSortKey {
+    Id,
+    Alias,
+    Status,
+    MembershipType,
+    Email,
+    #[serde(untagged)]
+    Stats(TokenStatsSort),
+}
+
+#[derive(::serde::Deserialize, Default)]
+#[serde(default)]
+pub struct TokensQueryParams {
+    // 分页与排序控制
+    pub offset: Option<usize>,          // 起始位置偏移量
+    pub limit: Option<usize>,           // 返回记录数量限制
+    pub sort_by: Option<TokensSortKey>, // 排序字段，默认按ID
+    pub desc: bool,                     // 是否降序
+
+    // 过滤条件
+    #[serde(flatten)]
+    pub filter: TokensFilter,
+
+    // 输出控制
+    pub fields: Option<String>, // 逗号分隔的返回字段，默认返回全部
+    pub summary: bool,          // 仅返回别名、状态与会员类型
+}
+
+/// 摘要模式下的令牌信息
+#[derive(::serde::Serialize)]
+pub struct TokenSummary {
+    status: TokenStatus,
+    membership_type: Option<MembershipType>,
+}
+
+/// 与 `TokenManager` 中相同的 `[id, alias, info]` 结构
+#[derive(::serde::Serialize)]
+#[serde(untagged)]
+pub enum TokenEntry {
+    Full(usize, Alias, Box<TokenInfo>),
+    Projected(usize, Alias, Map<String, Value>),
+    Summary(usize, Alias, TokenSummary),
+}
+
+#[derive(::serde::Serialize)]
+pub struct TokensGetResponse {
+    pub status: ApiStatus,
+    pub tokens: Vec<TokenEntry>,
+    /// 分页前符合条件的令牌数
+    pub tokens_count: usize,
+}
+
+impl TokensQueryParams {
+    /// 解析投影字段，存在未知字段时返回错误
+    fn projection(&self) -> Result<Option<Vec<&str>>, String> {
+        let Some(fields) = &self.fields else {
+            return Ok(None);
+        };
+        let fields: Vec<&str> = fields
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+        if let Some(unknown) = fields.iter().find(|f| !PROJECTABLE_FIELDS.contains(f)) {
+            return Err(format!(
+                "未知字段: {unknown}，可选: {}",
+                PROJECTABLE_FIELDS.join(", ")
+            ));
+        }
+        Ok(Some(fields))
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        let membership_type = |c: &Candidate| c.info.stripe.map(|s| s.membership_type as u8);
+        fn email<'a>(c: &Candidate<'a>) -> Option<&'a str> {
+            c.info.bundle.user.as_ref().map(|u| u.email.as_str())
+        }
+        let ordering = match self.sort_by.unwrap_or(TokensSortKey::Id) {
+            TokensSortKey::Id => a.id.cmp(&b.id),
+            TokensSortKey::Alias => Borrow::<str>::borrow(a.alias).cmp(b.alias.borrow()),
+            TokensSortKey::Status => (a.info.status as u8).cmp(&(b.info.status as u8)),
+            TokensSortKey::MembershipType => membership_type(a).cmp(&membership_type(b)),
+            TokensSortKey::Email => email(a).cmp(&email(b)),
+            TokensSortKey::Stats(key) => match (&a.stats, &b.stats) {
+                (Some(x), Some(y)) => x.compare(y, key),
+                _ => Ordering::Equal,
+            },
+        };
+        // 相同值按ID保持稳定顺序
+        let ordering = ordering.then(a.id.cmp(&b.id));
+        if self.desc {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// 筛选、排序并分页，仅复制返回的令牌
+    pub fn select(
+        &self,
+        token_manager: &TokenManager,
+        token_stats: &TokenStatsStore,
+    ) -> Result<TokensGetResponse, String> {
+        let projection = self.projection()?;
+        let sort_by_stats = matches!(self.sort_by, Some(TokensSortKey::Stats(_)));
+        let mut candidates = self
+            .filter
+            .candidates(token_manager, token_stats, sort_by_stats)?;
+        if self.sort_by.is_some() || self.desc {
+            candidates.sort_unstable_by(|a, b| self.compare(a, b));
+        }
+        let tokens_count = candidates.len();
+
+        let now = TokenStatsStore::now();
+        let with_stats = projection
+            .as_ref()
+            .is_none_or(|fields| fields.contains(&"stats"));
+        let tokens = candidates
+            .into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .map(|c| {
+                if self.summary {
+                    return TokenEntry::Summary(c.id, c.alias.clone(), TokenSummary {
+                        status: c.info.status,
+                        membership_type: c.info.stripe.map(|s| s.membership_type),
+                    });
+                }
+                let mut info = c.info.clone();
+                if with_stats {
+                    info.stats = Some(c.stats.unwrap_or_else(|| {
+                        token_stats.view(&info.bundle.primary_token.key(), now)
+                    }));
+                }
+                match &projection {
+                    None => TokenEntry::Full(c.id, c.alias.clone(), Box::new(info)),
+                    Some(fields) => {
+                        let mut object = match serde_json::to_value(info) {
+                            Ok(Value::Object(object)) => object,
+                            _ => Map::new(),
+                        };
+                        object.retain(|key, _| fields.contains(&key.as_str()));
+                        TokenEntry::Projected(c.id, c.alias.clone(), object)
+                    }
+                }
+            })
+            .collect();
+
+        Ok(TokensGetResponse {
+            status: ApiStatus::Success,
+            tokens,
+            tokens_count,
+        })
+    }
+}
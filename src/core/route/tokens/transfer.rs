@@ -0,0 +1,363 @@
+use super::{ERROR_SAVE_TOKEN_DATA, MESSAGE_SAVE_TOKEN_DATA_FAILED};
+use crate::{
+    app::{
+        constant::{UNNAMED, UNNAMED_PATTERN},
+        model::{AppState, DateTime, TokenInfo, TokenManager},
+    },
+    common::{
+        model::{ApiStatus, GenericError},
+        utils::crypto::{self, CryptoError},
+    },
+};
+use axum::{Json, extract::State, http::StatusCode};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use std::{borrow::Cow, sync::Arc};
+
+crate::define_typed_constants! {
+    &'static str => {
+        ARCHIVE_FORMAT = "cursor-api-tokens",
+        KDF_ALGORITHM = "pbkdf2-sha256",
+        CIPHER_ALGORITHM = "aes-256-gcm",
+        ERROR_EMPTY_PASSPHRASE = "Empty passphrase",
+        MESSAGE_EMPTY_PASSPHRASE = "未提供口令",
+        ERROR_INVALID_ARCHIVE = "Invalid archive",
+        ERROR_DECRYPT_FAILED = "Decryption failed",
+        MESSAGE_DECRYPT_FAILED = "口令错误或文件已损坏",
+        ERROR_ENCRYPT_FAILED = "Encryption failed",
+    }
+}
+
+/// 导出格式版本，明文结构变化时递增
+const ARCHIVE_VERSION: u32 = 1;
+
+type TransferError = (StatusCode, Json<GenericError>);
+
+#[inline]
+fn transfer_error(
+    status: StatusCode,
+    error: &'static str,
+    message: impl Into<Cow<'static, str>>,
+) -> TransferError {
+    (
+        status,
+        Json(GenericError {
+            status: ApiStatus::Error,
+            code: None,
+            error: Some(Cow::Borrowed(error)),
+            message: Some(message.into()),
+        }),
+    )
+}
+
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+pub struct KdfParams {
+    algorithm: String,
+    iterations: u32,
+    /// Base64
+    salt: String,
+}
+
+/// 加密的令牌导出文件，可在实例之间迁移
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+pub struct TokensArchive {
+    format: String,
+    version: u32,
+    exported_at: DateTime,
+    kdf: KdfParams,
+    cipher: String,
+    /// Base64
+    nonce: String,
+    /// Base64，解密后为 `TokensDocument` 的 JSON
+    data: String,
+}
+
+/// 导出文件的明文内容
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+struct TokensDocument {
+    tokens: Vec<ExportedToken>,
+}
+
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+struct ExportedToken {
+    alias: String,
+    info: TokenInfo,
+}
+
+/// 附加认证数据，防止篡改格式与版本
+#[inline]
+fn aad(version: u32) -> String { format!("{ARCHIVE_FORMAT}/{version}") }
+
+#[derive(::serde::Deserialize)]
+pub struct TokensExportRequest {
+    pub passphrase: String,
+}
+
+/// 别名冲突时的处理方式
+#[derive(::serde::Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// 保留现有令牌
+    #[default]
+    Skip,
+    /// 用导入的令牌替换
+    Overwrite,
+    /// 以新别名导入
+    Rename,
+}
+
+#[derive(::serde::Deserialize)]
+pub struct TokensImportRequest {
+    pub passphrase: String,
+    pub archive: TokensArchive,
+    #[serde(default)]
+    pub strategy: ConflictStrategy,
+}
+
+#[derive(::serde::Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum ImportOutcome {
+    Added,
+    Overwritten,
+    Renamed,
+    Skipped,
+}
+
+#[derive(::serde::Serialize)]
+struct ImportResult {
+    alias: String,
+    outcome: ImportOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    renamed_to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'static str>,
+}
+
+#[derive(::serde::Serialize)]
+pub struct TokensImportResponse {
+    status: ApiStatus,
+    tokens_count: usize,
+    added: usize,
+    overwritten: usize,
+    renamed: usize,
+    skipped: usize,
+    results: Vec<ImportResult>,
+}
+
+/// 派生密钥的计算量较大，避免阻塞其他任务
+#[inline]
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    iterations: u32,
+) -> Result<[u8; crypto::KEY_LEN], CryptoError> {
+    tokio::task::block_in_place(|| crypto::derive_key(passphrase.as_bytes(), salt, iterations))
+}
+
+pub async fn handle_export_tokens(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TokensExportRequest>,
+) -> Result<Json<TokensArchive>, TransferError> {
+    if request.passphrase.is_empty() {
+        return Err(transfer_error(
+            StatusCode::BAD_REQUEST,
+            ERROR_EMPTY_PASSPHRASE,
+            MESSAGE_EMPTY_PASSPHRASE,
+        ));
+    }
+
+    let document = TokensDocument {
+        tokens: state
+            .token_manager_read()
+            .await
+            .iter()
+            .map(|(_, alias, info)| ExportedToken {
+                alias: alias.to_string(),
+                info: info.clone(),
+            })
+            .collect(),
+    };
+    let encrypt_failed = |e: &dyn std::fmt::Display| {
+        transfer_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ERROR_ENCRYPT_FAILED,
+            e.to_string(),
+        )
+    };
+    let plaintext = serde_json::to_vec(&document).map_err(|e| encrypt_failed(&e))?;
+
+    let salt = crypto::random_bytes::<{ crypto::SALT_LEN }>().map_err(|e| encrypt_failed(&e))?;
+    let key = derive_key(&request.passphrase, &salt, crypto::DEFAULT_ITERATIONS)
+        .map_err(|e| encrypt_failed(&e))?;
+    let (nonce, data) = crypto::seal(&key, aad(ARCHIVE_VERSION).as_bytes(), plaintext)
+        .map_err(|e| encrypt_failed(&e))?;
+
+    Ok(Json(TokensArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        exported_at: DateTime::now(),
+        kdf: KdfParams {
+            algorithm: KDF_ALGORITHM.to_string(),
+            iterations: crypto::DEFAULT_ITERATIONS,
+            salt: STANDARD.encode(salt),
+        },
+        cipher: CIPHER_ALGORITHM.to_string(),
+        nonce: STANDARD.encode(nonce),
+        data: STANDARD.encode(data),
+    }))
+}
+
+/// 解密导出文件
+fn decrypt(passphrase: &str, archive: TokensArchive) -> Result<TokensDocument, TransferError> {
+    let invalid =
+        |message: String| transfer_error(StatusCode::BAD_REQUEST, ERROR_INVALID_ARCHIVE, message);
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(invalid(format!("未知格式: {}", archive.format)));
+    }
+    if archive.version != ARCHIVE_VERSION {
+        return Err(invalid(format!("不支持的版本: {}", archive.version)));
+    }
+    if archive.kdf.algorithm != KDF_ALGORITHM || archive.cipher != CIPHER_ALGORITHM {
+        return Err(invalid(format!(
+            "不支持的算法: {}, {}",
+            archive.kdf.algorithm, archive.cipher
+        )));
+    }
+    let decode = |field: &str, value: &str| {
+        STANDARD
+            .decode(value)
+            .map_err(|e| invalid(format!("{field}: {e}")))
+    };
+    let salt = decode("kdf.salt", &archive.kdf.salt)?;
+    let nonce = decode("nonce", &archive.nonce)?;
+    let data = decode("data", &archive.data)?;
+
+    let key = derive_key(passphrase, &salt, archive.kdf.iterations)
+        .map_err(|e| invalid(e.to_string()))?;
+    let plaintext =
+        crypto::open(&key, &nonce, aad(archive.version).as_bytes(), data).map_err(|_| {
+            transfer_error(
+                StatusCode::BAD_REQUEST,
+                ERROR_DECRYPT_FAILED,
+                MESSAGE_DECRYPT_FAILED,
+            )
+        })?;
+    serde_json::from_slice(&plaintext).map_err(|e| invalid(e.to_string()))
+}
+
+#[inline]
+fn is_unnamed(alias: &str) -> bool { alias == UNNAMED || alias.starts_with(UNNAMED_PATTERN) }
+
+/// 为冲突的别名生成未被占用的新别名
+fn free_alias(token_manager: &TokenManager, alias: &str) -> String {
+    (2..)
+        .map(|n| format!("{alias}-{n}"))
+        .find(|candidate| !token_manager.alias_map().contains_key(candidate.as_str()))
+        .unwrap_or_default()
+}
+
+/// 按冲突策略导入单个令牌
+fn import_token(
+    token_manager: &mut TokenManager,
+    strategy: ConflictStrategy,
+    ExportedToken { alias, info }: ExportedToken,
+) -> ImportResult {
+    let key_id = token_manager
+        .id_map()
+        .get(&info.bundle.primary_token.key())
+        .copied();
+    // 未命名的别名在添加时重新生成，不会冲突
+    let alias_id = if is_unnamed(&alias) {
+        None
+    } else {
+        token_manager.alias_map().get(alias.as_str()).copied()
+    };
+
+    let mut result = ImportResult {
+        alias,
+        outcome: ImportOutcome::Added,
+        renamed_to: None,
+        reason: None,
+    };
+    if key_id.is_some() || alias_id.is_some() {
+        match strategy {
+            ConflictStrategy::Skip => {
+                result.outcome = ImportOutcome::Skipped;
+                result.reason = Some(if key_id.is_some() {
+                    "令牌已存在"
+                } else {
+                    "别名已存在"
+                });
+                return result;
+            }
+            ConflictStrategy::Overwrite => {
+                for id in key_id
+                    .into_iter()
+                    .chain(alias_id.filter(|&id| Some(id) != key_id))
+                {
+                    let _ = token_manager.remove(id);
+                }
+                result.outcome = ImportOutcome::Overwritten;
+            }
+            ConflictStrategy::Rename => {
+                // 相同的令牌改名后仍然重复
+                if key_id.is_some() {
+                    result.outcome = ImportOutcome::Skipped;
+                    result.reason = Some("令牌已存在");
+                    return result;
+                }
+                result.renamed_to = Some(free_alias(token_manager, &result.alias));
+                result.outcome = ImportOutcome::Renamed;
+            }
+        }
+    }
+
+    let alias = result.renamed_to.as_deref().unwrap_or(&result.alias);
+    if token_manager.add(info, alias.to_string()).is_err() {
+        result.outcome = ImportOutcome::Skipped;
+        result.reason = Some("别名已存在");
+    }
+    result
+}
+
+pub async fn handle_import_tokens(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TokensImportRequest>,
+) -> Result<Json<TokensImportResponse>, TransferError> {
+    if request.passphrase.is_empty() {
+        return Err(transfer_error(
+            StatusCode::BAD_REQUEST,
+            ERROR_EMPTY_PASSPHRASE,
+            MESSAGE_EMPTY_PASSPHRASE,
+        ));
+    }
+    let document = decrypt(&request.passphrase, request.archive)?;
+
+    let mut token_manager = state.token_manager_write().await;
+    let results: Vec<ImportResult> = document
+        .tokens
+        .into_iter()
+        .map(|token| import_token(&mut token_manager, request.strategy, token))
+        .collect();
+
+    let count = |outcome: ImportOutcome| results.iter().filter(|r| r.outcome == outcome).count();
+    let skipped = count(ImportOutcome::Skipped);
+
+    // 保存更改
+    if skipped < results.len() && token_manager.save().await.is_err() {
+        return Err(transfer_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ERROR_SAVE_TOKEN_DATA,
+            MESSAGE_SAVE_TOKEN_DATA_FAILED,
+        ));
+    }
+
+    Ok(Json(TokensImportResponse {
+        status: ApiStatus::Success,
+        tokens_count: token_manager.tokens().len(),
+        added: count(ImportOutcome::Added),
+        overwritten: count(ImportOutcome::Overwritten),
+        renamed: count(ImportOutcome::Renamed),
+        skipped,
+        results,
+    }))
+}
@@ -31,10 +31,14 @@ use crate::{
             OBJECT_CHAT_COMPLETION, OBJECT_CHAT_COMPLETION_CHUNK, UNKNOWN, UPSTREAM_FAILURE,
             get_thinking_tag_close, get_thinking_tag_open,
         },
-        lazy::{AUTH_TOKEN, KEY_PREFIX, REAL_USAGE, chat_url},
+        lazy::{
+            AUTH_TOKEN, CAPTURE_MAX_SIZE, KEY_PREFIX, REAL_USAGE, STREAM_EARLY_HEADERS,
+            STREAM_KEEPALIVE_INTERVAL, chat_url,
+        },
         model::{
-            Alias, AppConfig, AppState, Chain, ChainUsage, DateTime, ErrorInfo, ExtToken,
-            LogStatus, LogTokenInfo, Prompt, RequestLog, TimingInfo, TokenKey, UsageCheck,
+            Alias, AppConfig, AppState, CaptureEnabled, CaptureKind, Chain, ChainUsage, DateTime,
+            ErrorInfo, ExtToken, LogStatus, LogTokenInfo, Prompt, RequestLog, TimingInfo, TokenKey,
+            UsageCheck, UsageRecorder, access::ClientIp, proxy_pool, redaction,
         },
     },
     common::{
@@ -57,6 +61,7 @@ use crate::{
         stream::{
             decoder::{StreamDecoder, StreamMessage},
             droppable::DroppableStream,
+            keepalive::KeepAliveStream,
         },
     },
 };
@@ -94,7 +99,7 @@ pub async fn handle_models(
     // 获取token信息
     let (ext_token, is_pri) = {
         // 管理员Token
-        if let Some(part) = auth_token.strip_prefix(&**AUTH_TOKEN) {
+        if let Some(part) = auth_token.strip_prefix(AUTH_TOKEN.load().as_str()) {
             let token_manager = state.token_manager.read().await;
 
             let token_info = if part.is_empty() {
@@ -229,10 +234,16 @@ pub async fn handle_models(
 }
 
 // 聊天处理函数的签名
+/// 序列化规范化后的请求用于捕获，超出 `CAPTURE_MAX_SIZE` 时不捕获
+fn captured_body<T: ::serde::Serialize>(request: &T) -> Option<Bytes> {
+    let body = ::serde_json::to_vec(request).ok()?;
+    (body.len() <= CAPTURE_MAX_SIZE.get()).then(|| Bytes::from(body))
+}
+
 pub async fn handle_chat_completions(
     State(state): State<Arc<AppState>>,
     mut extensions: Extensions,
-    Json(request): Json<openai::ChatRequest>,
+    Json(mut request): Json<openai::ChatRequest>,
 ) -> Result<Response<Body>, (StatusCode, Json<OpenAiError>)> {
     // 验证模型是否支持并获取模型信息
     let model = if let Some(model) = ExtModel::from_str(&request.model) {
@@ -260,6 +271,9 @@ pub async fn handle_chat_completions(
         .remove::<KeyConfig>()
         .expect("middleware doesn't have `KeyConfig`");
 
+    let capture = extensions.remove::<CaptureEnabled>().is_some();
+    let client_ip = extensions.get::<ClientIp>().map(|&ClientIp(ip)| ip);
+
     let current_id: u64;
     let mut usage_check = None;
 
@@ -268,6 +282,7 @@ pub async fn handle_chat_completions(
     // 更新请求日志
     state.increment_total();
     state.increment_active();
+    let recorder = UsageRecorder::new(state.clone(), ext_token.primary_token.key(), is_pri);
     if state.log_manager_lock().await.is_enabled() {
         // let mut need_profile_check = false;
 
@@ -328,6 +343,7 @@ pub async fn handle_chat_completions(
                     stream: request.stream,
                     status: LogStatus::Pending,
                     error: ErrorInfo::None,
+                    client_ip,
                 },
                 ext_token.clone_without_user(),
             )
@@ -412,6 +428,22 @@ pub async fn handle_chat_completions(
         current_id = 0;
     }
 
+    if redaction::is_outbound() {
+        request.redact(redaction::outbound_text);
+    }
+    if capture && current_id != 0 {
+        let body = if redaction::is_logs() {
+            let mut request = request.clone();
+            request.redact(redaction::capture_text);
+            captured_body(&request)
+        } else {
+            captured_body(&request)
+        };
+        if let Some(body) = body {
+            state.capture_request(current_id, CaptureKind::ChatCompletions, body);
+        }
+    }
+
     // 将消息转换为hex格式
     let msg_id = uuid::Uuid::new_v4();
     let hex_data = match super::adapter::openai::encode_chat_message(
@@ -435,6 +467,7 @@ pub async fn handle_chat_completions(
                 .await;
             state.decrement_active();
             state.increment_error();
+            recorder.fail(&e);
             return Err((
                 StatusCode::BAD_REQUEST,
                 Json(ChatError::ProcessingFailed(Cow::Owned(e)).to_openai()),
@@ -443,9 +476,14 @@ pub async fn handle_chat_completions(
     };
     let msg_id = MessageId::new(msg_id.as_u128());
 
+    // 代理组在此确定本次使用的成员，便于失败归因
+    let mut req_token = ext_token.clone_without_user();
+    req_token.proxy = proxy_pool::pin(ext_token.proxy.as_deref(), ext_token.sticky_hash());
+    let proxy_name = req_token.proxy.clone();
+
     // 构建请求客户端
     let req = build_client_request(AiServiceRequest {
-        ext_token: ext_token.clone_without_user(),
+        ext_token: req_token,
         fs_client_key: None,
         url: chat_url(is_pri),
         is_stream: true,
@@ -463,6 +501,7 @@ pub async fn handle_chat_completions(
     // 处理请求结果
     let response = match response {
         Ok(resp) => {
+            proxy_pool::report_success(proxy_name.as_deref());
             // 更新请求日志为成功
             state
                 .update_log(current_id, |log| {
@@ -480,17 +519,27 @@ pub async fn handle_chat_completions(
             } else {
                 StatusCode::INTERNAL_SERVER_ERROR
             };
+            // 连接失败归因于代理，单独记录
+            let proxy = if e.is_connect() {
+                proxy_pool::report_failure(proxy_name.as_deref(), e.to_string())
+            } else {
+                None
+            };
             let e = e.to_string();
 
             // 更新请求日志为失败
             state
                 .update_log(current_id, |log| {
                     log.status = LogStatus::Failure;
-                    log.error = ErrorInfo::Error(crate::leak::intern_static(e.as_str()));
+                    log.error = match proxy {
+                        Some(proxy) => ErrorInfo::new_proxy(proxy.as_str(), e.as_str()),
+                        None => ErrorInfo::Error(crate::leak::intern_static(e.as_str())),
+                    };
                 })
                 .await;
             state.decrement_active();
             state.increment_error();
+            recorder.fail(&e);
 
             return Err((
                 status_code,
@@ -522,6 +571,7 @@ pub async fn handle_chat_completions(
             is_pri,
         )));
         let is_end = Arc::new(AtomicBool::new(false));
+        let recorder = Arc::new(recorder);
 
         // 定义消息处理器的上下文结构体
         struct MessageProcessContext<'a> {
@@ -536,6 +586,7 @@ pub async fn handle_chat_completions(
             created: i64,
             is_end: &'a AtomicBool,
             start: DateTime,
+            recorder: &'a UsageRecorder,
         }
 
         pub struct NeedUsage {
@@ -761,13 +812,14 @@ pub async fn handle_chat_completions(
                         if let Some((is_need, ext_token, is_pri)) =
                             ctx.need_usage.lock().await.take()
                         {
-                            let usage = if *REAL_USAGE {
+                            let usage = if REAL_USAGE.get() {
                                 let usage = tokio::spawn(get_token_usage(
                                     *ext_token, is_pri, ctx.start, ctx.model,
                                 ))
                                 .await
                                 .unwrap_or_default();
                                 if let Some(usage) = usage {
+                                    ctx.recorder.set_usage(usage);
                                     ctx.state
                                         .update_log(ctx.current_id, |log| {
                                             if let Some(chain) = &mut log.chain {
@@ -812,7 +864,7 @@ pub async fn handle_chat_completions(
                                     // chain.prompt.push_str(&debug_prompt);
                                 } else {
                                     log.chain = Some(Chain {
-                                        prompt: Prompt::new(debug_prompt),
+                                        prompt: Prompt::new(redaction::log_text(debug_prompt)),
                                         delays: None,
                                         usage: None,
                                         think: None,
@@ -828,9 +880,9 @@ pub async fn handle_chat_completions(
             response_data
         }
 
-        // 首先处理stream直到获得第一个结果
+        // 首先处理stream直到获得第一个结果，提前返回响应头时交由后续stream处理
         let (mut stream, drop_handle) = DroppableStream::new(response.bytes_stream());
-        {
+        if !*STREAM_EARLY_HEADERS {
             let mut decoder = decoder.lock().await;
             while !decoder.is_first_result_ready() {
                 match stream.next().await {
@@ -857,10 +909,12 @@ pub async fn handle_chat_completions(
                                 })
                                 .await;
                             state.increment_error();
+                            recorder.fail(canonical.title().as_deref().unwrap_or(UNKNOWN));
                             return Err((canonical.status_code(), Json(canonical.into_openai())));
                         }
                     }
                     Some(Err(e)) => {
+                        recorder.fail(&e.to_string());
                         return Err((
                             StatusCode::INTERNAL_SERVER_ERROR,
                             Json(
@@ -880,6 +934,7 @@ pub async fn handle_chat_completions(
                             })
                             .await;
                         state.increment_error();
+                        recorder.fail(ERR_STREAM_RESPONSE);
                         return Err((
                             StatusCode::INTERNAL_SERVER_ERROR,
                             Json(
@@ -896,6 +951,8 @@ pub async fn handle_chat_completions(
 
         let decoder_clone = decoder.clone();
         let state_clone = state.clone();
+        let is_end_clone = is_end.clone();
+        let recorder_clone = recorder.clone();
 
         // 处理后续的stream
         let stream = stream
@@ -909,6 +966,7 @@ pub async fn handle_chat_completions(
         let created = created.clone();
         let is_end = is_end.clone();
         let drop_handle = drop_handle.clone();
+        let recorder = recorder_clone.clone();
 
         async move {
           let chunk = match chunk {
@@ -931,6 +989,7 @@ pub async fn handle_chat_completions(
             created: *created.get_or_init(|| DateTime::utc_now().timestamp()),
             is_end: &is_end,
             start: request_time,
+            recorder: &recorder,
           };
 
           // 使用decoder处理chunk
@@ -949,7 +1008,26 @@ pub async fn handle_chat_completions(
                 // 罕见
                 StreamError::Upstream(e) => {
                   __cold_path!();
-                  let message = __unwrap!(serde_json::to_string(&e.canonical().into_openai()));
+                  let canonical = e.canonical();
+                  // 提前返回响应头时错误未在首个结果前记录，需在此标记失败
+                  if *STREAM_EARLY_HEADERS {
+                    state
+                      .update_log(current_id, |log| {
+                        log.status = LogStatus::Failure;
+                        log.error = ErrorInfo::Error(if let Some(title) = canonical.title() {
+                          crate::leak::intern_static(title)
+                        } else {
+                          UNKNOWN
+                        });
+                        if let Some(detail) = canonical.detail() {
+                          log.error.add_detail(detail)
+                        }
+                      })
+                      .await;
+                    state.increment_error();
+                  }
+                  recorder.fail(canonical.title().as_deref().unwrap_or(UNKNOWN));
+                  let message = __unwrap!(serde_json::to_string(&canonical.into_openai()));
                   let messages = [StreamMessage::Content(message), StreamMessage::StreamEnd];
                   return Ok(Bytes::from(process_messages(messages, &ctx).await));
                 }
@@ -987,11 +1065,22 @@ pub async fn handle_chat_completions(
       .chain(futures::stream::once(async move {
         // 更新delays
         let mut decoder_guard = decoder.lock().await;
-        let content_delays = decoder_guard.take_content_delays();
-        let thinking_content = decoder_guard.take_thinking_content();
+        let content_delays = redaction::log_delays(decoder_guard.take_content_delays());
+        let thinking_content = decoder_guard.take_thinking_content().map(redaction::log_text);
+        // 提前返回响应头时，上游可能在产生内容前就已结束
+        let is_empty = *STREAM_EARLY_HEADERS
+          && !decoder_guard.has_seen_content()
+          && !is_end_clone.load(Ordering::Acquire);
+        if let Some((_, delays)) = &content_delays {
+          recorder.first_token_from_delays(start_time, delays);
+        }
 
         state
           .update_log(current_id, move |log| {
+            if is_empty {
+              log.status = LogStatus::Failure;
+              log.error = ErrorInfo::Error(ERR_STREAM_RESPONSE);
+            }
             if let Some(chain) = &mut log.chain {
               chain.delays = content_delays;
             } else {
@@ -1004,6 +1093,11 @@ pub async fn handle_chat_completions(
             }
           })
           .await;
+        state.finish_capture(current_id).await;
+        if is_empty {
+          state.increment_error();
+          recorder.fail(ERR_STREAM_RESPONSE);
+        }
 
         if let Some(usage_check) = usage_check {
           tokio::spawn(usage_check);
@@ -1012,6 +1106,11 @@ pub async fn handle_chat_completions(
         Ok(Bytes::from_static(b"data: [DONE]\n\n"))
       }));
 
+        // 等待期间以SSE注释保活
+        let stream = KeepAliveStream::new(stream, STREAM_KEEPALIVE_INTERVAL.get(), || {
+            Bytes::from_static(b": keep-alive\n\n")
+        });
+
         Ok(__unwrap!(
             Response::builder()
                 .header(CACHE_CONTROL, NO_CACHE_REVALIDATE)
@@ -1032,6 +1131,7 @@ pub async fn handle_chat_completions(
         // 逐个处理chunks
         while let Some(chunk) = stream.next().await {
             let chunk = chunk.map_err(|e| {
+                recorder.fail(&e.to_string());
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(
@@ -1056,7 +1156,7 @@ pub async fn handle_chat_completions(
                             }
                             StreamMessage::Debug(debug_prompt) =>
                                 if prompt.is_none() {
-                                    prompt = Prompt::new(debug_prompt);
+                                    prompt = Prompt::new(redaction::log_text(debug_prompt));
                                 } else {
                                     __cold_path!();
                                     crate::debug!("UB!2 {debug_prompt:?}");
@@ -1080,6 +1180,7 @@ pub async fn handle_chat_completions(
                         })
                         .await;
                     state.increment_error();
+                    recorder.fail(canonical.title().as_deref().unwrap_or(UNKNOWN));
                     return Err((canonical.status_code(), Json(canonical.into_openai())));
                 }
                 Err(StreamError::EmptyStream) => {
@@ -1099,6 +1200,7 @@ pub async fn handle_chat_completions(
                         })
                         .await;
                     state.increment_error();
+                    recorder.fail(INVALID_STREAM);
                     let error_detail = openai::ErrorDetail {
                         code: Some(Cow::Borrowed(INVALID_STREAM)),
                         message: Cow::Borrowed(EMPTY_STRING),
@@ -1130,13 +1232,14 @@ pub async fn handle_chat_completions(
                 })
                 .await;
             state.increment_error();
+            recorder.fail(ERR_RESPONSE_RECEIVED);
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ChatError::RequestFailed(Cow::Borrowed(ERR_RESPONSE_RECEIVED)).to_openai()),
             ));
         }
 
-        let (chain_usage, openai_usage) = if *REAL_USAGE {
+        let (chain_usage, openai_usage) = if REAL_USAGE.get() {
             let usage = get_token_usage(ext_token, is_pri, request_time, model.id).await;
             let openai = usage.map(ChainUsage::to_openai);
             (usage, openai)
@@ -1169,8 +1272,14 @@ pub async fn handle_chat_completions(
 
         // 更新请求日志时间信息和状态
         let total_time = format_time_ms(start_time.elapsed().as_secs_f64());
-        let content_delays = decoder.take_content_delays();
-        let thinking_content = decoder.take_thinking_content();
+        let content_delays = redaction::log_delays(decoder.take_content_delays());
+        let thinking_content = decoder.take_thinking_content().map(redaction::log_text);
+        if let Some((_, delays)) = &content_delays {
+            recorder.first_token_from_delays(start_time, delays);
+        }
+        if let Some(usage) = chain_usage {
+            recorder.set_usage(usage);
+        }
 
         state
             .update_log(current_id, |log| {
@@ -1184,6 +1293,7 @@ pub async fn handle_chat_completions(
                 });
             })
             .await;
+        state.finish_capture(current_id).await;
 
         if let Some(usage_check) = usage_check {
             tokio::spawn(usage_check);
@@ -1222,7 +1332,7 @@ pub async fn handle_messages(
             Json(ChatError::ModelNotSupported(request.model).to_anthropic()),
         ));
     };
-    let params = request;
+    let mut params = request;
 
     // 验证请求
     if params.messages.is_empty() {
@@ -1240,6 +1350,9 @@ pub async fn handle_messages(
         .remove::<KeyConfig>()
         .expect("middleware doesn't have `KeyConfig`");
 
+    let capture = extensions.remove::<CaptureEnabled>().is_some();
+    let client_ip = extensions.get::<ClientIp>().map(|&ClientIp(ip)| ip);
+
     let current_id: u64;
     let mut usage_check = None;
 
@@ -1248,6 +1361,7 @@ pub async fn handle_messages(
     // 更新请求日志
     state.increment_total();
     state.increment_active();
+    let recorder = UsageRecorder::new(state.clone(), ext_token.primary_token.key(), is_pri);
     if state.log_manager_lock().await.is_enabled() {
         // let mut need_profile_check = false;
 
@@ -1308,6 +1422,7 @@ pub async fn handle_messages(
                     stream: params.stream,
                     status: LogStatus::Pending,
                     error: ErrorInfo::None,
+                    client_ip,
                 },
                 ext_token.clone_without_user(),
             )
@@ -1392,6 +1507,22 @@ pub async fn handle_messages(
         current_id = 0;
     }
 
+    if redaction::is_outbound() {
+        params.redact(redaction::outbound_text);
+    }
+    if capture && current_id != 0 {
+        let body = if redaction::is_logs() {
+            let mut params = params.clone();
+            params.redact(redaction::capture_text);
+            captured_body(&params)
+        } else {
+            captured_body(&params)
+        };
+        if let Some(body) = body {
+            state.capture_request(current_id, CaptureKind::Messages, body);
+        }
+    }
+
     // 将消息转换为hex格式
     let stream = params.stream;
     let msg_id = uuid::Uuid::new_v4();
@@ -1416,6 +1547,7 @@ pub async fn handle_messages(
                 .await;
             state.decrement_active();
             state.increment_error();
+            recorder.fail(&e);
             return Err((
                 StatusCode::BAD_REQUEST,
                 Json(ChatError::ProcessingFailed(Cow::Owned(e)).to_anthropic()),
@@ -1424,9 +1556,14 @@ pub async fn handle_messages(
     };
     let msg_id = MessageId::new(msg_id.as_u128());
 
+    // 代理组在此确定本次使用的成员，便于失败归因
+    let mut req_token = ext_token.clone_without_user();
+    req_token.proxy = proxy_pool::pin(ext_token.proxy.as_deref(), ext_token.sticky_hash());
+    let proxy_name = req_token.proxy.clone();
+
     // 构建请求客户端
     let req = build_client_request(AiServiceRequest {
-        ext_token: ext_token.clone_without_user(),
+        ext_token: req_token,
         fs_client_key: None,
         url: chat_url(is_pri),
         is_stream: true,
@@ -1444,6 +1581,7 @@ pub async fn handle_messages(
     // 处理请求结果
     let response = match response {
         Ok(resp) => {
+            proxy_pool::report_success(proxy_name.as_deref());
             // 更新请求日志为成功
             state
                 .update_log(current_id, |log| {
@@ -1461,17 +1599,27 @@ pub async fn handle_messages(
             } else {
                 StatusCode::INTERNAL_SERVER_ERROR
             };
+            // 连接失败归因于代理，单独记录
+            let proxy = if e.is_connect() {
+                proxy_pool::report_failure(proxy_name.as_deref(), e.to_string())
+            } else {
+                None
+            };
             let e = e.to_string();
 
             // 更新请求日志为失败
             state
                 .update_log(current_id, |log| {
                     log.status = LogStatus::Failure;
-                    log.error = ErrorInfo::Error(crate::leak::intern_static(e.as_str()));
+                    log.error = match proxy {
+                        Some(proxy) => ErrorInfo::new_proxy(proxy.as_str(), e.as_str()),
+                        None => ErrorInfo::Error(crate::leak::intern_static(e.as_str())),
+                    };
                 })
                 .await;
             state.decrement_active();
             state.increment_error();
+            recorder.fail(&e);
 
             return Err((
                 status_code,
@@ -1499,6 +1647,7 @@ pub async fn handle_messages(
         let stream_state = Arc::new(AtomicU8::new(0));
         let last_content_type = Arc::new(AtomicU8::new(0)); // 新增：记录上次内容类型
         let need_usage = Arc::new(Mutex::new(NeedUsage::new(ext_token, is_pri)));
+        let recorder = Arc::new(recorder);
 
         #[repr(u8)]
         #[derive(Clone, Copy, PartialEq)]
@@ -1537,6 +1686,7 @@ pub async fn handle_messages(
             current_id: u64,
             need_usage: &'a Mutex<NeedUsage>,
             start: DateTime,
+            recorder: &'a UsageRecorder,
         }
 
         pub struct NeedUsage {
@@ -1757,13 +1907,14 @@ pub async fn handle_messages(
 
                         // 处理使用量统计
                         if let Some((ext_token, is_pri)) = ctx.need_usage.lock().await.take() {
-                            let usage = if *REAL_USAGE {
+                            let usage = if REAL_USAGE.get() {
                                 let usage = tokio::spawn(get_token_usage(
                                     *ext_token, is_pri, ctx.start, ctx.model,
                                 ))
                                 .await
                                 .unwrap_or_default();
                                 if let Some(usage) = usage {
+                                    ctx.recorder.set_usage(usage);
                                     ctx.app_state
                                         .update_log(ctx.current_id, |log| {
                                             if let Some(chain) = &mut log.chain {
@@ -1803,7 +1954,7 @@ pub async fn handle_messages(
                                     // chain.prompt.push_str(&debug_prompt);
                                 } else {
                                     log.chain = Some(Chain {
-                                        prompt: Prompt::new(debug_prompt),
+                                        prompt: Prompt::new(redaction::log_text(debug_prompt)),
                                         delays: None,
                                         usage: None,
                                         think: None,
@@ -1819,9 +1970,9 @@ pub async fn handle_messages(
             response_data
         }
 
-        // 首先处理stream直到获得第一个结果
+        // 首先处理stream直到获得第一个结果，提前返回响应头时交由后续stream处理
         let (mut stream, drop_handle) = DroppableStream::new(response.bytes_stream());
-        {
+        if !*STREAM_EARLY_HEADERS {
             let mut decoder = decoder.lock().await;
             while !decoder.is_first_result_ready() {
                 match stream.next().await {
@@ -1848,6 +1999,7 @@ pub async fn handle_messages(
                                 })
                                 .await;
                             state.increment_error();
+                            recorder.fail(canonical.title().as_deref().unwrap_or(UNKNOWN));
                             return Err((
                                 canonical.status_code(),
                                 Json(canonical.into_anthropic()),
@@ -1855,6 +2007,7 @@ pub async fn handle_messages(
                         }
                     }
                     Some(Err(e)) => {
+                        recorder.fail(&e.to_string());
                         return Err((
                             StatusCode::INTERNAL_SERVER_ERROR,
                             Json(
@@ -1874,6 +2027,7 @@ pub async fn handle_messages(
                             })
                             .await;
                         state.increment_error();
+                        recorder.fail(ERR_STREAM_RESPONSE);
                         return Err((
                             StatusCode::INTERNAL_SERVER_ERROR,
                             Json(
@@ -1888,6 +2042,10 @@ pub async fn handle_messages(
 
         let decoder_clone = decoder.clone();
         let state_clone = state.clone();
+        let stream_state_clone = stream_state.clone();
+        let keepalive_state = stream_state.clone();
+        let keepalive_msg_id = msg_id.clone();
+        let recorder_clone = recorder.clone();
 
         // 处理后续的stream
         let stream = stream
@@ -1900,6 +2058,7 @@ pub async fn handle_messages(
         let last_content_type = last_content_type.clone();
         let need_usage = need_usage.clone();
         let drop_handle = drop_handle.clone();
+        let recorder = recorder_clone.clone();
 
         async move {
           let chunk = match chunk {
@@ -1921,6 +2080,7 @@ pub async fn handle_messages(
             current_id,
             need_usage: &need_usage,
             start: request_time,
+            recorder: &recorder,
           };
 
           // 使用decoder处理chunk
@@ -1940,6 +2100,26 @@ pub async fn handle_messages(
                 StreamError::Upstream(e) => {
                   __cold_path!();
                   let canonical = e.canonical();
+                  // 提前返回响应头时错误未在首个结果前记录，需在此标记失败
+                  if *STREAM_EARLY_HEADERS {
+                    app_state
+                      .update_log(current_id, |log| {
+                        log.status = LogStatus::Failure;
+                        log.error = ErrorInfo::Error(if let Some(title) = canonical.title() {
+                          crate::leak::intern_static(title)
+                        } else {
+                          UNKNOWN
+                        });
+                        if let Some(detail) = canonical.detail() {
+                          log.error.add_detail(detail)
+                        }
+                      })
+                      .await;
+                    app_state.increment_error();
+                    stream_state.store(StreamState::Completed as u8, Ordering::Release);
+                    drop_handle.drop_stream();
+                  }
+                  recorder.fail(canonical.title().as_deref().unwrap_or(UNKNOWN));
                   let mut buf = Vec::with_capacity(128);
                   extend_from_slice(&mut buf, &anthropic::RawMessageStreamEvent::Error {
                     error: {
@@ -1983,11 +2163,22 @@ pub async fn handle_messages(
       .chain(futures::stream::once(async move {
         // 更新delays
         let mut decoder_guard = decoder.lock().await;
-        let content_delays = decoder_guard.take_content_delays();
-        let thinking_content = decoder_guard.take_thinking_content();
+        let content_delays = redaction::log_delays(decoder_guard.take_content_delays());
+        let thinking_content = decoder_guard.take_thinking_content().map(redaction::log_text);
+        // 提前返回响应头时，上游可能在产生内容前就已结束
+        let is_empty = *STREAM_EARLY_HEADERS
+          && !decoder_guard.has_seen_content()
+          && stream_state_clone.load(Ordering::Acquire) != StreamState::Completed as u8;
+        if let Some((_, delays)) = &content_delays {
+          recorder.first_token_from_delays(start_time, delays);
+        }
 
         state
           .update_log(current_id, move |log| {
+            if is_empty {
+              log.status = LogStatus::Failure;
+              log.error = ErrorInfo::Error(ERR_STREAM_RESPONSE);
+            }
             if let Some(chain) = &mut log.chain {
               chain.delays = content_delays;
             } else {
@@ -2000,6 +2191,11 @@ pub async fn handle_messages(
             }
           })
           .await;
+        state.finish_capture(current_id).await;
+        if is_empty {
+          state.increment_error();
+          recorder.fail(ERR_STREAM_RESPONSE);
+        }
 
         if let Some(usage_check) = usage_check {
           tokio::spawn(usage_check);
@@ -2010,6 +2206,24 @@ pub async fn handle_messages(
         ))
       }));
 
+        // 等待期间先补发 message_start，之后以 ping 事件保活
+        let stream = KeepAliveStream::new(stream, STREAM_KEEPALIVE_INTERVAL.get(), move || {
+            let mut buf = Vec::with_capacity(64);
+            if keepalive_state.load(Ordering::Acquire) == StreamState::NotStarted as u8 {
+                extend_from_slice(&mut buf, &anthropic::RawMessageStreamEvent::MessageStart {
+                    message: anthropic::Message {
+                        content: vec![],
+                        usage: anthropic::Usage::default(),
+                        id: &keepalive_msg_id,
+                        model: model.id,
+                    },
+                });
+                keepalive_state.store(StreamState::MessageStarted as u8, Ordering::Release);
+            }
+            extend_from_slice(&mut buf, &anthropic::RawMessageStreamEvent::Ping);
+            Bytes::from(buf)
+        });
+
         Ok(__unwrap!(
             Response::builder()
                 .header(CACHE_CONTROL, NO_CACHE_REVALIDATE)
@@ -2029,6 +2243,7 @@ pub async fn handle_messages(
         // 逐个处理chunks
         while let Some(chunk) = stream.next().await {
             let chunk = chunk.map_err(|e| {
+                recorder.fail(&e.to_string());
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(
@@ -2102,7 +2317,7 @@ pub async fn handle_messages(
                             }
                             StreamMessage::Debug(debug_prompt) =>
                                 if prompt.is_none() {
-                                    prompt = Prompt::new(debug_prompt);
+                                    prompt = Prompt::new(redaction::log_text(debug_prompt));
                                 } else {
                                     __cold_path!();
                                     crate::debug!("UB!2 {debug_prompt:?}");
@@ -2126,6 +2341,7 @@ pub async fn handle_messages(
                         })
                         .await;
                     state.increment_error();
+                    recorder.fail(canonical.title().as_deref().unwrap_or(UNKNOWN));
                     return Err((canonical.status_code(), Json(canonical.into_anthropic())));
                 }
                 Err(StreamError::EmptyStream) => {
@@ -2145,6 +2361,7 @@ pub async fn handle_messages(
                         })
                         .await;
                     state.increment_error();
+                    recorder.fail(INVALID_STREAM);
                     let error_detail = anthropic::ErrorDetail {
                         r#type: INVALID_STREAM,
                         message: Cow::Borrowed(EMPTY_STRING),
@@ -2154,7 +2371,7 @@ pub async fn handle_messages(
             }
         }
 
-        let (chain_usage, anthropic_usage) = if *REAL_USAGE {
+        let (chain_usage, anthropic_usage) = if REAL_USAGE.get() {
             let usage = get_token_usage(ext_token, is_pri, request_time, model.id).await;
             let anthropic = usage.map(ChainUsage::to_anthropic);
             (usage, anthropic)
@@ -2177,8 +2394,14 @@ pub async fn handle_messages(
 
         // 更新请求日志时间信息和状态
         let total_time = format_time_ms(start_time.elapsed().as_secs_f64());
-        let content_delays = decoder.take_content_delays();
-        let thinking_content = decoder.take_thinking_content();
+        let content_delays = redaction::log_delays(decoder.take_content_delays());
+        let thinking_content = decoder.take_thinking_content().map(redaction::log_text);
+        if let Some((_, delays)) = &content_delays {
+            recorder.first_token_from_delays(start_time, delays);
+        }
+        if let Some(usage) = chain_usage {
+            recorder.set_usage(usage);
+        }
 
         state
             .update_log(current_id, |log| {
@@ -2192,6 +2415,7 @@ pub async fn handle_messages(
                 });
             })
             .await;
+        state.finish_capture(current_id).await;
 
         if let Some(usage_check) = usage_check {
             tokio::spawn(usage_check);
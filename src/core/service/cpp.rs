@@ -1,8 +1,9 @@
-use std::{borrow::Cow, convert::Infallible, sync::Arc};
+use std::{borrow::Cow, convert::Infallible, sync::Arc, time::Instant};
 
 use axum::{
     Json,
     body::Body,
+    extract::State,
     response::{IntoResponse as _, Response},
 };
 use bytes::Bytes;
@@ -20,10 +21,10 @@ use crate::{
     app::{
         constant::{
             CHUNKED, CLIENT_KEY, ERR_STREAM_RESPONSE, ERROR, EVENT_STREAM, JSON, KEEP_ALIVE,
-            NO_CACHE_REVALIDATE,
+            NO_CACHE_REVALIDATE, UNKNOWN,
         },
         lazy::{cpp_config_url, cpp_models_url},
-        model::{CppService, ExtToken},
+        model::{AppState, CppService, ExtToken, UsageRecorder},
     },
     common::{
         client::{AiServiceRequest, build_client_request},
@@ -35,7 +36,7 @@ use crate::{
             AvailableCppModelsResponse, CppConfigRequest, CppConfigResponse, FsSyncFileRequest,
             FsSyncFileResponse, FsUploadFileRequest, FsUploadFileResponse, StreamCppRequest,
         },
-        error::StreamError,
+        error::{CursorError, StreamError},
         stream::decoder::{
             cpp::{StreamDecoder, StreamMessage},
             direct,
@@ -45,6 +46,7 @@ use crate::{
 };
 
 pub async fn handle_cpp_config(
+    State(state): State<Arc<AppState>>,
     mut headers: HeaderMap,
     mut extensions: Extensions,
     Json(request): Json<CppConfigRequest>,
@@ -52,6 +54,7 @@ pub async fn handle_cpp_config(
     let (ext_token, is_pri) = extensions
         .remove::<(ExtToken, bool)>()
         .expect("middleware doesn't have `(ExtToken, bool)`");
+    let recorder = UsageRecorder::new(state, ext_token.primary_token.key(), is_pri);
 
     let req = build_client_request(AiServiceRequest {
         ext_token,
@@ -68,28 +71,37 @@ pub async fn handle_cpp_config(
     match async { req.body(body).send().await?.bytes().await }.await {
         Ok(bytes) => match direct::decode::<CppConfigResponse>(&bytes) {
             Ok(DecodedMessage::Protobuf(data)) => Ok(Json(data)),
-            Ok(DecodedMessage::Text(s)) => Err(__unwrap!(
-                Response::builder()
-                    .header(CONTENT_TYPE, JSON)
-                    .header(CONTENT_LENGTH, s.len())
-                    .body(Body::from(s))
-            )),
-            Err(DecoderError::Internal(e)) => Err((
-                StatusCode::BAD_GATEWAY,
-                Json(ChatError::RequestFailed(Cow::Borrowed(e)).to_generic()),
-            )
-                .into_response()),
+            Ok(DecodedMessage::Text(s)) => {
+                recorder.fail(&text_error_title(&s));
+                Err(__unwrap!(
+                    Response::builder()
+                        .header(CONTENT_TYPE, JSON)
+                        .header(CONTENT_LENGTH, s.len())
+                        .body(Body::from(s))
+                ))
+            }
+            Err(DecoderError::Internal(e)) => {
+                recorder.fail(e);
+                Err((
+                    StatusCode::BAD_GATEWAY,
+                    Json(ChatError::RequestFailed(Cow::Borrowed(e)).to_generic()),
+                )
+                    .into_response())
+            }
         },
         Err(mut e) => {
             e = e.without_url();
+            let status_code = if e.is_timeout() {
+                StatusCode::GATEWAY_TIMEOUT
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            let e = e.to_string();
+            recorder.fail(&e);
 
             Err((
-                if e.is_timeout() {
-                    StatusCode::GATEWAY_TIMEOUT
-                } else {
-                    StatusCode::INTERNAL_SERVER_ERROR
-                },
-                Json(ChatError::RequestFailed(Cow::Owned(e.to_string())).to_generic()),
+                status_code,
+                Json(ChatError::RequestFailed(Cow::Owned(e)).to_generic()),
             )
                 .into_response())
         }
@@ -97,12 +109,14 @@ pub async fn handle_cpp_config(
 }
 
 pub async fn handle_cpp_models(
+    State(state): State<Arc<AppState>>,
     mut headers: HeaderMap,
     mut extensions: Extensions,
 ) -> Result<Json<AvailableCppModelsResponse>, Response> {
     let (ext_token, is_pri) = extensions
         .remove::<(ExtToken, bool)>()
         .expect("middleware doesn't have `(ExtToken, bool)`");
+    let recorder = UsageRecorder::new(state, ext_token.primary_token.key(), is_pri);
 
     let req = build_client_request(AiServiceRequest {
         ext_token,
@@ -117,28 +131,37 @@ pub async fn handle_cpp_models(
     match async { req.send().await?.bytes().await }.await {
         Ok(bytes) => match direct::decode::<AvailableCppModelsResponse>(&bytes) {
             Ok(DecodedMessage::Protobuf(data)) => Ok(Json(data)),
-            Ok(DecodedMessage::Text(s)) => Err(__unwrap!(
-                Response::builder()
-                    .header(CONTENT_TYPE, JSON)
-                    .header(CONTENT_LENGTH, s.len())
-                    .body(Body::from(s))
-            )),
-            Err(DecoderError::Internal(e)) => Err((
-                StatusCode::BAD_GATEWAY,
-                Json(ChatError::RequestFailed(Cow::Borrowed(e)).to_generic()),
-            )
-                .into_response()),
+            Ok(DecodedMessage::Text(s)) => {
+                recorder.fail(&text_error_title(&s));
+                Err(__unwrap!(
+                    Response::builder()
+                        .header(CONTENT_TYPE, JSON)
+                        .header(CONTENT_LENGTH, s.len())
+                        .body(Body::from(s))
+                ))
+            }
+            Err(DecoderError::Internal(e)) => {
+                recorder.fail(e);
+                Err((
+                    StatusCode::BAD_GATEWAY,
+                    Json(ChatError::RequestFailed(Cow::Borrowed(e)).to_generic()),
+                )
+                    .into_response())
+            }
         },
         Err(mut e) => {
             e = e.without_url();
+            let status_code = if e.is_timeout() {
+                StatusCode::GATEWAY_TIMEOUT
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            let e = e.to_string();
+            recorder.fail(&e);
 
             Err((
-                if e.is_timeout() {
-                    StatusCode::GATEWAY_TIMEOUT
-                } else {
-                    StatusCode::INTERNAL_SERVER_ERROR
-                },
-                Json(ChatError::RequestFailed(Cow::Owned(e.to_string())).to_generic()),
+                status_code,
+                Json(ChatError::RequestFailed(Cow::Owned(e)).to_generic()),
             )
                 .into_response())
         }
@@ -154,6 +177,7 @@ const TO_REMOVE_HEADERS: [http::HeaderName; 5] = [
 ];
 
 pub async fn handle_upload_file(
+    State(state): State<Arc<AppState>>,
     mut headers: HeaderMap,
     mut extensions: Extensions,
     Json(request): Json<FsUploadFileRequest>,
@@ -161,6 +185,7 @@ pub async fn handle_upload_file(
     let (ext_token, is_pri) = extensions
         .remove::<(ExtToken, bool)>()
         .expect("middleware doesn't have `(ExtToken, bool)`");
+    let recorder = UsageRecorder::new(state, ext_token.primary_token.key(), is_pri);
     let gcpp_host = ext_token.get_gcpp_host();
 
     let req = build_client_request(AiServiceRequest {
@@ -194,17 +219,23 @@ pub async fn handle_upload_file(
                             parts,
                             Body::from(__unwrap!(serde_json::to_vec(&data))),
                         )),
-                        Ok(DecodedMessage::Text(s)) => Err(__unwrap!(
-                            Response::builder()
-                                .header(CONTENT_TYPE, JSON)
-                                .header(CONTENT_LENGTH, s.len())
-                                .body(Body::from(s))
-                        )),
-                        Err(DecoderError::Internal(e)) => Err((
-                            StatusCode::BAD_GATEWAY,
-                            Json(ChatError::RequestFailed(Cow::Borrowed(e)).to_generic()),
-                        )
-                            .into_response()),
+                        Ok(DecodedMessage::Text(s)) => {
+                            recorder.fail(&text_error_title(&s));
+                            Err(__unwrap!(
+                                Response::builder()
+                                    .header(CONTENT_TYPE, JSON)
+                                    .header(CONTENT_LENGTH, s.len())
+                                    .body(Body::from(s))
+                            ))
+                        }
+                        Err(DecoderError::Internal(e)) => {
+                            recorder.fail(e);
+                            Err((
+                                StatusCode::BAD_GATEWAY,
+                                Json(ChatError::RequestFailed(Cow::Borrowed(e)).to_generic()),
+                            )
+                                .into_response())
+                        }
                     };
                 }
                 Err(e) => e,
@@ -218,14 +249,17 @@ pub async fn handle_upload_file(
     } else {
         StatusCode::INTERNAL_SERVER_ERROR
     };
+    let e = e.to_string();
+    recorder.fail(&e);
     Err((
         status_code,
-        Json(ChatError::RequestFailed(Cow::Owned(e.to_string())).to_generic()),
+        Json(ChatError::RequestFailed(Cow::Owned(e)).to_generic()),
     )
         .into_response())
 }
 
 pub async fn handle_sync_file(
+    State(state): State<Arc<AppState>>,
     mut headers: HeaderMap,
     mut extensions: Extensions,
     Json(request): Json<FsSyncFileRequest>,
@@ -233,6 +267,7 @@ pub async fn handle_sync_file(
     let (ext_token, is_pri) = extensions
         .remove::<(ExtToken, bool)>()
         .expect("middleware doesn't have `(ExtToken, bool)`");
+    let recorder = UsageRecorder::new(state, ext_token.primary_token.key(), is_pri);
     let gcpp_host = ext_token.get_gcpp_host();
 
     let req = build_client_request(AiServiceRequest {
@@ -266,17 +301,23 @@ pub async fn handle_sync_file(
                             parts,
                             Body::from(__unwrap!(serde_json::to_vec(&data))),
                         )),
-                        Ok(DecodedMessage::Text(s)) => Err(__unwrap!(
-                            Response::builder()
-                                .header(CONTENT_TYPE, JSON)
-                                .header(CONTENT_LENGTH, s.len())
-                                .body(Body::from(s))
-                        )),
-                        Err(DecoderError::Internal(e)) => Err((
-                            StatusCode::BAD_GATEWAY,
-                            Json(ChatError::RequestFailed(Cow::Borrowed(e)).to_generic()),
-                        )
-                            .into_response()),
+                        Ok(DecodedMessage::Text(s)) => {
+                            recorder.fail(&text_error_title(&s));
+                            Err(__unwrap!(
+                                Response::builder()
+                                    .header(CONTENT_TYPE, JSON)
+                                    .header(CONTENT_LENGTH, s.len())
+                                    .body(Body::from(s))
+                            ))
+                        }
+                        Err(DecoderError::Internal(e)) => {
+                            recorder.fail(e);
+                            Err((
+                                StatusCode::BAD_GATEWAY,
+                                Json(ChatError::RequestFailed(Cow::Borrowed(e)).to_generic()),
+                            )
+                                .into_response())
+                        }
                     };
                 }
                 Err(e) => e,
@@ -290,14 +331,17 @@ pub async fn handle_sync_file(
     } else {
         StatusCode::INTERNAL_SERVER_ERROR
     };
+    let e = e.to_string();
+    recorder.fail(&e);
     Err((
         status_code,
-        Json(ChatError::RequestFailed(Cow::Owned(e.to_string())).to_generic()),
+        Json(ChatError::RequestFailed(Cow::Owned(e)).to_generic()),
     )
         .into_response())
 }
 
 pub async fn handle_stream_cpp(
+    State(state): State<Arc<AppState>>,
     mut headers: HeaderMap,
     mut extensions: Extensions,
     Json(request): Json<StreamCppRequest>,
@@ -305,6 +349,7 @@ pub async fn handle_stream_cpp(
     let (ext_token, is_pri) = extensions
         .remove::<(ExtToken, bool)>()
         .expect("middleware doesn't have `(ExtToken, bool)`");
+    let recorder = UsageRecorder::new(state, ext_token.primary_token.key(), is_pri);
     let gcpp_host = ext_token.get_gcpp_host();
 
     let req = build_client_request(AiServiceRequest {
@@ -318,9 +363,11 @@ pub async fn handle_stream_cpp(
     });
 
     let body = encode_message(&request, true).map_err(|e| {
+        let e = e.to_string();
+        recorder.fail(&e);
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ChatError::RequestFailed(Cow::Owned(e.to_string())).to_generic()),
+            Json(ChatError::RequestFailed(Cow::Owned(e)).to_generic()),
         )
     })?;
 
@@ -328,14 +375,17 @@ pub async fn handle_stream_cpp(
         Ok(r) => r,
         Err(mut e) => {
             e = e.without_url();
+            let status_code = if e.is_timeout() {
+                StatusCode::GATEWAY_TIMEOUT
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            let e = e.to_string();
+            recorder.fail(&e);
 
             return Err((
-                if e.is_timeout() {
-                    StatusCode::GATEWAY_TIMEOUT
-                } else {
-                    StatusCode::INTERNAL_SERVER_ERROR
-                },
-                Json(ChatError::RequestFailed(Cow::Owned(e.to_string())).to_generic()),
+                status_code,
+                Json(ChatError::RequestFailed(Cow::Owned(e)).to_generic()),
             ));
         }
     };
@@ -379,21 +429,20 @@ pub async fn handle_stream_cpp(
                 Some(Ok(chunk)) => {
                     if let Err(StreamError::Upstream(error)) = decoder.decode(&chunk) {
                         let canonical = error.canonical();
+                        recorder.fail(canonical.title().as_deref().unwrap_or(UNKNOWN));
                         return Err((canonical.status_code(), Json(canonical.into_generic())));
                     }
                 }
                 Some(Err(e)) => {
+                    let e = format!("Failed to read response chunk: {e}");
+                    recorder.fail(&e);
                     return Err((
                         StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(
-                            ChatError::RequestFailed(Cow::Owned(format!(
-                                "Failed to read response chunk: {e}"
-                            )))
-                            .to_generic(),
-                        ),
+                        Json(ChatError::RequestFailed(Cow::Owned(e)).to_generic()),
                     ));
                 }
                 None => {
+                    recorder.fail(ERR_STREAM_RESPONSE);
                     return Err((
                         StatusCode::INTERNAL_SERVER_ERROR,
                         Json(
@@ -406,11 +455,14 @@ pub async fn handle_stream_cpp(
         }
     }
 
+    recorder.first_token(Instant::now());
+    let recorder = Arc::new(recorder);
     let decoder_clone = decoder.clone();
 
-    // 处理后续的stream
+    // 处理后续的stream，统计随流一同结束
     let stream = stream.then(move |chunk| {
         let decoder = decoder_clone.clone();
+        let recorder = recorder.clone();
         async move {
             let chunk = match chunk {
                 Ok(c) => c,
@@ -436,8 +488,10 @@ pub async fn handle_stream_cpp(
                         // 罕见
                         StreamError::Upstream(e) => {
                             __cold_path!();
+                            let canonical = e.canonical();
+                            recorder.fail(canonical.title().as_deref().unwrap_or(UNKNOWN));
                             let message =
-                                __unwrap!(serde_json::to_string(&e.canonical().into_generic()));
+                                __unwrap!(serde_json::to_string(&canonical.into_generic()));
                             let messages = [StreamMessage::Error { message }];
                             return Ok(Bytes::from(process_messages(messages)));
                         }
@@ -478,3 +532,11 @@ pub async fn handle_stream_cpp(
             .body(Body::from_stream(stream))
     ))
 }
+
+/// 上游以文本返回的错误只记录其标题，无法解析时记为未知
+fn text_error_title(s: &str) -> String {
+    serde_json::from_str::<CursorError>(s)
+        .ok()
+        .and_then(|error| error.canonical().title())
+        .unwrap_or_else(|| UNKNOWN.to_string())
+}
@@ -1,3 +1,4 @@
 pub mod decoder;
 // pub mod processor;
 pub mod droppable;
+pub mod keepalive;
@@ -192,6 +192,9 @@ impl StreamDecoder {
     #[inline]
     pub fn is_first_result_ready(&self) -> bool { self.first_result_ready }
 
+    #[inline]
+    pub fn has_seen_content(&self) -> bool { self.has_seen_content }
+
     #[inline]
     pub fn take_content_delays(&mut self) -> Option<(String, Vec<(u32, f32)>)> {
         ::core::mem::take(&mut self.content_delays)
@@ -0,0 +1,143 @@
+use bytes::Bytes;
+use futures::stream::Stream;
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep};
+
+/// 在内部 Stream 长时间无输出时插入保活数据的包装器
+///
+/// 每当内部 Stream 产出非空数据时重置计时，超时后调用 `heartbeat` 生成保活数据
+pub struct KeepAliveStream<S, F> {
+    inner: Pin<Box<S>>,
+    sleep: Option<(Pin<Box<Sleep>>, Duration)>,
+    heartbeat: F,
+}
+
+impl<S, F> KeepAliveStream<S, F>
+where
+    S: Stream<Item = Result<Bytes, Infallible>>,
+    F: FnMut() -> Bytes + Unpin,
+{
+    /// 创建新的保活 Stream，`interval` 为 `None` 时不发送保活数据
+    pub fn new(inner: S, interval: Option<Duration>, heartbeat: F) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            sleep: interval.map(|interval| (Box::pin(tokio::time::sleep(interval)), interval)),
+            heartbeat,
+        }
+    }
+}
+
+impl<S, F> Stream for KeepAliveStream<S, F>
+where
+    S: Stream<Item = Result<Bytes, Infallible>>,
+    F: FnMut() -> Bytes + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // 优先轮询内部 stream
+        if let Poll::Ready(item) = this.inner.as_mut().poll_next(cx) {
+            if let (Some(Ok(bytes)), Some((sleep, interval))) = (&item, &mut this.sleep)
+                && !bytes.is_empty()
+            {
+                sleep.as_mut().reset(Instant::now() + *interval);
+            }
+            return Poll::Ready(item);
+        }
+
+        // 内部 stream 空闲，检查是否需要发送保活数据
+        if let Some((sleep, interval)) = &mut this.sleep
+            && sleep.as_mut().poll(cx).is_ready()
+        {
+            sleep.as_mut().reset(Instant::now() + *interval);
+            return Poll::Ready(Some(Ok((this.heartbeat)())));
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt as _;
+
+    const HEARTBEAT: &[u8] = b": keep-alive\n\n";
+
+    fn block_on<T>(future: impl Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// 延迟 `delay` 后依次产出 `items` 的 Stream
+    fn delayed(
+        delay: Duration,
+        items: Vec<&'static [u8]>,
+    ) -> impl Stream<Item = Result<Bytes, Infallible>> {
+        futures::stream::once(tokio::time::sleep(delay)).flat_map(move |()| {
+            futures::stream::iter(items.clone().into_iter().map(|b| Ok(Bytes::from_static(b))))
+        })
+    }
+
+    async fn collect(stream: impl Stream<Item = Result<Bytes, Infallible>>) -> Vec<Bytes> {
+        stream.map(|item| item.unwrap()).collect().await
+    }
+
+    #[test]
+    fn test_keepalive_interval() {
+        let items = block_on(async {
+            collect(KeepAliveStream::new(
+                delayed(Duration::from_millis(250), vec![b"data"]),
+                Some(Duration::from_millis(50)),
+                || Bytes::from_static(HEARTBEAT),
+            ))
+            .await
+        });
+
+        let (last, heartbeats) = items.split_last().unwrap();
+        assert_eq!(last.as_ref(), b"data");
+        assert!(heartbeats.len() >= 2, "heartbeats: {}", heartbeats.len());
+        assert!(heartbeats.iter().all(|b| b.as_ref() == HEARTBEAT));
+    }
+
+    #[test]
+    fn test_keepalive_disabled() {
+        let items = block_on(async {
+            collect(KeepAliveStream::new(
+                delayed(Duration::from_millis(100), vec![b"a", b"b"]),
+                None,
+                || Bytes::from_static(HEARTBEAT),
+            ))
+            .await
+        });
+        assert_eq!(items, [Bytes::from_static(b"a"), Bytes::from_static(b"b")]);
+    }
+
+    #[test]
+    fn test_keepalive_stream_end() {
+        // 内部 Stream 结束后立即结束，不再发送保活数据
+        let items = block_on(async {
+            let mut stream = KeepAliveStream::new(
+                delayed(Duration::ZERO, vec![b"a"]),
+                Some(Duration::from_millis(10)),
+                || Bytes::from_static(HEARTBEAT),
+            );
+            let first = stream.next().await;
+            let end = stream.next().await;
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            (first, end)
+        });
+        assert_eq!(items.0.unwrap().unwrap().as_ref(), b"a");
+        assert!(items.1.is_none());
+    }
+}
@@ -25,41 +25,58 @@ use ::axum::{
     routing::{get, post},
 };
 use ::tokio::signal;
-use ::tower_http::{cors::CorsLayer, limit::RequestBodyLimitLayer};
+use ::tower_http::limit::RequestBodyLimitLayer;
 
 use app::{
-    config::handle_config_update,
+    config::{handle_config_reload, handle_config_update},
     constant::{
-        EMPTY_STRING, EXE_NAME, ROUTE_ABOUT_PATH, ROUTE_API_PATH, ROUTE_BUILD_KEY_PATH,
-        ROUTE_CONFIG_PATH, ROUTE_CONFIG_VERSION_GET_PATH, ROUTE_CPP_CONFIG_PATH,
+        EMPTY_STRING, EXE_NAME, ROUTE_ABOUT_PATH, ROUTE_ADMINS_ADD_PATH, ROUTE_ADMINS_DELETE_PATH,
+        ROUTE_ADMINS_GET_PATH, ROUTE_ADMINS_SET_PATH, ROUTE_API_PATH, ROUTE_AUDIT_GET_PATH,
+        ROUTE_BACKUP_CREATE_PATH, ROUTE_BACKUP_LIST_PATH, ROUTE_BACKUP_PATH,
+        ROUTE_BACKUP_RESTORE_PATH, ROUTE_BUILD_KEY_PATH, ROUTE_CONFIG_PATH,
+        ROUTE_CONFIG_RELOAD_PATH, ROUTE_CONFIG_VERSION_GET_PATH, ROUTE_CPP_CONFIG_PATH,
         ROUTE_CPP_MODELS_PATH, ROUTE_CPP_STREAM_PATH, ROUTE_ENV_EXAMPLE_PATH, ROUTE_FILE_SYNC_PATH,
         ROUTE_FILE_UPLOAD_PATH, ROUTE_GEN_CHECKSUM, ROUTE_GEN_HASH, ROUTE_GEN_UUID,
-        ROUTE_GET_TIMESTAMP_HEADER, ROUTE_HEALTH_PATH, ROUTE_LOGS_GET_PATH, ROUTE_LOGS_PATH,
-        ROUTE_LOGS_TOKENS_GET_PATH, ROUTE_PROXIES_ADD_PATH, ROUTE_PROXIES_DELETE_PATH,
-        ROUTE_PROXIES_GET_PATH, ROUTE_PROXIES_PATH, ROUTE_PROXIES_SET_GENERAL_PATH,
-        ROUTE_PROXIES_SET_PATH, ROUTE_README_PATH, ROUTE_ROOT_PATH, ROUTE_STATIC_PATH,
-        ROUTE_TOKENS_ADD_PATH, ROUTE_TOKENS_ALIAS_SET_PATH,
-        ROUTE_TOKENS_CONFIG_VERSION_UPDATE_PATH, ROUTE_TOKENS_DELETE_PATH, ROUTE_TOKENS_GET_PATH,
-        ROUTE_TOKENS_PATH, ROUTE_TOKENS_PROFILE_UPDATE_PATH, ROUTE_TOKENS_PROXY_SET_PATH,
-        ROUTE_TOKENS_REFRESH_PATH, ROUTE_TOKENS_SET_PATH, ROUTE_TOKENS_STATUS_SET_PATH,
-        ROUTE_TOKENS_TIMEZONE_SET_PATH, VERSION,
+        ROUTE_GET_TIMESTAMP_HEADER, ROUTE_HEALTH_PATH, ROUTE_JOBS_GET_PATH, ROUTE_JOBS_RUN_PATH,
+        ROUTE_LOGS_ANALYTICS_PATH, ROUTE_LOGS_EXPORT_PATH, ROUTE_LOGS_GET_PATH, ROUTE_LOGS_PATH,
+        ROUTE_LOGS_REPLAY_PATH, ROUTE_LOGS_TAIL_PATH, ROUTE_LOGS_TOKENS_GET_PATH,
+        ROUTE_PROXIES_ADD_PATH, ROUTE_PROXIES_DELETE_PATH, ROUTE_PROXIES_GET_PATH,
+        ROUTE_PROXIES_PATH, ROUTE_PROXIES_SET_GENERAL_PATH, ROUTE_PROXIES_SET_PATH,
+        ROUTE_PROXY_GROUPS_DELETE_PATH, ROUTE_PROXY_GROUPS_GET_PATH, ROUTE_PROXY_GROUPS_SET_PATH,
+        ROUTE_README_PATH, ROUTE_ROOT_PATH, ROUTE_STATIC_PATH, ROUTE_TOKENS_ADD_PATH,
+        ROUTE_TOKENS_ALIAS_SET_PATH, ROUTE_TOKENS_BULK_PATH,
+        ROUTE_TOKENS_CONFIG_VERSION_UPDATE_PATH, ROUTE_TOKENS_DELETE_PATH,
+        ROUTE_TOKENS_EXPORT_PATH, ROUTE_TOKENS_GET_PATH, ROUTE_TOKENS_IMPORT_PATH,
+        ROUTE_TOKENS_PATH, ROUTE_TOKENS_PROFILE_UPDATE_PATH, ROUTE_TOKENS_PROXY_ASSIGN_PATH,
+        ROUTE_TOKENS_PROXY_SET_PATH, ROUTE_TOKENS_REFRESH_PATH, ROUTE_TOKENS_SET_PATH,
+        ROUTE_TOKENS_STATUS_SET_PATH, ROUTE_TOKENS_TIMEZONE_SET_PATH, VERSION,
     },
-    lazy::AUTH_TOKEN,
-    model::{AppConfig, AppState},
+    lazy::{AUTH_TOKEN, ROUTE_PREFIX},
+    model::{AppConfig, AppState, admin::Permission},
+    settings,
 };
 use common::utils::parse_from_env;
 use core::{
-    middleware::{admin_auth_middleware, cpp_auth_middleware, v1_auth_middleware},
+    middleware::{
+        Scope, access_middleware, admin_auth_middleware, audit_middleware, capture_middleware,
+        client_ip_middleware, cors_layer, cpp_auth_middleware, v1_auth_middleware,
+    },
     route::{
-        handle_about, handle_add_proxy, handle_add_tokens, handle_api_page, handle_build_key,
-        handle_build_key_page, handle_config_page, handle_delete_proxies, handle_delete_tokens,
-        handle_env_example, handle_gen_checksum, handle_gen_hash, handle_gen_uuid,
-        handle_get_config_version, handle_get_logs, handle_get_logs_tokens, handle_get_proxies,
-        handle_get_timestamp_header, handle_get_tokens, handle_health, handle_logs, handle_options,
-        handle_proxies_page, handle_readme, handle_refresh_tokens, handle_root,
-        handle_set_general_proxy, handle_set_proxies, handle_set_tokens, handle_set_tokens_alias,
+        handle_about, handle_add_admin, handle_add_proxy, handle_add_tokens, handle_api_page,
+        handle_assign_tokens_proxy, handle_backup, handle_build_key, handle_build_key_page,
+        handle_bulk_tokens, handle_config_page, handle_create_backup, handle_delete_admins,
+        handle_delete_proxies, handle_delete_proxy_groups, handle_delete_tokens,
+        handle_env_example, handle_export_logs, handle_export_tokens, handle_gen_checksum,
+        handle_gen_hash, handle_gen_uuid, handle_get_admins, handle_get_audit,
+        handle_get_config_version, handle_get_jobs, handle_get_logs, handle_get_logs_analytics,
+        handle_get_logs_tokens, handle_get_proxies, handle_get_proxy_groups,
+        handle_get_timestamp_header, handle_get_tokens, handle_health, handle_import_tokens,
+        handle_list_backups, handle_logs, handle_options, handle_proxies_page, handle_readme,
+        handle_refresh_tokens, handle_replay_log, handle_restore_backup, handle_root,
+        handle_run_job, handle_set_admin, handle_set_general_proxy, handle_set_proxies,
+        handle_set_proxy_groups, handle_set_tokens, handle_set_tokens_alias,
         handle_set_tokens_proxy, handle_set_tokens_status, handle_set_tokens_timezone,
-        handle_static, handle_tokens_page, handle_update_tokens_config_version,
+        handle_static, handle_tail_logs, handle_tokens_page, handle_update_tokens_config_version,
         handle_update_tokens_profile,
     },
     service::{
@@ -70,7 +87,7 @@ use core::{
         handle_chat_completions, handle_messages, handle_models, handle_raw_models,
     },
 };
-use natural_args::{DEFAULT_LISTEN_HOST, ENV_HOST, ENV_PORT};
+use natural_args::{ENV_HOST, ENV_PORT, ENV_TLS_CERT, ENV_TLS_KEY, ENV_TLS_PORT};
 
 #[tokio::main]
 async fn main() {
@@ -115,7 +132,7 @@ async fn main() {
 
     // tracing_subscriber::fmt::init();
 
-    if AUTH_TOKEN.is_empty() {
+    if AUTH_TOKEN.load().is_empty() {
         __cold_path!();
         __eprintln!("AUTH_TOKEN must be set\n");
         std::process::exit(1);
@@ -125,7 +142,14 @@ async fn main() {
     AppConfig::init();
 
     // 初始化应用状态
-    let state = std::sync::Arc::new(__unwrap_panic!(AppState::load().await));
+    let state = match AppState::load().await {
+        Ok(state) => std::sync::Arc::new(state),
+        Err(e) => {
+            // 数据无法读取时拒绝启动，避免以空状态覆盖原有数据
+            eprintln!("加载数据失败: {e}");
+            std::process::exit(1);
+        }
+    };
 
     // 尝试加载保存的配置
     if let Err(e) = AppConfig::load() {
@@ -162,11 +186,14 @@ async fn main() {
         }
     });
 
-    // 创建一个克隆用于信号处理
-    let state_for_shutdown = state.clone();
+    // 定时任务：自动保存、资料刷新、日志清理、代理探测与备份
+    app::jobs::start(state.clone());
+
+    // 收到 SIGHUP 时重新加载配置
+    settings::spawn_reloader();
 
     // 设置关闭信号处理
-    let shutdown_signal = async move {
+    let shutdown_signal = async {
         let ctrl_c = async {
             signal::ctrl_c()
                 .await
@@ -194,208 +221,461 @@ async fn main() {
         }
 
         __println!("正在关闭服务器...");
-
-        // 保存配置
-        if let Err(e) = AppConfig::save() {
-            __cold_path!(); // 配置保存失败是错误路径
-            eprintln!("保存配置失败: {e}");
-        } else {
-            __println!("配置已保存");
-        }
-
-        // 保存状态
-        if let Err(e) = state_for_shutdown.save().await {
-            __cold_path!(); // 状态保存失败是错误路径
-            eprintln!("保存状态失败: {e}");
-        } else {
-            __println!("状态已保存");
-        }
-
-        app::lazy::log::flush_all_debug_logs().await;
     };
 
     // 设置路由
-    let app = {
-        let (
-            route_raw_models_path,
-            route_models_path,
-            route_chat_completions_path,
-            route_messages_path,
-        ) = {
-            define_typed_constants! {
-                &'static str => {
-                    RAW_MODELS_PATH = "/raw/models",
-                    MODELS_PATH = "/v1/models",
-                    CHAT_COMPLETIONS_PATH = "/v1/chat/completions",
-                    MESSAGES_PATH = "/v1/messages",
-                }
-            }
-            use ::std::borrow::Cow;
-
-            let route_prefix = parse_from_env("ROUTE_PREFIX", EMPTY_STRING);
-            if route_prefix.is_empty() {
-                (
-                    Cow::Borrowed(RAW_MODELS_PATH),
-                    Cow::Borrowed(MODELS_PATH),
-                    Cow::Borrowed(CHAT_COMPLETIONS_PATH),
-                    Cow::Borrowed(MESSAGES_PATH),
+    define_typed_constants! {
+        &'static str => {
+            RAW_MODELS_PATH = "/raw/models",
+            MODELS_PATH = "/v1/models",
+            CHAT_COMPLETIONS_PATH = "/v1/chat/completions",
+            MESSAGES_PATH = "/v1/messages",
+        }
+    }
+
+    // 对话、模型与 Copilot++ 接口，始终由主端口提供
+    let api_routes = Router::new()
+        .without_v07_checks()
+        .merge(
+            Router::new()
+                .without_v07_checks()
+                .route(ROUTE_HEALTH_PATH, get(handle_health))
+                .route(RAW_MODELS_PATH, get(handle_raw_models))
+                .route(MODELS_PATH, get(handle_models).options(handle_options))
+                .route(
+                    MESSAGES_PATH,
+                    post(handle_messages)
+                        .route_layer(middleware::from_fn(capture_middleware))
+                        .route_layer(middleware::from_fn_with_state(
+                            state.clone(),
+                            v1_auth_middleware,
+                        ))
+                        .options(handle_options),
                 )
-            } else {
-                #[inline]
-                fn make_route(route_prefix: &str, path: &'static str) -> Cow<'static, str> {
-                    let mut route_path = String::with_capacity(path.len() + route_prefix.len());
-                    route_path.push_str(route_prefix);
-                    route_path.push_str(path);
-                    Cow::Owned(route_path)
-                }
+                .route(
+                    CHAT_COMPLETIONS_PATH,
+                    post(handle_chat_completions)
+                        .route_layer(middleware::from_fn(capture_middleware))
+                        .route_layer(middleware::from_fn_with_state(
+                            state.clone(),
+                            v1_auth_middleware,
+                        ))
+                        .options(handle_options),
+                )
+                .route(ROUTE_GEN_UUID, get(handle_gen_uuid))
+                .route(ROUTE_GEN_HASH, get(handle_gen_hash))
+                .route(ROUTE_GEN_CHECKSUM, get(handle_gen_checksum))
+                .route(ROUTE_GET_TIMESTAMP_HEADER, get(handle_get_timestamp_header))
+                // .route(ROUTE_BASIC_CALIBRATION_PATH, post(handle_basic_calibration))
+                // .route(ROUTE_USER_INFO_PATH, post(handle_user_info))
+                .route_layer(middleware::from_fn_with_state(
+                    Scope::Api,
+                    access_middleware,
+                )),
+        )
+        .merge(
+            Router::new()
+                .without_v07_checks()
+                .route(ROUTE_CPP_CONFIG_PATH, post(handle_cpp_config))
+                .route(ROUTE_CPP_MODELS_PATH, post(handle_cpp_models))
+                .route(ROUTE_FILE_UPLOAD_PATH, post(handle_upload_file))
+                .route(ROUTE_FILE_SYNC_PATH, post(handle_sync_file))
+                .route(ROUTE_CPP_STREAM_PATH, post(handle_stream_cpp))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    cpp_auth_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    Scope::Api,
+                    access_middleware,
+                )),
+        );
 
-                (
-                    make_route(&route_prefix, RAW_MODELS_PATH),
-                    make_route(&route_prefix, MODELS_PATH),
-                    make_route(&route_prefix, CHAT_COMPLETIONS_PATH),
-                    make_route(&route_prefix, MESSAGES_PATH),
+    // 页面、日志与管理接口，设置 ADMIN_PORT 后仅由管理端口提供
+    let admin_routes = Router::new()
+        .without_v07_checks()
+        .merge(
+            Router::new()
+                .without_v07_checks()
+                .route(ROUTE_ROOT_PATH, get(handle_root))
+                .route(ROUTE_TOKENS_PATH, get(handle_tokens_page))
+                .route(ROUTE_PROXIES_PATH, get(handle_proxies_page))
+                .route(ROUTE_LOGS_PATH, get(handle_logs))
+                .route(ROUTE_LOGS_GET_PATH, post(handle_get_logs))
+                .route(ROUTE_LOGS_TOKENS_GET_PATH, post(handle_get_logs_tokens))
+                .route(ROUTE_LOGS_ANALYTICS_PATH, post(handle_get_logs_analytics))
+                .route(ROUTE_LOGS_EXPORT_PATH, post(handle_export_logs))
+                .route(ROUTE_LOGS_TAIL_PATH, post(handle_tail_logs))
+                .route(ROUTE_ENV_EXAMPLE_PATH, get(handle_env_example))
+                .route(
+                    ROUTE_STATIC_PATH,
+                    get(handle_static).options(handle_options),
                 )
-            }
+                .route(ROUTE_ABOUT_PATH, get(handle_about))
+                .route(ROUTE_README_PATH, get(handle_readme))
+                .route(ROUTE_API_PATH, get(handle_api_page))
+                .route(
+                    ROUTE_BUILD_KEY_PATH,
+                    get(handle_build_key_page).post(handle_build_key),
+                )
+                .route(
+                    ROUTE_CONFIG_VERSION_GET_PATH,
+                    post(handle_get_config_version),
+                )
+                // .route(ROUTE_TOKEN_UPGRADE_PATH, post(handle_token_upgrade))
+                .route_layer(middleware::from_fn_with_state(
+                    Scope::Api,
+                    access_middleware,
+                )),
+        )
+        .merge(
+            Router::new()
+                .without_v07_checks()
+                .route(ROUTE_TOKENS_GET_PATH, post(handle_get_tokens))
+                .route(ROUTE_TOKENS_SET_PATH, post(handle_set_tokens))
+                .route(ROUTE_TOKENS_ADD_PATH, post(handle_add_tokens))
+                .route(ROUTE_TOKENS_DELETE_PATH, post(handle_delete_tokens))
+                .route(ROUTE_TOKENS_ALIAS_SET_PATH, post(handle_set_tokens_alias))
+                .route(
+                    ROUTE_TOKENS_PROFILE_UPDATE_PATH,
+                    post(handle_update_tokens_profile),
+                )
+                .route(
+                    ROUTE_TOKENS_CONFIG_VERSION_UPDATE_PATH,
+                    post(handle_update_tokens_config_version),
+                )
+                .route(ROUTE_TOKENS_REFRESH_PATH, post(handle_refresh_tokens))
+                .route(ROUTE_TOKENS_STATUS_SET_PATH, post(handle_set_tokens_status))
+                .route(ROUTE_TOKENS_PROXY_SET_PATH, post(handle_set_tokens_proxy))
+                .route(
+                    ROUTE_TOKENS_PROXY_ASSIGN_PATH,
+                    post(handle_assign_tokens_proxy),
+                )
+                .route(
+                    ROUTE_TOKENS_TIMEZONE_SET_PATH,
+                    post(handle_set_tokens_timezone),
+                )
+                .route(ROUTE_TOKENS_BULK_PATH, post(handle_bulk_tokens))
+                .route(ROUTE_TOKENS_EXPORT_PATH, post(handle_export_tokens))
+                .route(ROUTE_TOKENS_IMPORT_PATH, post(handle_import_tokens))
+                .route_layer(middleware::from_fn_with_state(
+                    Permission::ManageTokens,
+                    admin_auth_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    audit_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    Scope::Admin,
+                    access_middleware,
+                )),
+        )
+        .merge(
+            Router::new()
+                .without_v07_checks()
+                .route(ROUTE_PROXIES_GET_PATH, post(handle_get_proxies))
+                .route(ROUTE_PROXIES_SET_PATH, post(handle_set_proxies))
+                .route(ROUTE_PROXIES_ADD_PATH, post(handle_add_proxy))
+                .route(ROUTE_PROXIES_DELETE_PATH, post(handle_delete_proxies))
+                .route(
+                    ROUTE_PROXIES_SET_GENERAL_PATH,
+                    post(handle_set_general_proxy),
+                )
+                .route(ROUTE_PROXY_GROUPS_GET_PATH, post(handle_get_proxy_groups))
+                .route(ROUTE_PROXY_GROUPS_SET_PATH, post(handle_set_proxy_groups))
+                .route(
+                    ROUTE_PROXY_GROUPS_DELETE_PATH,
+                    post(handle_delete_proxy_groups),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    Permission::ManageProxies,
+                    admin_auth_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    audit_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    Scope::Admin,
+                    access_middleware,
+                )),
+        )
+        .merge(
+            Router::new()
+                .without_v07_checks()
+                .route(ROUTE_CONFIG_RELOAD_PATH, post(handle_config_reload))
+                .route_layer(middleware::from_fn_with_state(
+                    Permission::ManageConfig,
+                    admin_auth_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    audit_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    Scope::Admin,
+                    access_middleware,
+                )),
+        )
+        .merge(
+            Router::new()
+                .without_v07_checks()
+                .route(ROUTE_LOGS_REPLAY_PATH, post(handle_replay_log))
+                .route(ROUTE_BACKUP_PATH, post(handle_backup))
+                .route(ROUTE_BACKUP_CREATE_PATH, post(handle_create_backup))
+                .route(ROUTE_BACKUP_LIST_PATH, post(handle_list_backups))
+                .route(ROUTE_BACKUP_RESTORE_PATH, post(handle_restore_backup))
+                .route(ROUTE_ADMINS_GET_PATH, post(handle_get_admins))
+                .route(ROUTE_ADMINS_ADD_PATH, post(handle_add_admin))
+                .route(ROUTE_ADMINS_SET_PATH, post(handle_set_admin))
+                .route(ROUTE_ADMINS_DELETE_PATH, post(handle_delete_admins))
+                .route(ROUTE_AUDIT_GET_PATH, post(handle_get_audit))
+                .route(ROUTE_JOBS_GET_PATH, post(handle_get_jobs))
+                .route(ROUTE_JOBS_RUN_PATH, post(handle_run_job))
+                .route_layer(middleware::from_fn_with_state(
+                    Permission::Full,
+                    admin_auth_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    audit_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    Scope::Admin,
+                    access_middleware,
+                )),
+        )
+        .merge(
+            Router::new()
+                .without_v07_checks()
+                .route(
+                    ROUTE_CONFIG_PATH,
+                    get(handle_config_page)
+                        .post(handle_config_update)
+                        .route_layer(middleware::from_fn_with_state(
+                            state.clone(),
+                            audit_middleware,
+                        )),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    Scope::Admin,
+                    access_middleware,
+                )),
+        );
+
+    // 加上路由前缀与全局中间件
+    let finish = |routes: Router<std::sync::Arc<AppState>>| {
+        let routes = if ROUTE_PREFIX.is_empty() {
+            routes
+        } else {
+            Router::new()
+                .without_v07_checks()
+                .nest(&ROUTE_PREFIX, routes)
         };
-        Router::new()
-            .without_v07_checks()
-            .route(ROUTE_ROOT_PATH, get(handle_root))
-            .route(ROUTE_HEALTH_PATH, get(handle_health))
-            .route(ROUTE_TOKENS_PATH, get(handle_tokens_page))
-            .route(ROUTE_PROXIES_PATH, get(handle_proxies_page))
-            .merge(
-                Router::new()
-                    .without_v07_checks()
-                    .route(ROUTE_TOKENS_GET_PATH, post(handle_get_tokens))
-                    .route(ROUTE_TOKENS_SET_PATH, post(handle_set_tokens))
-                    .route(ROUTE_TOKENS_ADD_PATH, post(handle_add_tokens))
-                    .route(ROUTE_TOKENS_DELETE_PATH, post(handle_delete_tokens))
-                    .route(ROUTE_TOKENS_ALIAS_SET_PATH, post(handle_set_tokens_alias))
-                    .route(
-                        ROUTE_TOKENS_PROFILE_UPDATE_PATH,
-                        post(handle_update_tokens_profile),
-                    )
-                    .route(
-                        ROUTE_TOKENS_CONFIG_VERSION_UPDATE_PATH,
-                        post(handle_update_tokens_config_version),
-                    )
-                    .route(ROUTE_TOKENS_REFRESH_PATH, post(handle_refresh_tokens))
-                    .route(ROUTE_TOKENS_STATUS_SET_PATH, post(handle_set_tokens_status))
-                    .route(ROUTE_TOKENS_PROXY_SET_PATH, post(handle_set_tokens_proxy))
-                    .route(
-                        ROUTE_TOKENS_TIMEZONE_SET_PATH,
-                        post(handle_set_tokens_timezone),
-                    )
-                    .route(ROUTE_PROXIES_GET_PATH, post(handle_get_proxies))
-                    .route(ROUTE_PROXIES_SET_PATH, post(handle_set_proxies))
-                    .route(ROUTE_PROXIES_ADD_PATH, post(handle_add_proxy))
-                    .route(ROUTE_PROXIES_DELETE_PATH, post(handle_delete_proxies))
-                    .route(
-                        ROUTE_PROXIES_SET_GENERAL_PATH,
-                        post(handle_set_general_proxy),
-                    )
-                    .route_layer(middleware::from_fn(admin_auth_middleware)),
-            )
-            .merge(
-                Router::new()
-                    .without_v07_checks()
-                    .route(ROUTE_CPP_CONFIG_PATH, post(handle_cpp_config))
-                    .route(ROUTE_CPP_MODELS_PATH, post(handle_cpp_models))
-                    .route(ROUTE_FILE_UPLOAD_PATH, post(handle_upload_file))
-                    .route(ROUTE_FILE_SYNC_PATH, post(handle_sync_file))
-                    .route(ROUTE_CPP_STREAM_PATH, post(handle_stream_cpp))
-                    .route_layer(middleware::from_fn_with_state(
-                        state.clone(),
-                        cpp_auth_middleware,
-                    )),
-            )
-            .route(&route_raw_models_path, get(handle_raw_models))
-            .route(
-                &route_models_path,
-                get(handle_models).options(handle_options),
-            )
-            .route(
-                &route_messages_path,
-                post(handle_messages)
-                    .route_layer(middleware::from_fn_with_state(
-                        state.clone(),
-                        v1_auth_middleware,
-                    ))
-                    .options(handle_options),
-            )
-            .route(
-                &route_chat_completions_path,
-                post(handle_chat_completions)
-                    .route_layer(middleware::from_fn_with_state(
-                        state.clone(),
-                        v1_auth_middleware,
-                    ))
-                    .options(handle_options),
-            )
-            .route(ROUTE_LOGS_PATH, get(handle_logs))
-            .route(ROUTE_LOGS_GET_PATH, post(handle_get_logs))
-            .route(ROUTE_LOGS_TOKENS_GET_PATH, post(handle_get_logs_tokens))
-            .route(ROUTE_ENV_EXAMPLE_PATH, get(handle_env_example))
-            .route(
-                ROUTE_CONFIG_PATH,
-                get(handle_config_page).post(handle_config_update),
-            )
-            .route(
-                ROUTE_STATIC_PATH,
-                get(handle_static).options(handle_options),
-            )
-            .route(ROUTE_ABOUT_PATH, get(handle_about))
-            .route(ROUTE_README_PATH, get(handle_readme))
-            .route(ROUTE_API_PATH, get(handle_api_page))
-            .route(ROUTE_GEN_UUID, get(handle_gen_uuid))
-            .route(ROUTE_GEN_HASH, get(handle_gen_hash))
-            .route(ROUTE_GEN_CHECKSUM, get(handle_gen_checksum))
-            .route(ROUTE_GET_TIMESTAMP_HEADER, get(handle_get_timestamp_header))
-            // .route(ROUTE_BASIC_CALIBRATION_PATH, post(handle_basic_calibration))
-            // .route(ROUTE_USER_INFO_PATH, post(handle_user_info))
-            .route(
-                ROUTE_BUILD_KEY_PATH,
-                get(handle_build_key_page).post(handle_build_key),
-            )
-            .route(
-                ROUTE_CONFIG_VERSION_GET_PATH,
-                post(handle_get_config_version),
-            )
-            // .route(ROUTE_TOKEN_UPGRADE_PATH, post(handle_token_upgrade))
-            .layer(RequestBodyLimitLayer::new(parse_from_env(
+        routes
+            .layer(middleware::from_fn(client_ip_middleware))
+            .layer(RequestBodyLimitLayer::new(settings::get::<usize>(
                 "REQUEST_BODY_LIMIT",
-                2_000_000,
             )))
-            .layer(CorsLayer::permissive())
-            .with_state(state)
+            .layer(cors_layer())
+            .with_state(state.clone())
     };
 
-    // 启动服务器
-    let listener = {
-        use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-        let port = {
-            std::env::var(ENV_PORT)
+    // 启动服务器，收到关闭信号后各服务停止接受新连接并等待已有连接结束
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    #[cfg(unix)]
+    let mut unix_socket = None::<std::path::PathBuf>;
+    let servers = {
+        use common::{model::PeerAddr, systemd::Inherited};
+        use std::{
+            future::IntoFuture as _,
+            net::{IpAddr, Ipv4Addr, SocketAddr},
+            pin::Pin,
+        };
+
+        type Server = Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>>;
+
+        fn serve<L>(
+            listener: L,
+            app: Router,
+            mut shutdown: tokio::sync::watch::Receiver<bool>,
+        ) -> Server
+        where
+            L: axum::serve::Listener,
+            L::Addr: std::fmt::Debug,
+            PeerAddr:
+                for<'a> axum::extract::connect_info::Connected<axum::serve::IncomingStream<'a, L>>,
+        {
+            Box::pin(
+                axum::serve(
+                    listener,
+                    app.into_make_service_with_connect_info::<PeerAddr>(),
+                )
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown.wait_for(|&stop| stop).await;
+                })
+                .into_future(),
+            )
+        }
+
+        fn serve_inherited(
+            listener: Inherited,
+            app: Router,
+            shutdown: tokio::sync::watch::Receiver<bool>,
+        ) -> std::io::Result<Server> {
+            Ok(match listener {
+                Inherited::Tcp(listener) => {
+                    listener.set_nonblocking(true)?;
+                    serve(tokio::net::TcpListener::from_std(listener)?, app, shutdown)
+                }
+                #[cfg(unix)]
+                Inherited::Unix(listener) => {
+                    listener.set_nonblocking(true)?;
+                    serve(tokio::net::UnixListener::from_std(listener)?, app, shutdown)
+                }
+            })
+        }
+
+        let parse_port = |key| {
+            std::env::var(key)
                 .ok()
-                .and_then(|v| v.trim().parse().ok())
-                .unwrap_or(3000)
+                .and_then(|v| v.trim().parse::<u16>().ok())
         };
-        let addr = SocketAddr::new(
-            IpAddr::parse_ascii(parse_from_env(ENV_HOST, DEFAULT_LISTEN_HOST).as_bytes())
-                .unwrap_or_else(|e| {
-                    __cold_path!(); // IP解析失败是错误路径
-                    eprintln!("无法解析IP: {e}");
-                    IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))
-                }),
-            port,
-        );
-        println!("服务器运行在 {addr}");
-        tokio::net::TcpListener::bind(addr)
+        let port = parse_port(ENV_PORT).unwrap_or_else(|| settings::get::<usize>(ENV_PORT) as u16);
+        let ip =
+            IpAddr::parse_ascii(settings::get::<&str>(ENV_HOST).as_bytes()).unwrap_or_else(|e| {
+                __cold_path!(); // IP解析失败是错误路径
+                eprintln!("无法解析IP: {e}");
+                IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))
+            });
+
+        // 同时配置证书与私钥时启用 HTTPS，未单独指定 TLS_PORT 则主端口仅提供 HTTPS
+        let tls_cert = parse_from_env(ENV_TLS_CERT, EMPTY_STRING);
+        let tls_key = parse_from_env(ENV_TLS_KEY, EMPTY_STRING);
+        let tls_port = if tls_cert.is_empty() || tls_key.is_empty() {
+            None
+        } else {
+            Some(parse_port(ENV_TLS_PORT).unwrap_or(port))
+        };
+
+        // systemd 套接字激活：名为 admin 的套接字提供管理接口，其余代替 PORT 与 UNIX_SOCKET
+        let (admin_inherited, inherited): (Vec<_>, Vec<_>) = common::systemd::listen_fds()
+            .into_iter()
+            .partition(|(name, _)| name == "admin");
+
+        // 设置管理端口时主端口仅提供对话与模型接口
+        let admin_port = parse_port("ADMIN_PORT");
+        let (app, admin_app) = if admin_port.is_some() || !admin_inherited.is_empty() {
+            (
+                finish(api_routes.clone()),
+                Some(finish(api_routes.merge(admin_routes))),
+            )
+        } else {
+            (finish(api_routes.merge(admin_routes)), None)
+        };
+
+        let mut servers: Vec<Server> = Vec::with_capacity(3);
+        let bind_failed = |addr: &dyn std::fmt::Display, e: std::io::Error| -> ! {
+            __cold_path!();
+            eprintln!("无法绑定到地址 {addr}: {e}");
+            std::process::exit(1);
+        };
+
+        let plain_bound = !inherited.is_empty();
+        for (name, listener) in inherited {
+            println!("服务器运行在 systemd 套接字 {name}");
+            servers.push(
+                serve_inherited(listener, app.clone(), shutdown_rx.clone())
+                    .unwrap_or_else(|e| bind_failed(&name, e)),
+            );
+        }
+
+        #[cfg(unix)]
+        let plain_bound = plain_bound || {
+            let path = parse_from_env("UNIX_SOCKET", EMPTY_STRING);
+            if path.is_empty() {
+                false
+            } else {
+                use std::os::unix::fs::{FileTypeExt as _, PermissionsExt as _};
+
+                let path = std::path::PathBuf::from(&*path);
+                let display = path.display();
+                // 移除上次未正常退出时残留的套接字文件
+                if std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_socket()) {
+                    let _ = std::fs::remove_file(&path);
+                }
+                let listener = tokio::net::UnixListener::bind(&path)
+                    .unwrap_or_else(|e| bind_failed(&display, e));
+                let mode = u32::from_str_radix(&settings::get::<&str>("UNIX_SOCKET_MODE"), 8)
+                    .unwrap_or(0o660);
+                if let Err(e) =
+                    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+                {
+                    eprintln!("无法设置 {display} 的权限: {e}");
+                }
+                println!("服务器运行在 {display}");
+                servers.push(serve(listener, app.clone(), shutdown_rx.clone()));
+                unix_socket = Some(path);
+                true
+            }
+        };
+
+        if !plain_bound && tls_port != Some(port) {
+            let addr = SocketAddr::new(ip, port);
+            println!("服务器运行在 {addr}");
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .unwrap_or_else(|e| bind_failed(&addr, e));
+            servers.push(serve(listener, app.clone(), shutdown_rx.clone()));
+        }
+
+        if let Some(tls_port) = tls_port {
+            let addr = SocketAddr::new(ip, tls_port);
+            println!("HTTPS 服务器运行在 {addr}");
+            let listener = common::tls::TlsListener::bind(
+                addr,
+                tls_cert.into_owned(),
+                tls_key.into_owned(),
+                shutdown_rx.clone(),
+            )
             .await
             .unwrap_or_else(|e| {
                 __cold_path!();
-                eprintln!("无法绑定到地址 {addr}: {e}");
+                eprintln!("无法启动 HTTPS 服务于 {addr}: {e}");
                 std::process::exit(1);
-            })
+            });
+            servers.push(serve(listener, app, shutdown_rx.clone()));
+        }
+
+        if let Some(admin_app) = admin_app {
+            if admin_inherited.is_empty()
+                && let Some(admin_port) = admin_port
+            {
+                let admin_ip = IpAddr::parse_ascii(settings::get::<&str>("ADMIN_HOST").as_bytes())
+                    .unwrap_or_else(|e| {
+                        __cold_path!();
+                        eprintln!("无法解析管理端口IP: {e}");
+                        IpAddr::V4(Ipv4Addr::LOCALHOST)
+                    });
+                let addr = SocketAddr::new(admin_ip, admin_port);
+                println!("管理接口运行在 {addr}");
+                let listener = tokio::net::TcpListener::bind(addr)
+                    .await
+                    .unwrap_or_else(|e| bind_failed(&addr, e));
+                servers.push(serve(listener, admin_app, shutdown_rx));
+            } else {
+                for (name, listener) in admin_inherited {
+                    println!("管理接口运行在 systemd 套接字 {name}");
+                    servers.push(
+                        serve_inherited(listener, admin_app.clone(), shutdown_rx.clone())
+                            .unwrap_or_else(|e| bind_failed(&name, e)),
+                    );
+                }
+            }
+        }
+
+        servers
     };
     println!("当前版本: v{VERSION}");
     #[cfg(feature = "__preview")]
@@ -405,29 +685,68 @@ async fn main() {
     common::time::print_project_age();
     common::time::print_build_age();
 
+    // 监听已就绪
+    common::systemd::notify("READY=1");
+    common::systemd::spawn_watchdog();
+
     let start_time = app::lazy::get_start_time();
-    let server = axum::serve(listener, app);
-    tokio::select! {
-        result = server => {
-            if let Err(e) = result {
-                __cold_path!(); // 服务器错误是异常路径
-                eprintln!("服务器错误: {e}");
-            }
-        }
+    let shutdown_timeout =
+        std::time::Duration::from_secs(settings::get::<usize>("SHUTDOWN_TIMEOUT") as u64);
+    let server = futures::future::try_join_all(servers);
+    tokio::pin!(server);
+    let result = tokio::select! {
+        result = &mut server => result,
         _ = shutdown_signal => {
-            println!(
-                "运行时间: {}",
-                common::utils::duration_fmt::human(__unwrap!(
-                    app::model::DateTime::naive_now()
-                        .signed_duration_since(*start_time)
-                        .to_std()
-                ))
-                .format(common::utils::duration_fmt::DurationFormat::Random)
-                .language(common::utils::duration_fmt::Language::Random)
-            );
-            common::time::print_project_age();
-            common::time::print_build_age();
-            __println!("服务器已关闭");
+            common::systemd::notify("STOPPING=1");
+            let _ = shutdown_tx.send(true);
+            // 等待进行中的请求（包括流式响应）结束
+            tokio::time::timeout(shutdown_timeout, &mut server)
+                .await
+                .unwrap_or_else(|_| {
+                    eprintln!("等待请求结束超时({}秒)，强制关闭", shutdown_timeout.as_secs());
+                    Ok(Vec::new())
+                })
         }
+    };
+    if let Err(e) = result {
+        __cold_path!(); // 服务器错误是异常路径
+        eprintln!("服务器错误: {e}");
+    }
+
+    // 保存配置
+    if let Err(e) = AppConfig::save() {
+        __cold_path!(); // 配置保存失败是错误路径
+        eprintln!("保存配置失败: {e}");
+    } else {
+        __println!("配置已保存");
+    }
+
+    // 保存状态
+    if let Err(e) = state.save().await {
+        __cold_path!(); // 状态保存失败是错误路径
+        eprintln!("保存状态失败: {e}");
+    } else {
+        __println!("状态已保存");
+    }
+
+    app::lazy::log::flush_all_debug_logs().await;
+
+    #[cfg(unix)]
+    if let Some(path) = unix_socket {
+        let _ = std::fs::remove_file(path);
     }
+
+    println!(
+        "运行时间: {}",
+        common::utils::duration_fmt::human(__unwrap!(
+            app::model::DateTime::naive_now()
+                .signed_duration_since(*start_time)
+                .to_std()
+        ))
+        .format(common::utils::duration_fmt::DurationFormat::Random)
+        .language(common::utils::duration_fmt::Language::Random)
+    );
+    common::time::print_project_age();
+    common::time::print_build_age();
+    __println!("服务器已关闭");
 }
@@ -1,6 +1,6 @@
 #![allow(unsafe_op_in_unsafe_fn)]
 
-use crate::common::utils::StringBuilder;
+use crate::{app::settings, common::utils::StringBuilder};
 use dotenvy::LoadResult;
 use std::{env, process::exit};
 
@@ -20,6 +20,11 @@ crate::define_typed_constants! {
         TOKEN_OVERRIDING = "overriding",
         TOKEN_AND = "and",
         TOKEN_EXISTING = "existing",
+        TOKEN_TLS = "tls",
+        TOKEN_CERT = "cert",
+        TOKEN_KEY = "key",
+        TOKEN_CONFIG = "config",
+        TOKEN_SHOW = "show",
 
         // 解析器用到的字符串
         WORD_ME = "me",
@@ -36,6 +41,9 @@ crate::define_typed_constants! {
         // 环境变量名
         ENV_HOST = "HOST",
         ENV_PORT = "PORT",
+        ENV_TLS_CERT = "TLS_CERT",
+        ENV_TLS_KEY = "TLS_KEY",
+        ENV_TLS_PORT = "TLS_PORT",
 
         // 字符串分隔符
         COLON_SEPARATOR = ":",
@@ -52,6 +60,9 @@ crate::define_typed_constants! {
         INFO_OVERRIDDEN = ", overridden: ",
         INFO_CLOSING = ")",
         INFO_STARTING = "Starting server on ",
+        INFO_TLS_CERT = "Using TLS certificate ",
+        INFO_TLS_KEY = " with key ",
+        INFO_TLS_PORT = "Serving HTTPS on port ",
     }
 }
 
@@ -70,6 +81,11 @@ enum Token<'a> {
     Overriding,
     And,
     Existing,
+    Tls,
+    Cert,
+    Key,
+    Config,
+    Show,
     String(&'a str),
     Number(&'a str),
 }
@@ -85,6 +101,17 @@ pub enum Action<'a> {
         host: Option<&'a str>,
         port: Option<&'a str>, // 完美匹配 Token::Number(&'a str)
     },
+    Tls {
+        cert: &'a str,
+        key: &'a str,
+    },
+    TlsListen {
+        port: &'a str,
+    },
+    ImportConfig {
+        file: &'a str,
+    },
+    ShowConfig,
     Help,
 }
 
@@ -118,6 +145,11 @@ impl<'a> NaturalParser<'a> {
                     TOKEN_OVERRIDING => Token::Overriding,
                     TOKEN_AND => Token::And,
                     TOKEN_EXISTING => Token::Existing,
+                    TOKEN_TLS => Token::Tls,
+                    TOKEN_CERT => Token::Cert,
+                    TOKEN_KEY => Token::Key,
+                    TOKEN_CONFIG => Token::Config,
+                    TOKEN_SHOW => Token::Show,
                     _ => {
                         // parse 仅用于验证，Token 存储原始 word: &'a str
                         if word.parse::<u16>().is_ok() {
@@ -284,6 +316,46 @@ impl<'a> NaturalParser<'a> {
                     }
                     i += 3;
                 }
+                // tls cert <file> key <file>
+                [
+                    Token::Tls,
+                    Token::Cert,
+                    Token::String(cert),
+                    Token::Key,
+                    Token::String(key),
+                    ..,
+                ] => {
+                    actions.push(Action::Tls { cert, key });
+                    i += 5;
+                }
+                // tls on port <number>
+                [
+                    Token::Tls,
+                    Token::On,
+                    Token::Port,
+                    Token::Number(port_str),
+                    ..,
+                ] => {
+                    actions.push(Action::TlsListen { port: port_str });
+                    i += 4;
+                }
+
+                // import config from <file>
+                [
+                    Token::Import,
+                    Token::Config,
+                    Token::From,
+                    Token::String(file),
+                    ..,
+                ] => {
+                    actions.push(Action::ImportConfig { file });
+                    i += 4;
+                }
+                // show config
+                [Token::Show, Token::Config, ..] => {
+                    actions.push(Action::ShowConfig);
+                    i += 2;
+                }
 
                 // help me (使用 match guard)
                 [Token::Help, Token::String(s), ..] if *s == WORD_ME => {
@@ -332,6 +404,7 @@ fn load_env_file(filename: &str, override_existing: bool) -> LoadResult {
         dotenvy::from_filename(filename)
     } {
         Ok(result) => {
+            settings::record_env_file(filename, override_existing);
             // 字符串构建是热路径，保持高效
             let mut msg = StringBuilder::with_capacity(7)
                 .append(INFO_IMPORTING)
@@ -375,11 +448,30 @@ pub fn process_args(program_name: &str) {
             Err(s) => s.to_string_lossy().into_owned(),
         })
         .collect();
-    unsafe { __process_args_impl(program_name, &args) }
+    let mut config = ConfigOptions::default();
+    settings::capture_environment();
+    unsafe { __process_args_impl(program_name, &args, &mut config) }
+
+    // 配置文件在全部参数处理完成后加载，不覆盖已设置的环境变量
+    settings::init(config.file);
+    if config.show {
+        settings::print_effective();
+        exit(0);
+    }
+}
+
+#[derive(Default)]
+struct ConfigOptions<'a> {
+    file: Option<&'a str>,
+    show: bool,
 }
 
 #[inline(always)]
-unsafe fn __process_args_impl(program_name: &str, args: &[String]) {
+unsafe fn __process_args_impl<'a>(
+    program_name: &str,
+    args: &'a [String],
+    config: &mut ConfigOptions<'a>,
+) {
     if args.is_empty() {
         // 静默加载默认配置
         load_env_file(DEFAULT_ENV_FILE, false);
@@ -430,7 +522,37 @@ unsafe fn __process_args_impl(program_name: &str, args: &[String]) {
 
                 env::set_var(ENV_HOST, h);
                 env::set_var(ENV_PORT, p);
+                settings::pin(ENV_HOST);
+                settings::pin(ENV_PORT);
             }
+            Action::Tls { cert, key } => {
+                __println!(
+                    StringBuilder::with_capacity(4)
+                        .append(INFO_TLS_CERT)
+                        .append(cert)
+                        .append(INFO_TLS_KEY)
+                        .append(key)
+                        .build()
+                );
+
+                env::set_var(ENV_TLS_CERT, cert);
+                env::set_var(ENV_TLS_KEY, key);
+                settings::pin(ENV_TLS_CERT);
+                settings::pin(ENV_TLS_KEY);
+            }
+            Action::TlsListen { port } => {
+                __println!(
+                    StringBuilder::with_capacity(2)
+                        .append(INFO_TLS_PORT)
+                        .append(port)
+                        .build()
+                );
+
+                env::set_var(ENV_TLS_PORT, port);
+                settings::pin(ENV_TLS_PORT);
+            }
+            Action::ImportConfig { file } => config.file = Some(file),
+            Action::ShowConfig => config.show = true,
             // Help 路径，调用 cold 函数
             Action::Help => handle_help_and_exit(program_name),
         }
@@ -452,6 +574,10 @@ Hi! I'm {program}, and here's what I understand:
    {program} import env from prod.env and override existing  Override from specific file
    {program} import env from prod.env overriding existing    Alternative syntax
 
+⚙️  Config file stuff:
+   {program} import config from prod.toml                    Load settings from a TOML file (default: config.toml)
+   {program} show config                                     Print the effective settings (secrets masked) and exit
+
 🌐 Server stuff:  
    {program} listen on 127.0.0.1 port 8080                   Listen on specific IP and port
    {program} listen on localhost port 3000                   Listen on localhost with port
@@ -460,6 +586,10 @@ Hi! I'm {program}, and here's what I understand:
    {program} listen on 8080                                  Just the port (defaults to 0.0.0.0)
    {program} listen on localhost                             Just the host (defaults to port 3000)
 
+🔒 HTTPS stuff:
+   {program} tls cert server.crt key server.key              Serve HTTPS with PEM certificate and key
+   {program} tls on port 8443                                Also serve HTTPS on another port
+
 ❓ Getting help:  
    {program} help                                            Show this message
    {program} help me                                         Same thing, but more polite
@@ -469,6 +599,8 @@ Hi! I'm {program}, and here's what I understand:
 Examples:
    {program} import env from .env.prod and override existing listen on 10.0.0.1 port 8080
    {program} listen on localhost:5000 import env overriding existing
+   {program} listen on port 80 tls on port 443 tls cert fullchain.pem key privkey.pem
+   {program} import config from prod.toml import env show config
 "
     );
 }